@@ -12,7 +12,16 @@ use {
 
 /// Derive macro library to implement the `SplDiscriminate` trait
 /// on an enum or struct
-#[proc_macro_derive(SplDiscriminate, attributes(discriminator_hash_input))]
+///
+/// The optional `#[discriminator_length(N)]` attribute truncates the
+/// discriminator to the first `N` bytes of the hash, with `N` in [1, 8]
+///
+/// Also generates an inherent `const fn discriminator() -> [u8; N]` on the
+/// type, for use in constant expressions
+#[proc_macro_derive(
+    SplDiscriminate,
+    attributes(discriminator_hash_input, discriminator_length)
+)]
 pub fn spl_discriminator(input: TokenStream) -> TokenStream {
     parse_macro_input!(input as SplDiscriminateBuilder)
         .to_token_stream()
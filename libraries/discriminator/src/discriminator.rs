@@ -10,7 +10,8 @@ use {
 pub trait SplDiscriminate {
     /// The 8-byte discriminator as a `[u8; 8]`
     const SPL_DISCRIMINATOR: ArrayDiscriminator;
-    /// The 8-byte discriminator as a slice (`&[u8]`)
+    /// The discriminator as a slice (`&[u8]`), shorter than 8 bytes if the
+    /// type was derived with a `#[discriminator_length(...)]` attribute
     const SPL_DISCRIMINATOR_SLICE: &'static [u8] = Self::SPL_DISCRIMINATOR.as_slice();
 }
 
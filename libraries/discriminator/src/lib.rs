@@ -79,6 +79,24 @@ mod tests {
         data2: &'c [V],
     }
 
+    #[allow(dead_code)]
+    #[derive(SplDiscriminate)]
+    #[discriminator_hash_input("global:my_compact_instruction")]
+    #[discriminator_length(4)]
+    pub struct MyInstruction8 {
+        data: u8,
+    }
+
+    // the truncated length is known at compile time
+    const _: () = assert!(MyInstruction8::SPL_DISCRIMINATOR_SLICE.len() == 4);
+
+    // the generated accessor can be used to build static lookup tables
+    static FULL_DISCRIMINATORS: [[u8; 8]; 2] = [
+        MyInstruction1::discriminator(),
+        MyInstruction2::discriminator(),
+    ];
+    static COMPACT_DISCRIMINATORS: [[u8; 4]; 1] = [MyInstruction8::discriminator()];
+
     fn assert_discriminator<T: spl_discriminator::discriminator::SplDiscriminate>(
         hash_input: &str,
     ) {
@@ -126,6 +144,41 @@ mod tests {
             "global:my_instruction_with_multiple_generics_and_lifetime_and_where",
         );
     }
+
+    #[test]
+    fn test_discriminator_length() {
+        let full = build_discriminator("global:my_compact_instruction");
+        assert_eq!(
+            MyInstruction8::SPL_DISCRIMINATOR_SLICE,
+            &full.as_slice()[..4]
+        );
+        let mut padded = [0u8; 8];
+        padded[..4].copy_from_slice(&full.as_slice()[..4]);
+        assert_eq!(
+            MyInstruction8::SPL_DISCRIMINATOR,
+            ArrayDiscriminator::new(padded)
+        );
+    }
+
+    #[test]
+    fn test_const_discriminator_table() {
+        assert_eq!(
+            FULL_DISCRIMINATORS[0],
+            <[u8; 8]>::from(MyInstruction1::SPL_DISCRIMINATOR)
+        );
+        assert_eq!(
+            FULL_DISCRIMINATORS[1],
+            <[u8; 8]>::from(MyInstruction2::SPL_DISCRIMINATOR)
+        );
+        assert_eq!(
+            COMPACT_DISCRIMINATORS[0],
+            MyInstruction8::SPL_DISCRIMINATOR_SLICE
+        );
+        assert_eq!(
+            MyInstruction4::<u8>::discriminator(),
+            build_discriminator("global:my_instruction_with_one_generic").as_slice()
+        );
+    }
 }
 
 #[cfg(all(test, feature = "borsh"))]
@@ -9,4 +9,7 @@ pub enum SplDiscriminateError {
     /// Error parsing discriminator hash_input attribute
     #[error("Error parsing discriminator `hash_input` attribute")]
     HashInputAttributeParseError,
+    /// Error parsing discriminator length attribute
+    #[error("Error parsing discriminator `length` attribute, expected an integer in [1, 8]")]
+    LengthAttributeParseError,
 }
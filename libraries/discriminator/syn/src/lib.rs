@@ -7,8 +7,11 @@ mod error;
 pub mod parser;
 
 use {
-    crate::{error::SplDiscriminateError, parser::parse_hash_input},
-    proc_macro2::{Span, TokenStream},
+    crate::{
+        error::SplDiscriminateError,
+        parser::{parse_hash_input, parse_length, DEFAULT_DISCRIMINATOR_LENGTH},
+    },
+    proc_macro2::{Literal, Span, TokenStream},
     quote::{quote, ToTokens},
     sha2::{Digest, Sha256},
     syn::{parse::Parse, Generics, Ident, Item, ItemEnum, ItemStruct, LitByteStr, WhereClause},
@@ -25,6 +28,8 @@ pub struct SplDiscriminateBuilder {
     pub where_clause: Option<WhereClause>,
     /// The TLV hash_input
     pub hash_input: String,
+    /// The number of hash bytes used for the discriminator
+    pub length: usize,
 }
 
 impl TryFrom<ItemEnum> for SplDiscriminateBuilder {
@@ -35,11 +40,13 @@ impl TryFrom<ItemEnum> for SplDiscriminateBuilder {
         let where_clause = item_enum.generics.where_clause.clone();
         let generics = item_enum.generics;
         let hash_input = parse_hash_input(&item_enum.attrs)?;
+        let length = parse_length(&item_enum.attrs)?;
         Ok(Self {
             ident,
             generics,
             where_clause,
             hash_input,
+            length,
         })
     }
 }
@@ -52,11 +59,13 @@ impl TryFrom<ItemStruct> for SplDiscriminateBuilder {
         let where_clause = item_struct.generics.where_clause.clone();
         let generics = item_struct.generics;
         let hash_input = parse_hash_input(&item_struct.attrs)?;
+        let length = parse_length(&item_struct.attrs)?;
         Ok(Self {
             ident,
             generics,
             where_clause,
             hash_input,
+            length,
         })
     }
 }
@@ -89,20 +98,32 @@ impl From<&SplDiscriminateBuilder> for TokenStream {
         let ident = &builder.ident;
         let generics = &builder.generics;
         let where_clause = &builder.where_clause;
-        let bytes = get_discriminator_bytes(&builder.hash_input);
+        let (bytes, slice_bytes) = get_discriminator_bytes(&builder.hash_input, builder.length);
+        let length = Literal::usize_unsuffixed(builder.length);
         quote! {
             impl #generics spl_discriminator::discriminator::SplDiscriminate for #ident #generics #where_clause {
                 const SPL_DISCRIMINATOR: spl_discriminator::discriminator::ArrayDiscriminator
                     = spl_discriminator::discriminator::ArrayDiscriminator::new(*#bytes);
+                const SPL_DISCRIMINATOR_SLICE: &'static [u8] = #slice_bytes;
+            }
+
+            impl #generics #ident #generics #where_clause {
+                /// Returns the discriminator bytes, usable in const contexts
+                pub const fn discriminator() -> [u8; #length] {
+                    *#slice_bytes
+                }
             }
         }
     }
 }
 
-/// Returns the bytes for the TLV hash_input discriminator
-fn get_discriminator_bytes(hash_input: &str) -> LitByteStr {
-    LitByteStr::new(
-        &Sha256::digest(hash_input.as_bytes())[..8],
-        Span::call_site(),
+/// Returns the bytes for the TLV hash_input discriminator, truncated to
+/// `length` bytes: first zero-padded to the full array size, then as-is
+fn get_discriminator_bytes(hash_input: &str, length: usize) -> (LitByteStr, LitByteStr) {
+    let mut bytes = [0u8; DEFAULT_DISCRIMINATOR_LENGTH];
+    bytes[..length].copy_from_slice(&Sha256::digest(hash_input.as_bytes())[..length]);
+    (
+        LitByteStr::new(&bytes, Span::call_site()),
+        LitByteStr::new(&bytes[..length], Span::call_site()),
     )
 }
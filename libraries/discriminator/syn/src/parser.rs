@@ -1,15 +1,20 @@
 //! Parser for the `syn` crate to parse the
-//! `#[discriminator_hash_input("...")]` attribute
+//! `#[discriminator_hash_input("...")]` and `#[discriminator_length(...)]`
+//! attributes
 
 use {
     crate::error::SplDiscriminateError,
     syn::{
         parse::{Parse, ParseStream},
         token::Comma,
-        Attribute, LitStr,
+        Attribute, LitInt, LitStr,
     },
 };
 
+/// Discriminator length used when no `#[discriminator_length(...)]` attribute
+/// is provided
+pub const DEFAULT_DISCRIMINATOR_LENGTH: usize = 8;
+
 /// Struct used for `syn` parsing of the hash_input attribute
 /// #[discriminator_hash_input("...")]
 struct HashInputValueParser {
@@ -41,3 +46,24 @@ pub fn parse_hash_input(attrs: &[Attribute]) -> Result<String, SplDiscriminateEr
         None => Err(SplDiscriminateError::HashInputAttributeNotProvided),
     }
 }
+
+/// Parses the discriminator length from the `#[discriminator_length(...)]`
+/// attribute, defaulting to the full 8 bytes
+pub fn parse_length(attrs: &[Attribute]) -> Result<usize, SplDiscriminateError> {
+    match attrs
+        .iter()
+        .find(|a| a.path().is_ident("discriminator_length"))
+    {
+        Some(attr) => {
+            let length = attr
+                .parse_args::<LitInt>()
+                .and_then(|lit| lit.base10_parse::<usize>())
+                .map_err(|_| SplDiscriminateError::LengthAttributeParseError)?;
+            if length == 0 || length > DEFAULT_DISCRIMINATOR_LENGTH {
+                return Err(SplDiscriminateError::LengthAttributeParseError);
+            }
+            Ok(length)
+        }
+        None => Ok(DEFAULT_DISCRIMINATOR_LENGTH),
+    }
+}
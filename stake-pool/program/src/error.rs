@@ -164,6 +164,9 @@ pub enum StakePoolError {
     /// Missing required sysvar account
     #[error("Missing required sysvar account")]
     MissingRequiredSysvar,
+    /// Fee ratio provided at initialization has a zero denominator
+    #[error("ZeroFeeDenominator")]
+    ZeroFeeDenominator,
 }
 impl From<StakePoolError> for ProgramError {
     fn from(e: StakePoolError) -> Self {
@@ -65,7 +65,8 @@ pub enum StakePoolInstruction {
     ///      and staker / withdrawer authority set to pool withdraw authority.
     ///   6. `[]` Pool token mint. Must have zero supply, owned by withdraw
     ///      authority.
-    ///   7. `[]` Pool account to deposit the generated fee for manager.
+    ///   7. `[]` Pool account to deposit the generated fee for manager. Must
+    ///      be a token account for the pool token mint.
     ///   8. `[]` Token program id
     ///   9. `[]` (Optional) Deposit authority that must sign all deposits.
     ///      Defaults to the program address generated using
@@ -2646,3 +2647,134 @@ pub fn create_token_metadata(
             .unwrap(),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check_encoding(instruction: StakePoolInstruction, expected: &[u8]) {
+        let packed = borsh::to_vec(&instruction).unwrap();
+        assert_eq!(packed, expected);
+        let unpacked = StakePoolInstruction::try_from_slice(expected).unwrap();
+        assert_eq!(unpacked, instruction);
+    }
+
+    #[test]
+    fn initialize_encoding() {
+        let mut expected = vec![0];
+        // fee
+        expected.extend_from_slice(&100u64.to_le_bytes());
+        expected.extend_from_slice(&3u64.to_le_bytes());
+        // withdrawal_fee
+        expected.extend_from_slice(&1_000u64.to_le_bytes());
+        expected.extend_from_slice(&5u64.to_le_bytes());
+        // deposit_fee
+        expected.extend_from_slice(&10_000u64.to_le_bytes());
+        expected.extend_from_slice(&7u64.to_le_bytes());
+        // referral_fee
+        expected.push(50);
+        // max_validators
+        expected.extend_from_slice(&2_950u32.to_le_bytes());
+        check_encoding(
+            StakePoolInstruction::Initialize {
+                fee: Fee {
+                    denominator: 100,
+                    numerator: 3,
+                },
+                withdrawal_fee: Fee {
+                    denominator: 1_000,
+                    numerator: 5,
+                },
+                deposit_fee: Fee {
+                    denominator: 10_000,
+                    numerator: 7,
+                },
+                referral_fee: 50,
+                max_validators: 2_950,
+            },
+            &expected,
+        );
+    }
+
+    #[test]
+    fn set_fee_encoding() {
+        let mut expected = vec![12, 2];
+        expected.extend_from_slice(&100u64.to_le_bytes());
+        expected.extend_from_slice(&3u64.to_le_bytes());
+        check_encoding(
+            StakePoolInstruction::SetFee {
+                fee: FeeType::Epoch(Fee {
+                    denominator: 100,
+                    numerator: 3,
+                }),
+            },
+            &expected,
+        );
+    }
+
+    #[test]
+    fn withdraw_stake_encoding() {
+        let amount = 1_234_567_890_123u64;
+        let mut expected = vec![10];
+        expected.extend_from_slice(&amount.to_le_bytes());
+        check_encoding(StakePoolInstruction::WithdrawStake(amount), &expected);
+    }
+
+    #[test]
+    fn slippage_encoding() {
+        let pool_tokens = 5_000_000_000u64;
+        let lamports = 4_999_000_000u64;
+
+        let mut expected = vec![23];
+        expected.extend_from_slice(&pool_tokens.to_le_bytes());
+        check_encoding(
+            StakePoolInstruction::DepositStakeWithSlippage {
+                minimum_pool_tokens_out: pool_tokens,
+            },
+            &expected,
+        );
+
+        let mut expected = vec![24];
+        expected.extend_from_slice(&pool_tokens.to_le_bytes());
+        expected.extend_from_slice(&lamports.to_le_bytes());
+        check_encoding(
+            StakePoolInstruction::WithdrawStakeWithSlippage {
+                pool_tokens_in: pool_tokens,
+                minimum_lamports_out: lamports,
+            },
+            &expected,
+        );
+
+        let mut expected = vec![25];
+        expected.extend_from_slice(&lamports.to_le_bytes());
+        expected.extend_from_slice(&pool_tokens.to_le_bytes());
+        check_encoding(
+            StakePoolInstruction::DepositSolWithSlippage {
+                lamports_in: lamports,
+                minimum_pool_tokens_out: pool_tokens,
+            },
+            &expected,
+        );
+
+        let mut expected = vec![26];
+        expected.extend_from_slice(&pool_tokens.to_le_bytes());
+        expected.extend_from_slice(&lamports.to_le_bytes());
+        check_encoding(
+            StakePoolInstruction::WithdrawSolWithSlippage {
+                pool_tokens_in: pool_tokens,
+                minimum_lamports_out: lamports,
+            },
+            &expected,
+        );
+    }
+
+    #[test]
+    fn unit_variant_encoding() {
+        check_encoding(StakePoolInstruction::RemoveValidatorFromPool, &[2]);
+        check_encoding(StakePoolInstruction::UpdateStakePoolBalance, &[7]);
+        check_encoding(StakePoolInstruction::CleanupRemovedValidatorEntries, &[8]);
+        check_encoding(StakePoolInstruction::DepositStake, &[9]);
+        check_encoding(StakePoolInstruction::SetManager, &[11]);
+        check_encoding(StakePoolInstruction::SetStaker, &[13]);
+    }
+}
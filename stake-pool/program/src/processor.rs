@@ -724,12 +724,18 @@ impl Processor {
             return Err(ProgramError::AccountNotRentExempt);
         }
 
-        // Numerator should be smaller than or equal to denominator (fee <= 1)
-        if epoch_fee.numerator > epoch_fee.denominator
-            || withdrawal_fee.numerator > withdrawal_fee.denominator
-            || deposit_fee.numerator > deposit_fee.denominator
-            || referral_fee > 100u8
-        {
+        // Numerator should be smaller than or equal to denominator (fee <= 1),
+        // so a zero denominator is only accepted as "no fee"
+        for fee in [&epoch_fee, &withdrawal_fee, &deposit_fee] {
+            if !fee.is_valid() {
+                msg!("Invalid fee ratio provided: {:?}", fee);
+                if fee.denominator == 0 {
+                    return Err(StakePoolError::ZeroFeeDenominator.into());
+                }
+                return Err(StakePoolError::FeeTooHigh.into());
+            }
+        }
+        if referral_fee > 100u8 {
             return Err(StakePoolError::FeeTooHigh.into());
         }
 
@@ -769,10 +775,16 @@ impl Processor {
             let pool_mint = StateWithExtensions::<Mint>::unpack(&pool_mint_data)?;
 
             if pool_mint.base.supply != 0 {
+                msg!("Pool mint has a supply of {}", pool_mint.base.supply);
                 return Err(StakePoolError::NonZeroPoolTokenSupply.into());
             }
 
             if pool_mint.base.decimals != native_mint::DECIMALS {
+                msg!(
+                    "Pool mint has {} decimals, expected {}",
+                    pool_mint.base.decimals,
+                    native_mint::DECIMALS
+                );
                 return Err(StakePoolError::IncorrectMintDecimals.into());
             }
 
@@ -781,6 +793,11 @@ impl Processor {
                 .mint_authority
                 .contains(&withdraw_authority_key)
             {
+                msg!(
+                    "Pool mint has incorrect mint authority {:?}, should be {}",
+                    pool_mint.base.mint_authority,
+                    withdraw_authority_key
+                );
                 return Err(StakePoolError::WrongMintingAuthority.into());
             }
 
@@ -3371,7 +3388,7 @@ impl Processor {
             return Err(StakePoolError::StakeListAndPoolOutOfDate.into());
         }
 
-        fee.check_too_high()?;
+        fee.check_valid()?;
         stake_pool.update_fee(&fee)?;
         borsh::to_writer(&mut stake_pool_info.data.borrow_mut()[..], &stake_pool)?;
         Ok(())
@@ -3710,6 +3727,7 @@ impl PrintProgramError for StakePoolError {
             StakePoolError::IncorrectMintDecimals => msg!("Error: Provided mint does not have 9 decimals to match SOL"),
             StakePoolError::ReserveDepleted => msg!("Error: Pool reserve does not have enough lamports to fund rent-exempt reserve in split destination. Deposit more SOL in reserve, or pre-fund split destination with the rent-exempt reserve for a stake account."),
             StakePoolError::MissingRequiredSysvar => msg!("Missing required sysvar account"),
+            StakePoolError::ZeroFeeDenominator => msg!("Error: Fee ratio has a zero denominator"),
         }
     }
 }
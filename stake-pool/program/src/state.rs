@@ -932,6 +932,14 @@ pub struct Fee {
 }
 
 impl Fee {
+    /// Checks that the fee ratio is well-formed, with a numerator no larger
+    /// than the denominator (fee <= 1). A zero denominator is only valid with
+    /// a zero numerator, meaning no fee
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.numerator <= self.denominator
+    }
+
     /// Applies the Fee's rates to a given amount, `amt`
     /// returning the amount to be subtracted from it as fees
     /// (0 if denominator is 0 or amt is 0),
@@ -1037,6 +1045,28 @@ impl FeeType {
         Ok(())
     }
 
+    /// Checks that the provided fee is well-formed, returning an error if a
+    /// non-zero fee ratio has a zero denominator or any fee is above 100%
+    pub fn check_valid(&self) -> Result<(), StakePoolError> {
+        match self {
+            Self::Epoch(fee)
+            | Self::StakeWithdrawal(fee)
+            | Self::SolWithdrawal(fee)
+            | Self::SolDeposit(fee)
+            | Self::StakeDeposit(fee)
+                if fee.numerator > 0 && fee.denominator == 0 =>
+            {
+                msg!(
+                    "Fee denominator cannot be zero for a non-zero fee: {:?}",
+                    self
+                );
+                return Err(StakePoolError::ZeroFeeDenominator);
+            }
+            _ => {}
+        }
+        self.check_too_high()
+    }
+
     /// Returns if the contained fee can only be updated earliest on the next
     /// epoch
     #[inline]
@@ -1303,6 +1333,79 @@ mod test {
         assert_eq!(fee, rewards);
     }
 
+    #[test]
+    fn fee_validity() {
+        assert!(Fee {
+            numerator: 0,
+            denominator: 1,
+        }
+        .is_valid());
+        assert!(Fee {
+            numerator: 10,
+            denominator: 10,
+        }
+        .is_valid());
+        // zero denominator, only as "no fee"
+        assert!(Fee {
+            numerator: 0,
+            denominator: 0,
+        }
+        .is_valid());
+        assert!(!Fee {
+            numerator: 1,
+            denominator: 0,
+        }
+        .is_valid());
+        // more than 100%
+        assert!(!Fee {
+            numerator: 11,
+            denominator: 10,
+        }
+        .is_valid());
+    }
+
+    #[test]
+    fn fee_type_validity() {
+        let fee = Fee {
+            numerator: 1,
+            denominator: 10,
+        };
+        assert_eq!(FeeType::Epoch(fee).check_valid(), Ok(()));
+        assert_eq!(FeeType::SolReferral(100).check_valid(), Ok(()));
+        assert_eq!(
+            FeeType::SolReferral(101).check_valid(),
+            Err(StakePoolError::FeeTooHigh)
+        );
+        assert_eq!(
+            FeeType::StakeDeposit(Fee {
+                numerator: 11,
+                denominator: 10,
+            })
+            .check_valid(),
+            Err(StakePoolError::FeeTooHigh)
+        );
+        // a zero fee can be encoded with a zero denominator
+        assert_eq!(FeeType::StakeDeposit(Fee::default()).check_valid(), Ok(()));
+        for numerator in [1, 11] {
+            let fee = Fee {
+                numerator,
+                denominator: 0,
+            };
+            for fee_type in [
+                FeeType::Epoch(fee),
+                FeeType::StakeWithdrawal(fee),
+                FeeType::SolWithdrawal(fee),
+                FeeType::SolDeposit(fee),
+                FeeType::StakeDeposit(fee),
+            ] {
+                assert_eq!(
+                    fee_type.check_valid(),
+                    Err(StakePoolError::ZeroFeeDenominator)
+                );
+            }
+        }
+    }
+
     #[test]
     fn approximate_apr_calculation() {
         // 8% / year means roughly .044% / epoch
@@ -415,6 +415,85 @@ async fn fail_with_wrong_mint_authority() {
     }
 }
 
+#[tokio::test]
+async fn fail_with_wrong_pool_mint_authority() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let stake_pool_accounts = StakePoolAccounts::default();
+
+    // pool mint controlled by someone other than the withdraw authority
+    create_mint(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &stake_pool_accounts.token_program_id,
+        &stake_pool_accounts.pool_mint,
+        &stake_pool_accounts.manager.pubkey(),
+        stake_pool_accounts.pool_decimals,
+        &[],
+    )
+    .await
+    .unwrap();
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &stake_pool_accounts.token_program_id,
+        &stake_pool_accounts.pool_fee_account,
+        &stake_pool_accounts.pool_mint.pubkey(),
+        &stake_pool_accounts.manager,
+        &[],
+    )
+    .await
+    .unwrap();
+    create_independent_stake_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &stake_pool_accounts.reserve_stake,
+        &stake::state::Authorized {
+            staker: stake_pool_accounts.withdraw_authority,
+            withdrawer: stake_pool_accounts.withdraw_authority,
+        },
+        &stake::state::Lockup::default(),
+        MINIMUM_RESERVE_LAMPORTS,
+    )
+    .await;
+
+    let transaction_error = create_stake_pool(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &stake_pool_accounts.stake_pool,
+        &stake_pool_accounts.validator_list,
+        &stake_pool_accounts.reserve_stake.pubkey(),
+        &stake_pool_accounts.token_program_id,
+        &stake_pool_accounts.pool_mint.pubkey(),
+        &stake_pool_accounts.pool_fee_account.pubkey(),
+        &stake_pool_accounts.manager,
+        &stake_pool_accounts.staker.pubkey(),
+        &stake_pool_accounts.withdraw_authority,
+        &None,
+        &stake_pool_accounts.epoch_fee,
+        &stake_pool_accounts.withdrawal_fee,
+        &stake_pool_accounts.deposit_fee,
+        stake_pool_accounts.referral_fee,
+        &stake_pool_accounts.sol_deposit_fee,
+        stake_pool_accounts.sol_referral_fee,
+        stake_pool_accounts.max_validators,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+
+    assert_eq!(
+        transaction_error,
+        TransactionError::InstructionError(
+            2,
+            InstructionError::Custom(error::StakePoolError::WrongMintingAuthority as u32),
+        )
+    );
+}
+
 #[tokio::test]
 async fn fail_with_freeze_authority() {
     let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
@@ -11,7 +11,9 @@ use {
         keypair::signer_from_path,
     },
     solana_client::rpc_client::RpcClient,
-    solana_program::{native_token::lamports_to_sol, program_pack::Pack, pubkey::Pubkey},
+    solana_program::{
+        instruction::Instruction, native_token::lamports_to_sol, program_pack::Pack, pubkey::Pubkey,
+    },
     solana_sdk::{
         commitment_config::CommitmentConfig,
         message::Message,
@@ -26,9 +28,12 @@ use {
     },
     spl_token_lending::{
         self,
-        instruction::{init_lending_market, init_reserve},
-        math::WAD,
-        state::{LendingMarket, Reserve, ReserveConfig, ReserveFees},
+        instruction::{
+            deposit_obligation_collateral, init_lending_market, init_obligation, init_reserve,
+            refresh_reserve,
+        },
+        math::{Rate, WAD},
+        state::{LendingMarket, Obligation, Reserve, ReserveConfig, ReserveFees},
     },
     std::{borrow::Borrow, process::exit, str::FromStr},
     system_instruction::create_account,
@@ -138,6 +143,16 @@ fn main() {
                         .required(true)
                         .default_value("USD")
                         .help("Currency market prices are quoted in"),
+                )
+                .arg(
+                    Arg::with_name("min_borrow_value")
+                        .long("min-borrow-value")
+                        .validator(is_parsable::<u64>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("0")
+                        .help("Minimum borrowed value of an obligation, in the quote currency"),
                 ),
         )
         .subcommand(
@@ -309,6 +324,109 @@ fn main() {
                         .default_value("20")
                         .help("Amount of fee going to host account: [0, 100]"),
                 )
+                .arg(
+                    Arg::with_name("emode_category")
+                        .long("emode-category")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("0")
+                        .help("Correlated asset category for elevated loan to value, 0 for none"),
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("create-obligation")
+                .about("Create a new obligation in a lending market")
+                .arg(
+                    Arg::with_name("obligation_owner")
+                        .long("obligation-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Owner of the new obligation"),
+                )
+                .arg(
+                    Arg::with_name("lending_market")
+                        .long("market")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Lending market address"),
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("deposit-collateral")
+                .about("Deposit reserve collateral into an obligation")
+                .arg(
+                    Arg::with_name("obligation_owner")
+                        .long("obligation-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Owner of the obligation"),
+                )
+                .arg(
+                    Arg::with_name("source_collateral_owner")
+                        .long("source-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Owner of the SPL Token account to deposit collateral from"),
+                )
+                .arg(
+                    Arg::with_name("obligation")
+                        .long("obligation")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Obligation address"),
+                )
+                .arg(
+                    Arg::with_name("reserve")
+                        .long("reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Reserve whose collateral is deposited"),
+                )
+                .arg(
+                    Arg::with_name("source_collateral")
+                        .long("source")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("SPL Token account to deposit collateral from"),
+                )
+                .arg(
+                    Arg::with_name("collateral_amount")
+                        .long("amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Amount of collateral to deposit"),
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("reserve-info")
+                .about("Display the current rates and amounts of a reserve")
+                .arg(
+                    Arg::with_name("reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Reserve address"),
+                )
         )
         .get_matches();
 
@@ -353,10 +471,12 @@ fn main() {
             let lending_market_owner = pubkey_of(arg_matches, "lending_market_owner").unwrap();
             let quote_currency = quote_currency_of(arg_matches, "quote_currency").unwrap();
             let oracle_program_id = pubkey_of(arg_matches, "oracle_program_id").unwrap();
+            let min_borrow_value = value_of(arg_matches, "min_borrow_value").unwrap();
             command_create_lending_market(
                 &config,
                 lending_market_owner,
                 quote_currency,
+                min_borrow_value,
                 oracle_program_id,
             )
         }
@@ -381,6 +501,7 @@ fn main() {
             let borrow_fee = value_of::<f64>(arg_matches, "borrow_fee").unwrap();
             let flash_loan_fee = value_of::<f64>(arg_matches, "flash_loan_fee").unwrap();
             let host_fee_percentage = value_of(arg_matches, "host_fee_percentage").unwrap();
+            let emode_category = value_of(arg_matches, "emode_category").unwrap();
 
             let borrow_fee_wad = (borrow_fee * WAD as f64) as u64;
             let flash_loan_fee_wad = (flash_loan_fee * WAD as f64) as u64;
@@ -401,6 +522,7 @@ fn main() {
                         flash_loan_fee_wad,
                         host_fee_percentage,
                     },
+                    emode_category,
                 },
                 source_liquidity_pubkey,
                 source_liquidity_owner_keypair,
@@ -410,6 +532,33 @@ fn main() {
                 pyth_price_pubkey,
             )
         }
+        ("create-obligation", Some(arg_matches)) => {
+            let obligation_owner_keypair = keypair_of(arg_matches, "obligation_owner").unwrap();
+            let lending_market_pubkey = pubkey_of(arg_matches, "lending_market").unwrap();
+            command_create_obligation(&config, lending_market_pubkey, obligation_owner_keypair)
+        }
+        ("deposit-collateral", Some(arg_matches)) => {
+            let obligation_owner_keypair = keypair_of(arg_matches, "obligation_owner").unwrap();
+            let source_collateral_owner_keypair =
+                keypair_of(arg_matches, "source_collateral_owner").unwrap();
+            let obligation_pubkey = pubkey_of(arg_matches, "obligation").unwrap();
+            let reserve_pubkey = pubkey_of(arg_matches, "reserve").unwrap();
+            let source_collateral_pubkey = pubkey_of(arg_matches, "source_collateral").unwrap();
+            let ui_amount = value_of(arg_matches, "collateral_amount").unwrap();
+            command_deposit_collateral(
+                &config,
+                ui_amount,
+                source_collateral_pubkey,
+                source_collateral_owner_keypair,
+                reserve_pubkey,
+                obligation_pubkey,
+                obligation_owner_keypair,
+            )
+        }
+        ("reserve-info", Some(arg_matches)) => {
+            let reserve_pubkey = pubkey_of(arg_matches, "reserve").unwrap();
+            command_reserve_info(&config, reserve_pubkey)
+        }
         _ => unreachable!(),
     }
     .map_err(|err| {
@@ -424,6 +573,7 @@ fn command_create_lending_market(
     config: &Config,
     lending_market_owner: Pubkey,
     quote_currency: [u8; 32],
+    min_borrow_value: u64,
     oracle_program_id: Pubkey,
 ) -> CommandResult {
     let lending_market_keypair = Keypair::new();
@@ -452,6 +602,7 @@ fn command_create_lending_market(
                 config.lending_program_id,
                 lending_market_owner,
                 quote_currency,
+                min_borrow_value,
                 lending_market_keypair.pubkey(),
                 oracle_program_id,
             ),
@@ -691,8 +842,238 @@ fn command_add_reserve(
     Ok(())
 }
 
+fn command_create_obligation(
+    config: &Config,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_keypair: Keypair,
+) -> CommandResult {
+    let obligation_keypair = Keypair::new();
+    println!("Creating obligation {}", obligation_keypair.pubkey());
+
+    let obligation_balance = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(Obligation::LEN)?;
+
+    let recent_blockhash = config.rpc_client.get_latest_blockhash()?;
+    let message = Message::new_with_blockhash(
+        &create_obligation_instructions(
+            config.lending_program_id,
+            config.fee_payer.pubkey(),
+            obligation_balance,
+            obligation_keypair.pubkey(),
+            lending_market_pubkey,
+            obligation_owner_keypair.pubkey(),
+        ),
+        Some(&config.fee_payer.pubkey()),
+        &recent_blockhash,
+    );
+
+    check_fee_payer_balance(
+        config,
+        obligation_balance + config.rpc_client.get_fee_for_message(&message)?,
+    )?;
+    let transaction = Transaction::new(
+        &vec![
+            config.fee_payer.as_ref(),
+            &obligation_keypair,
+            &obligation_owner_keypair,
+        ],
+        message,
+        recent_blockhash,
+    );
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
+fn command_deposit_collateral(
+    config: &Config,
+    ui_amount: f64,
+    source_collateral_pubkey: Pubkey,
+    source_collateral_owner_keypair: Keypair,
+    reserve_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    obligation_owner_keypair: Keypair,
+) -> CommandResult {
+    let source_collateral_account = config.rpc_client.get_account(&source_collateral_pubkey)?;
+    let source_collateral = Token::unpack_from_slice(source_collateral_account.data.borrow())?;
+
+    let source_collateral_mint_account = config.rpc_client.get_account(&source_collateral.mint)?;
+    let source_collateral_mint =
+        Mint::unpack_from_slice(source_collateral_mint_account.data.borrow())?;
+    let collateral_amount = ui_amount_to_amount(ui_amount, source_collateral_mint.decimals);
+
+    let reserve_account = config.rpc_client.get_account(&reserve_pubkey)?;
+    let reserve = Reserve::unpack_from_slice(reserve_account.data.borrow())?;
+
+    let user_transfer_authority_keypair = Keypair::new();
+    println!(
+        "Depositing {} collateral into obligation {}",
+        collateral_amount, obligation_pubkey
+    );
+    if config.verbose {
+        println!(
+            "Adding user transfer authority {}",
+            user_transfer_authority_keypair.pubkey()
+        );
+    }
+
+    let recent_blockhash = config.rpc_client.get_latest_blockhash()?;
+    let message = Message::new_with_blockhash(
+        &deposit_collateral_instructions(
+            config.lending_program_id,
+            collateral_amount,
+            source_collateral_pubkey,
+            source_collateral_owner_keypair.pubkey(),
+            reserve_pubkey,
+            &reserve,
+            obligation_pubkey,
+            obligation_owner_keypair.pubkey(),
+            user_transfer_authority_keypair.pubkey(),
+        ),
+        Some(&config.fee_payer.pubkey()),
+        &recent_blockhash,
+    );
+
+    check_fee_payer_balance(config, config.rpc_client.get_fee_for_message(&message)?)?;
+    let transaction = Transaction::new(
+        &vec![
+            config.fee_payer.as_ref(),
+            &source_collateral_owner_keypair,
+            &obligation_owner_keypair,
+            &user_transfer_authority_keypair,
+        ],
+        message,
+        recent_blockhash,
+    );
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
+fn command_reserve_info(config: &Config, reserve_pubkey: Pubkey) -> CommandResult {
+    let reserve_account = config.rpc_client.get_account(&reserve_pubkey)?;
+    if reserve_account.owner != config.lending_program_id {
+        return Err(format!(
+            "Reserve {} is not owned by the lending program {}",
+            reserve_pubkey, config.lending_program_id
+        )
+        .into());
+    }
+    let mut reserve = Reserve::unpack_from_slice(reserve_account.data.borrow())?;
+
+    // accrue interest up to the current slot, as RefreshReserve would, so
+    // the amounts shown are live
+    let current_slot = config.rpc_client.get_slot()?;
+    reserve.accrue_interest(current_slot)?;
+
+    println!("Reserve {}", reserve_pubkey);
+    println!("{}", reserve_info(&reserve)?);
+    if config.verbose {
+        println!("{}", reserve.config.to_display());
+    }
+    Ok(())
+}
+
 // HELPERS
 
+/// Instructions allocating and initializing a new obligation
+fn create_obligation_instructions(
+    lending_program_id: Pubkey,
+    fee_payer_pubkey: Pubkey,
+    obligation_balance: u64,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+) -> Vec<Instruction> {
+    vec![
+        // Account for the obligation
+        create_account(
+            &fee_payer_pubkey,
+            &obligation_pubkey,
+            obligation_balance,
+            Obligation::LEN as u64,
+            &lending_program_id,
+        ),
+        // Initialize obligation account
+        init_obligation(
+            lending_program_id,
+            obligation_pubkey,
+            lending_market_pubkey,
+            obligation_owner_pubkey,
+        ),
+    ]
+}
+
+/// Instructions depositing collateral into an obligation, refreshing the
+/// reserve first as the deposit requires
+#[allow(clippy::too_many_arguments)]
+fn deposit_collateral_instructions(
+    lending_program_id: Pubkey,
+    collateral_amount: u64,
+    source_collateral_pubkey: Pubkey,
+    source_collateral_owner_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve: &Reserve,
+    obligation_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Vec<Instruction> {
+    vec![
+        approve(
+            &spl_token::id(),
+            &source_collateral_pubkey,
+            &user_transfer_authority_pubkey,
+            &source_collateral_owner_pubkey,
+            &[],
+            collateral_amount,
+        )
+        .unwrap(),
+        refresh_reserve(
+            lending_program_id,
+            reserve_pubkey,
+            reserve.liquidity.oracle_pubkey,
+        ),
+        deposit_obligation_collateral(
+            lending_program_id,
+            collateral_amount,
+            source_collateral_pubkey,
+            reserve.collateral.supply_pubkey,
+            reserve_pubkey,
+            obligation_pubkey,
+            reserve.lending_market,
+            obligation_owner_pubkey,
+            user_transfer_authority_pubkey,
+        ),
+        revoke(
+            &spl_token::id(),
+            &source_collateral_pubkey,
+            &source_collateral_owner_pubkey,
+            &[],
+        )
+        .unwrap(),
+    ]
+}
+
+/// Summarize the current rates and amounts of a reserve, one per line
+fn reserve_info(reserve: &Reserve) -> Result<String, Error> {
+    Ok([
+        format!(
+            "Utilization rate: {}",
+            reserve.liquidity.utilization_rate()?
+        ),
+        format!("Current borrow rate: {}", reserve.current_borrow_rate()?),
+        format!("Available amount: {}", reserve.liquidity.available_amount),
+        format!(
+            "Borrowed amount: {}",
+            reserve.liquidity.borrowed_amount_wads
+        ),
+        format!(
+            "Collateral exchange rate: {}",
+            Rate::from(reserve.collateral_exchange_rate()?)
+        ),
+    ]
+    .join("\n"))
+}
+
 fn check_fee_payer_balance(config: &Config, required_balance: u64) -> Result<(), Error> {
     let balance = config.rpc_client.get_balance(&config.fee_payer.pubkey())?;
     if balance < required_balance {
@@ -739,3 +1120,154 @@ fn quote_currency_of(matches: &ArgMatches<'_>, name: &str) -> Option<[u8; 32]> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::hash::Hash,
+        spl_token_lending::{
+            math::Decimal,
+            state::{ReserveCollateral, ReserveLiquidity},
+        },
+    };
+
+    #[test]
+    fn create_obligation_transaction() {
+        let lending_program_id = spl_token_lending::id();
+        let fee_payer = Keypair::new();
+        let obligation = Keypair::new();
+        let obligation_owner = Keypair::new();
+        let lending_market_pubkey = Pubkey::new_unique();
+
+        let message = Message::new(
+            &create_obligation_instructions(
+                lending_program_id,
+                fee_payer.pubkey(),
+                1_000_000,
+                obligation.pubkey(),
+                lending_market_pubkey,
+                obligation_owner.pubkey(),
+            ),
+            Some(&fee_payer.pubkey()),
+        );
+        assert_eq!(message.header.num_required_signatures, 3);
+        let account_keys = &message.account_keys;
+        assert!(account_keys.contains(&lending_market_pubkey));
+        assert_eq!(
+            account_keys[message.instructions[1].program_id_index as usize],
+            lending_program_id
+        );
+
+        // all signers are known, so the transaction can be assembled and
+        // signed without a cluster, as for a dry run
+        let transaction = Transaction::new(
+            &vec![&fee_payer, &obligation, &obligation_owner],
+            message,
+            Hash::default(),
+        );
+        assert!(transaction.verify().is_ok());
+    }
+
+    #[test]
+    fn deposit_collateral_transaction() {
+        let lending_program_id = spl_token_lending::id();
+        let fee_payer = Keypair::new();
+        let source_collateral_owner = Keypair::new();
+        let obligation_owner = Keypair::new();
+        let user_transfer_authority = Keypair::new();
+        let source_collateral_pubkey = Pubkey::new_unique();
+        let reserve_pubkey = Pubkey::new_unique();
+        let obligation_pubkey = Pubkey::new_unique();
+        let reserve = Reserve {
+            lending_market: Pubkey::new_unique(),
+            liquidity: ReserveLiquidity {
+                oracle_pubkey: Pubkey::new_unique(),
+                ..ReserveLiquidity::default()
+            },
+            collateral: ReserveCollateral {
+                supply_pubkey: Pubkey::new_unique(),
+                ..ReserveCollateral::default()
+            },
+            ..Reserve::default()
+        };
+
+        let instructions = deposit_collateral_instructions(
+            lending_program_id,
+            100,
+            source_collateral_pubkey,
+            source_collateral_owner.pubkey(),
+            reserve_pubkey,
+            &reserve,
+            obligation_pubkey,
+            obligation_owner.pubkey(),
+            user_transfer_authority.pubkey(),
+        );
+        assert_eq!(
+            instructions[1],
+            refresh_reserve(
+                lending_program_id,
+                reserve_pubkey,
+                reserve.liquidity.oracle_pubkey
+            )
+        );
+        let deposit = &instructions[2];
+        assert_eq!(deposit.accounts[1].pubkey, reserve.collateral.supply_pubkey);
+        assert_eq!(deposit.accounts[3].pubkey, obligation_pubkey);
+        assert_eq!(deposit.accounts[4].pubkey, reserve.lending_market);
+
+        let message = Message::new(&instructions, Some(&fee_payer.pubkey()));
+        assert_eq!(message.header.num_required_signatures, 4);
+        let transaction = Transaction::new(
+            &vec![
+                &fee_payer,
+                &source_collateral_owner,
+                &obligation_owner,
+                &user_transfer_authority,
+            ],
+            message,
+            Hash::default(),
+        );
+        assert!(transaction.verify().is_ok());
+    }
+
+    #[test]
+    fn reserve_info_from_account_data() {
+        let reserve = Reserve {
+            version: 1,
+            liquidity: ReserveLiquidity {
+                available_amount: 750,
+                borrowed_amount_wads: Decimal::from(250u64),
+                cumulative_borrow_rate_wads: Decimal::one(),
+                ..ReserveLiquidity::default()
+            },
+            collateral: ReserveCollateral {
+                mint_total_supply: 500,
+                ..ReserveCollateral::default()
+            },
+            config: ReserveConfig {
+                optimal_utilization_rate: 50,
+                min_borrow_rate: 0,
+                optimal_borrow_rate: 4,
+                max_borrow_rate: 30,
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        };
+        let mut data = vec![0; Reserve::LEN];
+        reserve.pack_into_slice(&mut data);
+
+        let unpacked = Reserve::unpack_from_slice(&data).unwrap();
+        assert_eq!(
+            reserve_info(&unpacked).unwrap(),
+            [
+                "Utilization rate: 0.250000000000000000",
+                "Current borrow rate: 0.020000000000000000",
+                "Available amount: 750",
+                "Borrowed amount: 250.000000000000000000",
+                "Collateral exchange rate: 0.500000000000000000",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -168,6 +168,12 @@ pub enum LendingError {
     /// Lending instruction exceeds desired slippage limit
     #[error("Amount smaller than desired slippage limit")]
     ExceededSlippage,
+    /// Borrowed value too small
+    #[error("Obligation borrowed value would be below the market minimum")]
+    BorrowTooSmallValue,
+    /// Borrow reserve is outside the e-mode category of the obligation borrows
+    #[error("Borrow reserve e-mode category does not match the obligation borrows")]
+    EmodeCategoryMismatch,
 }
 
 impl From<LendingError> for ProgramError {
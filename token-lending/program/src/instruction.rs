@@ -35,6 +35,9 @@ pub enum LendingInstruction {
         /// (`*b"USD\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0"
         /// `) or SPL token mint pubkey
         quote_currency: [u8; 32],
+        /// Minimum total borrowed value of an obligation after a borrow, in
+        /// whole units of the quote currency - 0 for no minimum
+        min_borrow_value: u64,
     },
 
     // 1
@@ -64,9 +67,11 @@ pub enum LendingInstruction {
     ///   5. `[writable]` Reserve liquidity fee receiver - uninitialized.
     ///   6. `[writable]` Reserve collateral SPL Token mint - uninitialized.
     ///   7. `[writable]` Reserve collateral token supply - uninitialized.
-    ///   8. `[]` Pyth product account.
+    ///   8. `[]` Pyth product account - the system program id for a reserve
+    ///      without an oracle, priced by UpdateReservePrice.
     ///   9. `[]` Pyth price account. This will be used as the reserve liquidity
-    ///      oracle account.
+    ///      oracle account - the system program id for a reserve without an
+    ///      oracle.
     ///   10. `[]` Lending market account.
     ///   11. `[]` Derived lending market authority.
     ///   12. `[signer]` Lending market owner.
@@ -162,6 +167,8 @@ pub enum LendingInstruction {
     ///      order.
     ///   3. .. `[]` Liquidity borrow reserve accounts - refreshed, all, in
     ///      order.
+    ///   4. `[]` Lending market account - optional, applies its e-mode loan
+    ///      to value ratios when given.
     RefreshObligation,
 
     // 8
@@ -229,6 +236,9 @@ pub enum LendingInstruction {
     ///   8. `[]` Clock sysvar.
     ///   9. `[]` Token program id.
     ///   10. `[optional, writable]` Host fee receiver account.
+    ///   11. `[optional]` Reserve of the obligation's first borrow - required
+    ///       to borrow from a new reserve when the obligation has borrows and
+    ///       the lending market has e-mode loan to value ratios.
     BorrowObligationLiquidity {
         /// Amount of liquidity to borrow - u64::MAX for 100% of borrowing power
         liquidity_amount: u64,
@@ -283,6 +293,8 @@ pub enum LendingInstruction {
         /// Amount of liquidity to repay - u64::MAX for up to 100% of borrowed
         /// amount
         liquidity_amount: u64,
+        /// Minimum amount of collateral to receive, 0 for no limit
+        minimum_collateral_amount: u64,
     },
 
     // 13
@@ -342,6 +354,39 @@ pub enum LendingInstruction {
         /// Reserve configuration updated values
         new_config: ReserveConfig,
     },
+
+    // 15
+    /// Set the loan to value ratio used for collateral in an e-mode category
+    /// when an obligation only borrows from reserves in the same category.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` Lending market account.
+    ///   1. `[signer]` Lending market owner.
+    SetEmodeLoanToValueRatio {
+        /// E-mode category, from 1 to `MAX_EMODE_CATEGORIES`
+        category: u8,
+        /// Loan to value ratio for the category, as a percentage - 0 to
+        /// remove the override
+        loan_to_value_ratio: u8,
+    },
+
+    // 16
+    /// Set the market price of a reserve which has no oracle, for testing or
+    /// bootstrapping a market. The reserve is marked stale and must be
+    /// refreshed before use. A reserve without an oracle can't be refreshed
+    /// until its price is set.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` Reserve account
+    ///   1. `[]` Lending market account
+    ///   2. `[signer]` Lending market owner
+    UpdateReservePrice {
+        /// Market price of the reserve liquidity in quote currency, as a
+        /// `Decimal` scaled value - must be non-zero
+        price_wads: u128,
+    },
 }
 
 impl LendingInstruction {
@@ -354,10 +399,12 @@ impl LendingInstruction {
         Ok(match tag {
             0 => {
                 let (owner, rest) = Self::unpack_pubkey(rest)?;
-                let (quote_currency, _rest) = Self::unpack_bytes32(rest)?;
+                let (quote_currency, rest) = Self::unpack_bytes32(rest)?;
+                let (min_borrow_value, _rest) = Self::unpack_u64(rest).unwrap_or((0, &[]));
                 Self::InitLendingMarket {
                     owner,
                     quote_currency: *quote_currency,
+                    min_borrow_value,
                 }
             }
             1 => {
@@ -404,8 +451,12 @@ impl LendingInstruction {
                 Self::RepayObligationLiquidity { liquidity_amount }
             }
             12 => {
-                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::LiquidateObligation { liquidity_amount }
+                let (liquidity_amount, rest) = Self::unpack_u64(rest)?;
+                let (minimum_collateral_amount, _rest) = Self::unpack_u64(rest).unwrap_or((0, &[]));
+                Self::LiquidateObligation {
+                    liquidity_amount,
+                    minimum_collateral_amount,
+                }
             }
             13 => {
                 let (amount, _rest) = Self::unpack_u64(rest)?;
@@ -415,6 +466,18 @@ impl LendingInstruction {
                 let new_config = Self::unpack_reserve_config(rest)?;
                 Self::ModifyReserveConfig { new_config }
             }
+            15 => {
+                let (category, rest) = Self::unpack_u8(rest)?;
+                let (loan_to_value_ratio, _rest) = Self::unpack_u8(rest)?;
+                Self::SetEmodeLoanToValueRatio {
+                    category,
+                    loan_to_value_ratio,
+                }
+            }
+            16 => {
+                let (price_wads, _rest) = Self::unpack_u128(rest)?;
+                Self::UpdateReservePrice { price_wads }
+            }
             _ => {
                 msg!("Instruction cannot be unpacked");
                 return Err(LendingError::InstructionUnpackError.into());
@@ -436,6 +499,20 @@ impl LendingInstruction {
         Ok((value, rest))
     }
 
+    fn unpack_u128(input: &[u8]) -> Result<(u128, &[u8]), ProgramError> {
+        if input.len() < 16 {
+            msg!("u128 cannot be unpacked");
+            return Err(LendingError::InstructionUnpackError.into());
+        }
+        let (bytes, rest) = input.split_at(16);
+        let value = bytes
+            .get(..16)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u128::from_le_bytes)
+            .ok_or(LendingError::InstructionUnpackError)?;
+        Ok((value, rest))
+    }
+
     fn unpack_u8(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
         if input.is_empty() {
             msg!("u8 cannot be unpacked");
@@ -484,7 +561,8 @@ impl LendingInstruction {
         let (max_borrow_rate, rest) = Self::unpack_u8(rest)?;
         let (borrow_fee_wad, rest) = Self::unpack_u64(rest)?;
         let (flash_loan_fee_wad, rest) = Self::unpack_u64(rest)?;
-        let (host_fee_percentage, _rest) = Self::unpack_u8(rest)?;
+        let (host_fee_percentage, rest) = Self::unpack_u8(rest)?;
+        let (emode_category, _rest) = Self::unpack_u8(rest).unwrap_or((0, &[]));
 
         Ok(ReserveConfig {
             optimal_utilization_rate,
@@ -499,6 +577,7 @@ impl LendingInstruction {
                 flash_loan_fee_wad,
                 host_fee_percentage,
             },
+            emode_category,
         })
     }
 
@@ -510,10 +589,12 @@ impl LendingInstruction {
             Self::InitLendingMarket {
                 owner,
                 quote_currency,
+                min_borrow_value,
             } => {
                 buf.push(0);
                 buf.extend_from_slice(owner.as_ref());
                 buf.extend_from_slice(quote_currency.as_ref());
+                buf.extend_from_slice(&min_borrow_value.to_le_bytes());
             }
             Self::SetLendingMarketOwner { new_owner } => {
                 buf.push(1);
@@ -564,9 +645,13 @@ impl LendingInstruction {
                 buf.push(11);
                 buf.extend_from_slice(&liquidity_amount.to_le_bytes());
             }
-            Self::LiquidateObligation { liquidity_amount } => {
+            Self::LiquidateObligation {
+                liquidity_amount,
+                minimum_collateral_amount,
+            } => {
                 buf.push(12);
                 buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_collateral_amount.to_le_bytes());
             }
             Self::FlashLoan { amount } => {
                 buf.push(13);
@@ -576,6 +661,18 @@ impl LendingInstruction {
                 buf.push(14);
                 Self::extend_buffer_from_reserve_config(&mut buf, &new_config);
             }
+            Self::SetEmodeLoanToValueRatio {
+                category,
+                loan_to_value_ratio,
+            } => {
+                buf.push(15);
+                buf.extend_from_slice(&category.to_le_bytes());
+                buf.extend_from_slice(&loan_to_value_ratio.to_le_bytes());
+            }
+            Self::UpdateReservePrice { price_wads } => {
+                buf.push(16);
+                buf.extend_from_slice(&price_wads.to_le_bytes());
+            }
         }
         buf
     }
@@ -592,6 +689,7 @@ impl LendingInstruction {
         buf.extend_from_slice(&config.fees.borrow_fee_wad.to_le_bytes());
         buf.extend_from_slice(&config.fees.flash_loan_fee_wad.to_le_bytes());
         buf.extend_from_slice(&config.fees.host_fee_percentage.to_le_bytes());
+        buf.extend_from_slice(&config.emode_category.to_le_bytes());
     }
 }
 
@@ -600,6 +698,7 @@ pub fn init_lending_market(
     program_id: Pubkey,
     owner: Pubkey,
     quote_currency: [u8; 32],
+    min_borrow_value: u64,
     lending_market_pubkey: Pubkey,
     oracle_program_id: Pubkey,
 ) -> Instruction {
@@ -614,6 +713,7 @@ pub fn init_lending_market(
         data: LendingInstruction::InitLendingMarket {
             owner,
             quote_currency,
+            min_borrow_value,
         }
         .pack(),
     }
@@ -823,6 +923,21 @@ pub fn refresh_obligation(
     }
 }
 
+/// Creates a 'RefreshObligation' instruction which applies the lending
+/// market's e-mode loan to value ratios.
+pub fn refresh_obligation_with_emode(
+    program_id: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    reserve_pubkeys: Vec<Pubkey>,
+) -> Instruction {
+    let mut instruction = refresh_obligation(program_id, obligation_pubkey, reserve_pubkeys);
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(lending_market_pubkey, false));
+    instruction
+}
+
 /// Creates a 'DepositObligationCollateral' instruction.
 #[allow(clippy::too_many_arguments)]
 pub fn deposit_obligation_collateral(
@@ -932,6 +1047,43 @@ pub fn borrow_obligation_liquidity(
     }
 }
 
+/// Creates a 'BorrowObligationLiquidity' instruction which adds a new
+/// reserve to the borrows of an obligation in an e-mode lending market.
+#[allow(clippy::too_many_arguments)]
+pub fn borrow_obligation_liquidity_with_emode(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    slippage_limit: Option<u64>,
+    source_liquidity_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    borrow_reserve_pubkey: Pubkey,
+    borrow_reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    host_fee_receiver_pubkey: Option<Pubkey>,
+    first_borrow_reserve_pubkey: Pubkey,
+) -> Instruction {
+    let mut instruction = borrow_obligation_liquidity(
+        program_id,
+        liquidity_amount,
+        slippage_limit,
+        source_liquidity_pubkey,
+        destination_liquidity_pubkey,
+        borrow_reserve_pubkey,
+        borrow_reserve_liquidity_fee_receiver_pubkey,
+        obligation_pubkey,
+        lending_market_pubkey,
+        obligation_owner_pubkey,
+        host_fee_receiver_pubkey,
+    );
+    instruction.accounts.push(AccountMeta::new_readonly(
+        first_borrow_reserve_pubkey,
+        false,
+    ));
+    instruction
+}
+
 /// Creates a `RepayObligationLiquidity` instruction
 #[allow(clippy::too_many_arguments)]
 pub fn repay_obligation_liquidity(
@@ -965,6 +1117,7 @@ pub fn repay_obligation_liquidity(
 pub fn liquidate_obligation(
     program_id: Pubkey,
     liquidity_amount: u64,
+    minimum_collateral_amount: u64,
     source_liquidity_pubkey: Pubkey,
     destination_collateral_pubkey: Pubkey,
     repay_reserve_pubkey: Pubkey,
@@ -995,7 +1148,11 @@ pub fn liquidate_obligation(
             AccountMeta::new_readonly(sysvar::clock::id(), false),
             AccountMeta::new_readonly(spl_token::id(), false),
         ],
-        data: LendingInstruction::LiquidateObligation { liquidity_amount }.pack(),
+        data: LendingInstruction::LiquidateObligation {
+            liquidity_amount,
+            minimum_collateral_amount,
+        }
+        .pack(),
     }
 }
 
@@ -1057,9 +1214,54 @@ pub fn modify_reserve_config(
     }
 }
 
+/// Creates a 'SetEmodeLoanToValueRatio' instruction.
+pub fn set_emode_loan_to_value_ratio(
+    program_id: Pubkey,
+    category: u8,
+    loan_to_value_ratio: u8,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner, true),
+        ],
+        data: LendingInstruction::SetEmodeLoanToValueRatio {
+            category,
+            loan_to_value_ratio,
+        }
+        .pack(),
+    }
+}
+
+/// Creates an 'UpdateReservePrice' instruction.
+pub fn update_reserve_price(
+    program_id: Pubkey,
+    price_wads: u128,
+    reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(reserve_pubkey, false),
+        AccountMeta::new_readonly(lending_market_pubkey, false),
+        AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+    ];
+    Instruction {
+        program_id,
+        accounts,
+        data: LendingInstruction::UpdateReservePrice { price_wads }.pack(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {
+        super::*,
+        crate::math::{Decimal, TryDiv},
+    };
 
     #[test]
     fn test_init_lending_market() {
@@ -1070,10 +1272,12 @@ mod tests {
         let currency = "USD";
         let mut quote_currency = [0u8; 32];
         quote_currency[0..currency.len()].clone_from_slice(currency.as_bytes());
+        let min_borrow_value = 10;
         let instruction = init_lending_market(
             program_id,
             owner,
             quote_currency,
+            min_borrow_value,
             lending_market_pubkey,
             oracle_program_id,
         );
@@ -1084,9 +1288,29 @@ mod tests {
             LendingInstruction::InitLendingMarket {
                 owner,
                 quote_currency,
+                min_borrow_value,
             }
             .pack()
         );
+        assert_eq!(
+            LendingInstruction::unpack(&instruction.data).unwrap(),
+            LendingInstruction::InitLendingMarket {
+                owner,
+                quote_currency,
+                min_borrow_value,
+            }
+        );
+
+        // data packed before the minimum borrow value existed
+        let legacy_data = &instruction.data[..instruction.data.len() - 8];
+        assert_eq!(
+            LendingInstruction::unpack(legacy_data).unwrap(),
+            LendingInstruction::InitLendingMarket {
+                owner,
+                quote_currency,
+                min_borrow_value: 0,
+            }
+        );
     }
 
     #[test]
@@ -1126,6 +1350,7 @@ mod tests {
                 flash_loan_fee_wad: 3,
                 host_fee_percentage: 1,
             },
+            emode_category: 2,
         };
         let source_liquidity_pubkey = Pubkey::new_unique();
         let destination_collateral_pubkey = Pubkey::new_unique();
@@ -1366,6 +1591,25 @@ mod tests {
             }
             .pack()
         );
+
+        let first_borrow_reserve_pubkey = Pubkey::new_unique();
+        let instruction = borrow_obligation_liquidity_with_emode(
+            program_id,
+            liquidity_amount,
+            None,
+            source_liquidity_pubkey,
+            destination_liquidity_pubkey,
+            borrow_reserve_pubkey,
+            borrow_reserve_liquidity_fee_receiver_pubkey,
+            obligation_pubkey,
+            lending_market_pubkey,
+            obligation_owner_pubkey,
+            None,
+            first_borrow_reserve_pubkey,
+        );
+        assert_eq!(instruction.accounts.len(), 11);
+        assert_eq!(instruction.accounts[10].pubkey, first_borrow_reserve_pubkey);
+        assert!(!instruction.accounts[10].is_writable);
     }
 
     #[test]
@@ -1400,6 +1644,7 @@ mod tests {
     fn test_liquidate_obligation() {
         let program_id = Pubkey::new_unique();
         let liquidity_amount = u64::MAX;
+        let minimum_collateral_amount = 1_000;
         let source_liquidity_pubkey = Pubkey::new_unique();
         let destination_collateral_pubkey = Pubkey::new_unique();
         let repay_reserve_pubkey = Pubkey::new_unique();
@@ -1412,6 +1657,7 @@ mod tests {
         let instruction = liquidate_obligation(
             program_id,
             liquidity_amount,
+            minimum_collateral_amount,
             source_liquidity_pubkey,
             destination_collateral_pubkey,
             repay_reserve_pubkey,
@@ -1426,7 +1672,28 @@ mod tests {
         assert_eq!(instruction.accounts.len(), 12);
         assert_eq!(
             instruction.data,
-            LendingInstruction::LiquidateObligation { liquidity_amount }.pack()
+            LendingInstruction::LiquidateObligation {
+                liquidity_amount,
+                minimum_collateral_amount,
+            }
+            .pack()
+        );
+        assert_eq!(
+            LendingInstruction::unpack(&instruction.data).unwrap(),
+            LendingInstruction::LiquidateObligation {
+                liquidity_amount,
+                minimum_collateral_amount,
+            }
+        );
+
+        // data packed before the minimum collateral amount existed
+        let legacy_data = &instruction.data[..instruction.data.len() - 8];
+        assert_eq!(
+            LendingInstruction::unpack(legacy_data).unwrap(),
+            LendingInstruction::LiquidateObligation {
+                liquidity_amount,
+                minimum_collateral_amount: 0,
+            }
         );
     }
 
@@ -1483,6 +1750,7 @@ mod tests {
                 flash_loan_fee_wad: 3,
                 host_fee_percentage: 1,
             },
+            emode_category: 2,
         };
         let reserve_pubkey = Pubkey::new_unique();
         let lending_market_pubkey = Pubkey::new_unique();
@@ -1501,4 +1769,91 @@ mod tests {
             LendingInstruction::ModifyReserveConfig { new_config: config }.pack()
         );
     }
+
+    #[test]
+    fn test_set_emode_loan_to_value_ratio() {
+        let program_id = Pubkey::new_unique();
+        let lending_market_pubkey = Pubkey::new_unique();
+        let lending_market_owner = Pubkey::new_unique();
+        let instruction = set_emode_loan_to_value_ratio(
+            program_id,
+            1,
+            90,
+            lending_market_pubkey,
+            lending_market_owner,
+        );
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(instruction.accounts.len(), 2);
+        assert_eq!(
+            LendingInstruction::unpack(&instruction.data).unwrap(),
+            LendingInstruction::SetEmodeLoanToValueRatio {
+                category: 1,
+                loan_to_value_ratio: 90,
+            }
+        );
+
+        let obligation_pubkey = Pubkey::new_unique();
+        let reserve_pubkeys = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let instruction = refresh_obligation_with_emode(
+            program_id,
+            obligation_pubkey,
+            lending_market_pubkey,
+            reserve_pubkeys,
+        );
+        assert_eq!(instruction.accounts.len(), 5);
+        assert_eq!(instruction.accounts[4].pubkey, lending_market_pubkey);
+    }
+
+    #[test]
+    fn test_unpack_reserve_config_emode_category() {
+        let config = ReserveConfig {
+            loan_to_value_ratio: 75,
+            liquidation_threshold: 80,
+            emode_category: 3,
+            ..ReserveConfig::default()
+        };
+        let data = LendingInstruction::ModifyReserveConfig { new_config: config }.pack();
+        assert_eq!(
+            LendingInstruction::unpack(&data).unwrap(),
+            LendingInstruction::ModifyReserveConfig { new_config: config }
+        );
+
+        // configs packed before the e-mode category have no category
+        assert_eq!(
+            LendingInstruction::unpack(&data[..data.len() - 1]).unwrap(),
+            LendingInstruction::ModifyReserveConfig {
+                new_config: ReserveConfig {
+                    emode_category: 0,
+                    ..config
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn test_update_reserve_price() {
+        let program_id = Pubkey::new_unique();
+        let price_wads = Decimal::from(42u64)
+            .try_div(1_000)
+            .unwrap()
+            .to_scaled_val()
+            .unwrap();
+        let reserve_pubkey = Pubkey::new_unique();
+        let lending_market_pubkey = Pubkey::new_unique();
+        let lending_market_owner_pubkey = Pubkey::new_unique();
+        let instruction = update_reserve_price(
+            program_id,
+            price_wads,
+            reserve_pubkey,
+            lending_market_pubkey,
+            lending_market_owner_pubkey,
+        );
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(instruction.accounts.len(), 3);
+        // fractional prices survive the round trip
+        assert_eq!(
+            LendingInstruction::unpack(&instruction.data).unwrap(),
+            LendingInstruction::UpdateReservePrice { price_wads }
+        );
+    }
 }
@@ -10,7 +10,7 @@ use {
             CalculateBorrowResult, CalculateLiquidationResult, CalculateRepayResult,
             InitLendingMarketParams, InitObligationParams, InitReserveParams, LendingMarket,
             NewReserveCollateralParams, NewReserveLiquidityParams, Obligation, Reserve,
-            ReserveCollateral, ReserveConfig, ReserveLiquidity,
+            ReserveCollateral, ReserveConfig, ReserveFees, ReserveLiquidity,
         },
     },
     num_traits::FromPrimitive,
@@ -44,9 +44,16 @@ pub fn process_instruction(
         LendingInstruction::InitLendingMarket {
             owner,
             quote_currency,
+            min_borrow_value,
         } => {
             msg!("Instruction: Init Lending Market");
-            process_init_lending_market(program_id, owner, quote_currency, accounts)
+            process_init_lending_market(
+                program_id,
+                owner,
+                quote_currency,
+                min_borrow_value,
+                accounts,
+            )
         }
         LendingInstruction::SetLendingMarketOwner { new_owner } => {
             msg!("Instruction: Set Lending Market Owner");
@@ -103,9 +110,17 @@ pub fn process_instruction(
             msg!("Instruction: Repay Obligation Liquidity");
             process_repay_obligation_liquidity(program_id, liquidity_amount, accounts)
         }
-        LendingInstruction::LiquidateObligation { liquidity_amount } => {
+        LendingInstruction::LiquidateObligation {
+            liquidity_amount,
+            minimum_collateral_amount,
+        } => {
             msg!("Instruction: Liquidate Obligation");
-            process_liquidate_obligation(program_id, liquidity_amount, accounts)
+            process_liquidate_obligation(
+                program_id,
+                liquidity_amount,
+                minimum_collateral_amount,
+                accounts,
+            )
         }
         LendingInstruction::FlashLoan { amount } => {
             msg!("Instruction: Flash Loan");
@@ -115,6 +130,22 @@ pub fn process_instruction(
             msg!("Instruction: Modify Reserve Config");
             process_modify_reserve_config(program_id, new_config, accounts)
         }
+        LendingInstruction::SetEmodeLoanToValueRatio {
+            category,
+            loan_to_value_ratio,
+        } => {
+            msg!("Instruction: Set E-mode Loan To Value Ratio");
+            process_set_emode_loan_to_value_ratio(
+                program_id,
+                category,
+                loan_to_value_ratio,
+                accounts,
+            )
+        }
+        LendingInstruction::UpdateReservePrice { price_wads } => {
+            msg!("Instruction: Update Reserve Price");
+            process_update_reserve_price(program_id, price_wads, accounts)
+        }
     }
 }
 
@@ -122,6 +153,7 @@ fn process_init_lending_market(
     program_id: &Pubkey,
     owner: Pubkey,
     quote_currency: [u8; 32],
+    min_borrow_value: u64,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
@@ -143,6 +175,7 @@ fn process_init_lending_market(
         quote_currency,
         token_program_id: *token_program_id.key,
         oracle_program_id: *oracle_program_id.key,
+        min_borrow_value,
     });
     LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
 
@@ -242,48 +275,56 @@ fn process_init_reserve(
         return Err(LendingError::InvalidSigner.into());
     }
 
-    if &lending_market.oracle_program_id != pyth_product_info.owner {
-        msg!("Pyth product account provided is not owned by the lending market oracle program");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-    if &lending_market.oracle_program_id != pyth_price_info.owner {
-        msg!("Pyth price account provided is not owned by the lending market oracle program");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
+    // the system program id stands in for both oracle accounts of a reserve
+    // without an oracle, which is priced by `UpdateReservePrice`
+    let has_oracle =
+        pyth_product_info.key != &Pubkey::default() || pyth_price_info.key != &Pubkey::default();
+    let market_price = if has_oracle {
+        if &lending_market.oracle_program_id != pyth_product_info.owner {
+            msg!("Pyth product account provided is not owned by the lending market oracle program");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+        if &lending_market.oracle_program_id != pyth_price_info.owner {
+            msg!("Pyth price account provided is not owned by the lending market oracle program");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
 
-    let pyth_product_data = pyth_product_info.try_borrow_data()?;
-    let pyth_product = pyth::load::<pyth::Product>(&pyth_product_data)
-        .map_err(|_| ProgramError::InvalidAccountData)?;
-    if pyth_product.magic != pyth::MAGIC {
-        msg!("Pyth product account provided is not a valid Pyth account");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-    if pyth_product.ver != pyth::VERSION_2 {
-        msg!("Pyth product account provided has a different version than expected");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-    if pyth_product.atype != pyth::AccountType::Product as u32 {
-        msg!("Pyth product account provided is not a valid Pyth product account");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
+        let pyth_product_data = pyth_product_info.try_borrow_data()?;
+        let pyth_product = pyth::load::<pyth::Product>(&pyth_product_data)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        if pyth_product.magic != pyth::MAGIC {
+            msg!("Pyth product account provided is not a valid Pyth account");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+        if pyth_product.ver != pyth::VERSION_2 {
+            msg!("Pyth product account provided has a different version than expected");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+        if pyth_product.atype != pyth::AccountType::Product as u32 {
+            msg!("Pyth product account provided is not a valid Pyth product account");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
 
-    let pyth_price_pubkey_bytes: &[u8; 32] = pyth_price_info
-        .key
-        .as_ref()
-        .try_into()
-        .map_err(|_| LendingError::InvalidAccountInput)?;
-    if &pyth_product.px_acc.val != pyth_price_pubkey_bytes {
-        msg!("Pyth product price account does not match the Pyth price provided");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
+        let pyth_price_pubkey_bytes: &[u8; 32] = pyth_price_info
+            .key
+            .as_ref()
+            .try_into()
+            .map_err(|_| LendingError::InvalidAccountInput)?;
+        if &pyth_product.px_acc.val != pyth_price_pubkey_bytes {
+            msg!("Pyth product price account does not match the Pyth price provided");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
 
-    let quote_currency = get_pyth_product_quote_currency(pyth_product)?;
-    if lending_market.quote_currency != quote_currency {
-        msg!("Lending market quote currency does not match the oracle quote currency");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
+        let quote_currency = get_pyth_product_quote_currency(pyth_product)?;
+        if lending_market.quote_currency != quote_currency {
+            msg!("Lending market quote currency does not match the oracle quote currency");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
 
-    let market_price = get_pyth_price(pyth_price_info, clock)?;
+        get_pyth_price(pyth_price_info, clock)?
+    } else {
+        Decimal::zero()
+    };
 
     let authority_signer_seeds = &[
         lending_market_info.key.as_ref(),
@@ -323,6 +364,10 @@ fn process_init_reserve(
     });
 
     let collateral_amount = reserve.deposit_liquidity(liquidity_amount)?;
+    if !has_oracle {
+        // can't be refreshed until its price is set
+        reserve.last_update.mark_stale();
+    }
     Reserve::pack(reserve, &mut reserve_info.data.borrow_mut())?;
 
     spl_token_init_account(TokenInitializeAccountParams {
@@ -402,7 +447,13 @@ fn process_refresh_reserve(program_id: &Pubkey, accounts: &[AccountInfo]) -> Pro
         return Err(LendingError::InvalidAccountInput.into());
     }
 
-    reserve.liquidity.market_price = get_pyth_price(reserve_liquidity_oracle_info, clock)?;
+    // Reserves without an oracle keep the price set by `UpdateReservePrice`
+    if reserve.liquidity.oracle_pubkey != Pubkey::default() {
+        reserve.liquidity.market_price = get_pyth_price(reserve_liquidity_oracle_info, clock)?;
+    } else if reserve.liquidity.market_price == Decimal::zero() {
+        msg!("Reserve without an oracle must have its price set before it is refreshed");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
 
     reserve.accrue_interest(clock.slot)?;
     reserve.last_update.update_slot(clock.slot);
@@ -672,6 +723,10 @@ fn process_refresh_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) ->
     let mut borrowed_value = Decimal::zero();
     let mut allowed_borrow_value = Decimal::zero();
     let mut unhealthy_borrow_value = Decimal::zero();
+    // the allowed borrow value depends on the borrows' e-mode category, so it
+    // is summed once all reserves have been read
+    let mut deposit_configs = Vec::with_capacity(obligation.deposits.len());
+    let mut borrow_emode_category = None;
 
     for (index, collateral) in obligation.deposits.iter_mut().enumerate() {
         let deposit_reserve_info = next_account_info(account_info_iter)?;
@@ -699,27 +754,16 @@ fn process_refresh_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) ->
             return Err(LendingError::ReserveStale.into());
         }
 
-        // @TODO: add lookup table https://git.io/JOCYq
-        let decimals = 10u64
-            .checked_pow(deposit_reserve.liquidity.mint_decimals as u32)
-            .ok_or(LendingError::MathOverflow)?;
-
-        let market_value = deposit_reserve
-            .collateral_exchange_rate()?
-            .decimal_collateral_to_liquidity(collateral.deposited_amount.into())?
-            .try_mul(deposit_reserve.liquidity.market_price)?
-            .try_div(decimals)?;
-        collateral.market_value = market_value;
+        collateral.refresh_market_value(&deposit_reserve)?;
+        let market_value = collateral.market_value;
 
-        let loan_to_value_rate = Rate::from_percent(deposit_reserve.config.loan_to_value_ratio);
         let liquidation_threshold_rate =
             Rate::from_percent(deposit_reserve.config.liquidation_threshold);
 
         deposited_value = deposited_value.try_add(market_value)?;
-        allowed_borrow_value =
-            allowed_borrow_value.try_add(market_value.try_mul(loan_to_value_rate)?)?;
         unhealthy_borrow_value =
             unhealthy_borrow_value.try_add(market_value.try_mul(liquidation_threshold_rate)?)?;
+        deposit_configs.push((market_value, deposit_reserve.config));
     }
 
     for (index, liquidity) in obligation.borrows.iter_mut().enumerate() {
@@ -750,25 +794,53 @@ fn process_refresh_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) ->
 
         liquidity.accrue_interest(borrow_reserve.liquidity.cumulative_borrow_rate_wads)?;
 
-        // @TODO: add lookup table https://git.io/JOCYq
-        let decimals = 10u64
-            .checked_pow(borrow_reserve.liquidity.mint_decimals as u32)
-            .ok_or(LendingError::MathOverflow)?;
+        let emode_category = borrow_reserve.config.emode_category;
+        borrow_emode_category = if index == 0 || borrow_emode_category == Some(emode_category) {
+            Some(emode_category)
+        } else {
+            None
+        };
 
-        let market_value = liquidity
-            .borrowed_amount_wads
-            .try_mul(borrow_reserve.liquidity.market_price)?
-            .try_div(decimals)?;
+        let market_value = borrow_reserve
+            .liquidity
+            .market_value(liquidity.borrowed_amount_wads)?;
         liquidity.market_value = market_value;
 
         borrowed_value = borrowed_value.try_add(market_value)?;
     }
 
+    let lending_market = match account_info_iter.next() {
+        Some(lending_market_info) => {
+            if lending_market_info.owner != program_id {
+                msg!("Lending market provided is not owned by the lending program");
+                return Err(LendingError::InvalidAccountOwner.into());
+            }
+            if &obligation.lending_market != lending_market_info.key {
+                msg!("Obligation lending market does not match the lending market provided");
+                return Err(LendingError::InvalidAccountInput.into());
+            }
+            Some(LendingMarket::unpack(&lending_market_info.data.borrow())?)
+        }
+        None => None,
+    };
+
     if account_info_iter.peek().is_some() {
         msg!("Too many obligation deposit or borrow reserves provided");
         return Err(LendingError::InvalidAccountInput.into());
     }
 
+    for (market_value, config) in deposit_configs {
+        let loan_to_value_ratio = match &lending_market {
+            Some(lending_market) => {
+                lending_market.loan_to_value_ratio(&config, borrow_emode_category)
+            }
+            None => config.loan_to_value_ratio,
+        };
+        let loan_to_value_rate = Rate::from_percent(loan_to_value_ratio);
+        allowed_borrow_value =
+            allowed_borrow_value.try_add(market_value.try_mul(loan_to_value_rate)?)?;
+    }
+
     obligation.deposited_value = deposited_value;
     obligation.borrowed_value = borrowed_value;
     obligation.allowed_borrow_value = allowed_borrow_value;
@@ -849,7 +921,7 @@ fn process_deposit_obligation_collateral(
         msg!("Obligation lending market does not match the lending market provided");
         return Err(LendingError::InvalidAccountInput.into());
     }
-    if &obligation.owner != obligation_owner_info.key {
+    if obligation.owner() != obligation_owner_info.key {
         msg!("Obligation owner does not match the obligation owner provided");
         return Err(LendingError::InvalidObligationOwner.into());
     }
@@ -939,7 +1011,7 @@ fn process_withdraw_obligation_collateral(
         msg!("Obligation lending market does not match the lending market provided");
         return Err(LendingError::InvalidAccountInput.into());
     }
-    if &obligation.owner != obligation_owner_info.key {
+    if obligation.owner() != obligation_owner_info.key {
         msg!("Obligation owner does not match the obligation owner provided");
         return Err(LendingError::InvalidObligationOwner.into());
     }
@@ -982,9 +1054,15 @@ fn process_withdraw_obligation_collateral(
         msg!("Obligation deposited value is zero");
         return Err(LendingError::ObligationDepositsZero.into());
     } else {
-        let max_withdraw_value = obligation.max_withdraw_value(Rate::from_percent(
-            withdraw_reserve.config.loan_to_value_ratio,
-        ))?;
+        // the obligation's borrow reserves are not passed here, so assume the
+        // highest ratio the collateral can count at, which bounds the value
+        // its withdrawal removes from the allowed borrow value
+        let withdraw_collateral_ltv = lending_market.loan_to_value_ratio(
+            &withdraw_reserve.config,
+            Some(withdraw_reserve.config.emode_category),
+        );
+        let max_withdraw_value =
+            obligation.max_withdraw_value(Rate::from_percent(withdraw_collateral_ltv))?;
         if max_withdraw_value == Decimal::zero() {
             msg!("Maximum withdraw value is zero");
             return Err(LendingError::WithdrawTooLarge.into());
@@ -1102,7 +1180,7 @@ fn process_borrow_obligation_liquidity(
         msg!("Obligation lending market does not match the lending market provided");
         return Err(LendingError::InvalidAccountInput.into());
     }
-    if &obligation.owner != obligation_owner_info.key {
+    if obligation.owner() != obligation_owner_info.key {
         msg!("Obligation owner does not match the obligation owner provided");
         return Err(LendingError::InvalidObligationOwner.into());
     }
@@ -1123,6 +1201,51 @@ fn process_borrow_obligation_liquidity(
         return Err(LendingError::ObligationDepositsZero.into());
     }
 
+    // the optional trailing accounts are the host fee receiver and the reserve
+    // of the obligation's first borrow, told apart by the reserve address
+    let first_borrow_reserve = obligation
+        .borrows
+        .first()
+        .map(|liquidity| liquidity.borrow_reserve);
+    let mut host_fee_receiver_info = None;
+    let mut first_borrow_reserve_info = None;
+    for account_info in account_info_iter {
+        if Some(*account_info.key) == first_borrow_reserve {
+            first_borrow_reserve_info = Some(account_info);
+        } else {
+            host_fee_receiver_info = Some(account_info);
+        }
+    }
+
+    // the obligation may have been refreshed with the e-mode loan to value
+    // ratio of its borrows' category, which only holds while every borrow
+    // stays in that category
+    let is_new_borrow_reserve = first_borrow_reserve.is_some()
+        && !obligation
+            .borrows
+            .iter()
+            .any(|liquidity| &liquidity.borrow_reserve == borrow_reserve_info.key);
+    if is_new_borrow_reserve && lending_market.has_emode_loan_to_value_ratios() {
+        let first_borrow_reserve_info = first_borrow_reserve_info.ok_or_else(|| {
+            msg!("Reserve of the obligation's first borrow must be provided when borrowing from a new reserve in an e-mode lending market");
+            LendingError::InvalidAccountInput
+        })?;
+        if first_borrow_reserve_info.owner != program_id {
+            msg!("First borrow reserve provided is not owned by the lending program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+        let first_borrow_reserve = Reserve::unpack(&first_borrow_reserve_info.data.borrow())?;
+        let emode_category = first_borrow_reserve.config.emode_category;
+        if emode_category != borrow_reserve.config.emode_category
+            && lending_market
+                .emode_loan_to_value_ratio(emode_category)
+                .is_some()
+        {
+            msg!("Borrow reserve e-mode category does not match the obligation's borrows");
+            return Err(LendingError::EmodeCategoryMismatch.into());
+        }
+    }
+
     let authority_signer_seeds = &[
         lending_market_info.key.as_ref(),
         &[lending_market.bump_seed],
@@ -1159,6 +1282,15 @@ fn process_borrow_obligation_liquidity(
         return Err(LendingError::ExceededSlippage.into());
     }
 
+    let borrowed_value = borrow_reserve
+        .liquidity
+        .market_value(borrow_amount)?
+        .try_add(obligation.borrowed_value)?;
+    if borrowed_value < Decimal::from(lending_market.min_borrow_value) {
+        msg!("Obligation borrowed value would be below the lending market minimum borrow value");
+        return Err(LendingError::BorrowTooSmallValue.into());
+    }
+
     borrow_reserve.liquidity.borrow(borrow_amount)?;
     borrow_reserve.last_update.mark_stale();
     Reserve::pack(borrow_reserve, &mut borrow_reserve_info.data.borrow_mut())?;
@@ -1169,13 +1301,11 @@ fn process_borrow_obligation_liquidity(
     obligation.last_update.mark_stale();
     Obligation::pack(obligation, &mut obligation_info.data.borrow_mut())?;
 
-    let mut owner_fee = borrow_fee;
-    if let Ok(host_fee_receiver_info) = next_account_info(account_info_iter) {
+    // without a host fee receiver, the host share goes to the owner
+    let (owner_fee, host_fee) =
+        ReserveFees::split_host_fee(borrow_fee, host_fee, host_fee_receiver_info.is_some())?;
+    if let Some(host_fee_receiver_info) = host_fee_receiver_info {
         if host_fee > 0 {
-            owner_fee = owner_fee
-                .checked_sub(host_fee)
-                .ok_or(LendingError::MathOverflow)?;
-
             spl_token_transfer(TokenTransferParams {
                 source: source_liquidity_info.clone(),
                 destination: host_fee_receiver_info.clone(),
@@ -1317,6 +1447,7 @@ fn process_repay_obligation_liquidity(
 fn process_liquidate_obligation(
     program_id: &Pubkey,
     liquidity_amount: u64,
+    minimum_collateral_amount: u64,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     if liquidity_amount == 0 {
@@ -1474,6 +1605,10 @@ fn process_liquidate_obligation(
         msg!("Liquidation is too small to receive collateral");
         return Err(LendingError::LiquidationTooSmall.into());
     }
+    if withdraw_amount < minimum_collateral_amount {
+        msg!("Received collateral would be smaller than the desired minimum amount");
+        return Err(LendingError::ExceededSlippage.into());
+    }
 
     repay_reserve.liquidity.repay(repay_amount, settle_amount)?;
     repay_reserve.last_update.mark_stale();
@@ -1575,7 +1710,7 @@ fn process_flash_loan(
     // @FIXME: if u64::MAX is flash loaned, fees should be inclusive as with
     // ordinary borrows
     let flash_loan_amount = if liquidity_amount == u64::MAX {
-        reserve.liquidity.available_amount
+        reserve.liquidity.max_flash_loan()
     } else {
         liquidity_amount
     };
@@ -1656,11 +1791,8 @@ fn process_flash_loan(
         return Err(LendingError::NotEnoughLiquidityAfterFlashLoan.into());
     }
 
-    let mut owner_fee = origination_fee;
+    let (owner_fee, host_fee) = ReserveFees::split_host_fee(origination_fee, host_fee, true)?;
     if host_fee > 0 {
-        owner_fee = owner_fee
-            .checked_sub(host_fee)
-            .ok_or(LendingError::MathOverflow)?;
         spl_token_transfer(TokenTransferParams {
             source: source_liquidity_info.clone(),
             destination: host_fee_receiver_info.clone(),
@@ -1733,6 +1865,87 @@ fn process_modify_reserve_config(
     Ok(())
 }
 
+fn process_set_emode_loan_to_value_ratio(
+    program_id: &Pubkey,
+    category: u8,
+    loan_to_value_ratio: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    if lending_market_info.owner != program_id {
+        msg!("Lending market provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &lending_market.owner != lending_market_owner_info.key {
+        msg!("Lending market owner does not match the lending market owner provided");
+        return Err(LendingError::InvalidMarketOwner.into());
+    }
+    if !lending_market_owner_info.is_signer {
+        msg!("Lending market owner provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    lending_market.set_emode_loan_to_value_ratio(category, loan_to_value_ratio)?;
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_update_reserve_price(
+    program_id: &Pubkey,
+    price_wads: u128,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if price_wads == 0 {
+        msg!("Reserve price provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let account_info_iter = &mut accounts.iter().peekable();
+    let reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    if reserve_info.owner != program_id {
+        msg!("Reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    if lending_market_info.owner != program_id {
+        msg!("Lending market provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &lending_market.owner != lending_market_owner_info.key {
+        msg!("Lending market owner does not match the lending market owner provided");
+        return Err(LendingError::InvalidMarketOwner.into());
+    }
+    if !lending_market_owner_info.is_signer {
+        msg!("Lending market owner provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    let mut reserve = Reserve::unpack(&reserve_info.data.borrow())?;
+    if reserve.lending_market != *lending_market_info.key {
+        msg!("Reserve account does not match the lending market");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if reserve.liquidity.oracle_pubkey != Pubkey::default() {
+        msg!("Reserve price can only be set directly when the reserve has no oracle");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+
+    reserve.liquidity.market_price = Decimal::from_scaled_val(price_wads);
+    reserve.last_update.mark_stale();
+    Reserve::pack(reserve, &mut reserve_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
 fn assert_rent_exempt(rent: &Rent, account_info: &AccountInfo) -> ProgramResult {
     if !rent.is_exempt(account_info.lamports(), account_info.data_len()) {
         msg!(&rent.minimum_balance(account_info.data_len()).to_string());
@@ -1,7 +1,9 @@
 use {
     super::*,
+    crate::error::LendingError,
     arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
     solana_program::{
+        entrypoint::ProgramResult,
         msg,
         program_error::ProgramError,
         program_pack::{IsInitialized, Pack, Sealed},
@@ -9,6 +11,10 @@ use {
     },
 };
 
+/// Number of correlated asset categories a lending market can configure,
+/// numbered from 1
+pub const MAX_EMODE_CATEGORIES: usize = 8;
+
 /// Lending market state
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct LendingMarket {
@@ -27,6 +33,12 @@ pub struct LendingMarket {
     pub token_program_id: Pubkey,
     /// Oracle (Pyth) program id
     pub oracle_program_id: Pubkey,
+    /// Minimum total borrowed value of an obligation after a borrow, in
+    /// whole units of the quote currency
+    pub min_borrow_value: u64,
+    /// Loan to value ratio for each e-mode category, as a percentage, indexed
+    /// by category - 1. 0 if the category has no override
+    pub emode_loan_to_value_ratios: [u8; MAX_EMODE_CATEGORIES],
 }
 
 impl LendingMarket {
@@ -45,6 +57,69 @@ impl LendingMarket {
         self.quote_currency = params.quote_currency;
         self.token_program_id = params.token_program_id;
         self.oracle_program_id = params.oracle_program_id;
+        self.min_borrow_value = params.min_borrow_value;
+    }
+
+    /// Loan to value ratio override for an e-mode category, if the category
+    /// has one
+    pub fn emode_loan_to_value_ratio(&self, category: u8) -> Option<u8> {
+        let index = usize::from(category).checked_sub(1)?;
+        self.emode_loan_to_value_ratios
+            .get(index)
+            .copied()
+            .filter(|ratio| *ratio > 0)
+    }
+
+    /// Whether any e-mode category has a loan to value ratio override
+    pub fn has_emode_loan_to_value_ratios(&self) -> bool {
+        self.emode_loan_to_value_ratios
+            .iter()
+            .any(|ratio| *ratio > 0)
+    }
+
+    /// Set the loan to value ratio override for an e-mode category, 0 to
+    /// remove it
+    pub fn set_emode_loan_to_value_ratio(
+        &mut self,
+        category: u8,
+        loan_to_value_ratio: u8,
+    ) -> ProgramResult {
+        if loan_to_value_ratio >= 100 {
+            msg!("Loan to value ratio must be in range [0, 100)");
+            return Err(LendingError::InvalidConfig.into());
+        }
+        let ratio = usize::from(category)
+            .checked_sub(1)
+            .and_then(|index| self.emode_loan_to_value_ratios.get_mut(index))
+            .ok_or_else(|| {
+                msg!("E-mode category must be in range [1, MAX_EMODE_CATEGORIES]");
+                LendingError::InvalidConfig
+            })?;
+        *ratio = loan_to_value_ratio;
+        Ok(())
+    }
+
+    /// Loan to value ratio of a reserve's collateral in an obligation whose
+    /// borrows all share `borrow_category`
+    ///
+    /// Collateral in the same e-mode category as the borrows uses the
+    /// category's override when it is higher than the reserve's own ratio,
+    /// kept below the reserve's liquidation threshold. Any other collateral
+    /// uses the reserve's own ratio, as does collateral which is disabled
+    /// with a ratio of 0.
+    pub fn loan_to_value_ratio(&self, config: &ReserveConfig, borrow_category: Option<u8>) -> u8 {
+        let base_ratio = config.loan_to_value_ratio;
+        match borrow_category {
+            Some(category) if category == config.emode_category && base_ratio > 0 => self
+                .emode_loan_to_value_ratio(category)
+                .map(|ratio| {
+                    ratio
+                        .max(base_ratio)
+                        .min(config.liquidation_threshold.saturating_sub(1))
+                })
+                .unwrap_or(base_ratio),
+            _ => base_ratio,
+        }
     }
 }
 
@@ -63,6 +138,9 @@ pub struct InitLendingMarketParams {
     pub token_program_id: Pubkey,
     /// Oracle (Pyth) program id
     pub oracle_program_id: Pubkey,
+    /// Minimum total borrowed value of an obligation after a borrow, in
+    /// whole units of the quote currency
+    pub min_borrow_value: u64,
 }
 
 impl Sealed for LendingMarket {}
@@ -72,7 +150,7 @@ impl IsInitialized for LendingMarket {
     }
 }
 
-const LENDING_MARKET_LEN: usize = 258; // 1 + 1 + 32 + 32 + 32 + 32 + 128
+const LENDING_MARKET_LEN: usize = 258; // 1 + 1 + 32 + 32 + 32 + 32 + 8 + 8 + 112
 impl Pack for LendingMarket {
     const LEN: usize = LENDING_MARKET_LEN;
 
@@ -86,6 +164,8 @@ impl Pack for LendingMarket {
             quote_currency,
             token_program_id,
             oracle_program_id,
+            min_borrow_value,
+            emode_loan_to_value_ratios,
             _padding,
         ) = mut_array_refs![
             output,
@@ -95,7 +175,9 @@ impl Pack for LendingMarket {
             32,
             PUBKEY_BYTES,
             PUBKEY_BYTES,
-            128
+            8,
+            MAX_EMODE_CATEGORIES,
+            112
         ];
 
         *version = self.version.to_le_bytes();
@@ -104,6 +186,8 @@ impl Pack for LendingMarket {
         quote_currency.copy_from_slice(self.quote_currency.as_ref());
         token_program_id.copy_from_slice(self.token_program_id.as_ref());
         oracle_program_id.copy_from_slice(self.oracle_program_id.as_ref());
+        *min_borrow_value = self.min_borrow_value.to_le_bytes();
+        emode_loan_to_value_ratios.copy_from_slice(&self.emode_loan_to_value_ratios);
     }
 
     /// Unpacks a byte buffer into a
@@ -118,6 +202,8 @@ impl Pack for LendingMarket {
             quote_currency,
             token_program_id,
             oracle_program_id,
+            min_borrow_value,
+            emode_loan_to_value_ratios,
             _padding,
         ) = array_refs![
             input,
@@ -127,7 +213,9 @@ impl Pack for LendingMarket {
             32,
             PUBKEY_BYTES,
             PUBKEY_BYTES,
-            128
+            8,
+            MAX_EMODE_CATEGORIES,
+            112
         ];
 
         let version = u8::from_le_bytes(*version);
@@ -143,6 +231,89 @@ impl Pack for LendingMarket {
             quote_currency: *quote_currency,
             token_program_id: Pubkey::new_from_array(*token_program_id),
             oracle_program_id: Pubkey::new_from_array(*oracle_program_id),
+            min_borrow_value: u64::from_le_bytes(*min_borrow_value),
+            emode_loan_to_value_ratios: *emode_loan_to_value_ratios,
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn emode_config(loan_to_value_ratio: u8, emode_category: u8) -> ReserveConfig {
+        ReserveConfig {
+            loan_to_value_ratio,
+            liquidation_threshold: 95,
+            emode_category,
+            ..ReserveConfig::default()
+        }
+    }
+
+    #[test]
+    fn emode_loan_to_value_ratio() {
+        let mut lending_market = LendingMarket::default();
+        lending_market.set_emode_loan_to_value_ratio(1, 90).unwrap();
+        assert!(lending_market.has_emode_loan_to_value_ratios());
+        assert!(!LendingMarket::default().has_emode_loan_to_value_ratios());
+        let stablecoin = emode_config(75, 1);
+        let volatile = emode_config(60, 2);
+
+        // same-category collateral and borrows use the higher category ratio
+        assert_eq!(lending_market.loan_to_value_ratio(&stablecoin, Some(1)), 90);
+
+        // cross-category positions use the reserve's own ratio
+        assert_eq!(lending_market.loan_to_value_ratio(&stablecoin, Some(2)), 75);
+        assert_eq!(lending_market.loan_to_value_ratio(&volatile, Some(1)), 60);
+        assert_eq!(lending_market.loan_to_value_ratio(&stablecoin, None), 75);
+
+        // categories without an override and reserves without a category
+        // keep their own ratio
+        assert_eq!(lending_market.loan_to_value_ratio(&volatile, Some(2)), 60);
+        assert_eq!(
+            lending_market.loan_to_value_ratio(&emode_config(50, 0), Some(0)),
+            50
+        );
+
+        // disabled collateral stays disabled
+        assert_eq!(
+            lending_market.loan_to_value_ratio(&emode_config(0, 1), Some(1)),
+            0
+        );
+
+        // the override never lowers the ratio or reaches the threshold
+        lending_market.set_emode_loan_to_value_ratio(1, 50).unwrap();
+        assert_eq!(lending_market.loan_to_value_ratio(&stablecoin, Some(1)), 75);
+        lending_market.set_emode_loan_to_value_ratio(1, 99).unwrap();
+        assert_eq!(lending_market.loan_to_value_ratio(&stablecoin, Some(1)), 94);
+
+        // only categories 1 to MAX_EMODE_CATEGORIES and ratios below 100
+        assert!(lending_market.set_emode_loan_to_value_ratio(0, 90).is_err());
+        assert!(lending_market
+            .set_emode_loan_to_value_ratio(MAX_EMODE_CATEGORIES as u8 + 1, 90)
+            .is_err());
+        assert!(lending_market
+            .set_emode_loan_to_value_ratio(1, 100)
+            .is_err());
+    }
+
+    #[test]
+    fn pack_emode_loan_to_value_ratios() {
+        let mut lending_market = LendingMarket {
+            version: PROGRAM_VERSION,
+            min_borrow_value: 10,
+            ..LendingMarket::default()
+        };
+        lending_market.set_emode_loan_to_value_ratio(1, 90).unwrap();
+        lending_market
+            .set_emode_loan_to_value_ratio(MAX_EMODE_CATEGORIES as u8, 80)
+            .unwrap();
+
+        let mut packed = [0u8; LendingMarket::LEN];
+        LendingMarket::pack(lending_market.clone(), &mut packed).unwrap();
+        let unpacked = LendingMarket::unpack(&packed).unwrap();
+        assert_eq!(unpacked, lending_market);
+        assert_eq!(unpacked.emode_loan_to_value_ratio(1), Some(90));
+        assert_eq!(unpacked.emode_loan_to_value_ratio(2), None);
+    }
+}
@@ -67,6 +67,11 @@ impl Obligation {
         self.borrows = params.borrows;
     }
 
+    /// Owner authorized to deposit, withdraw and borrow against the obligation
+    pub fn owner(&self) -> &Pubkey {
+        &self.owner
+    }
+
     /// Calculate the current ratio of borrowed value to deposited value
     pub fn loan_to_value(&self) -> Result<Decimal, ProgramError> {
         self.borrowed_value.try_div(self.deposited_value)
@@ -110,12 +115,42 @@ impl Obligation {
             .try_div(withdraw_collateral_ltv)
     }
 
-    /// Calculate the maximum liquidity value that can be borrowed
+    /// Calculate the maximum liquidity value that can be borrowed, which is
+    /// zero once the borrowed value reaches or exceeds the allowed value
     pub fn remaining_borrow_value(&self) -> Result<Decimal, ProgramError> {
+        if self.allowed_borrow_value <= self.borrowed_value {
+            return Ok(Decimal::zero());
+        }
         self.allowed_borrow_value.try_sub(self.borrowed_value)
     }
 
-    /// Calculate the maximum liquidation amount for a given liquidity
+    /// Calculate the collateral value weighted by each deposit reserve's
+    /// liquidation threshold, given as `(deposit_reserve, threshold)` pairs
+    pub fn liquidation_adjusted_collateral_value(
+        &self,
+        thresholds: &[(Pubkey, u8)],
+    ) -> Result<Decimal, ProgramError> {
+        self.deposits
+            .iter()
+            .try_fold(Decimal::zero(), |total, collateral| {
+                let (_, liquidation_threshold) = thresholds
+                    .iter()
+                    .find(|(deposit_reserve, _)| *deposit_reserve == collateral.deposit_reserve)
+                    .ok_or_else(|| {
+                        msg!("Liquidation threshold not provided for a deposit reserve");
+                        LendingError::InvalidObligationCollateral
+                    })?;
+                total.try_add(
+                    collateral
+                        .market_value
+                        .try_mul(Rate::from_percent(*liquidation_threshold))?,
+                )
+            })
+    }
+
+    /// Calculate the maximum liquidation amount for a given liquidity, limited
+    /// by the close factor of both the obligation's total borrowed value and
+    /// the liquidity's own borrowed amount
     pub fn max_liquidation_amount(
         &self,
         liquidity: &ObligationLiquidity,
@@ -125,7 +160,13 @@ impl Obligation {
             .try_mul(Rate::from_percent(LIQUIDATION_CLOSE_FACTOR))?
             .min(liquidity.market_value);
         let max_liquidation_pct = max_liquidation_value.try_div(liquidity.market_value)?;
-        liquidity.borrowed_amount_wads.try_mul(max_liquidation_pct)
+        let close_factor_amount = liquidity
+            .borrowed_amount_wads
+            .try_mul(Rate::from_percent(LIQUIDATION_CLOSE_FACTOR))?;
+        Ok(liquidity
+            .borrowed_amount_wads
+            .try_mul(max_liquidation_pct)?
+            .min(close_factor_amount))
     }
 
     /// Find collateral by deposit reserve
@@ -211,6 +252,22 @@ impl Obligation {
     }
 }
 
+/// Sum the bad debt across a set of obligations, given as
+/// `(borrowed_value, collateral_value)` pairs: the total by which borrowed
+/// value exceeds collateral value, ignoring solvent obligations
+pub fn detect_bad_debt(obligations: &[(Decimal, Decimal)]) -> Result<Decimal, ProgramError> {
+    obligations.iter().try_fold(
+        Decimal::zero(),
+        |total, &(borrowed_value, collateral_value)| {
+            if borrowed_value > collateral_value {
+                total.try_add(borrowed_value.try_sub(collateral_value)?)
+            } else {
+                Ok(total)
+            }
+        },
+    )
+}
+
 /// Initialize an obligation
 pub struct InitObligationParams {
     /// Last update to collateral, liquidity, or their market values
@@ -271,6 +328,16 @@ impl ObligationCollateral {
             .ok_or(LendingError::MathOverflow)?;
         Ok(())
     }
+
+    /// Update the market value of the deposited collateral from the deposit
+    /// reserve's market price, scaled down by the liquidity mint decimals
+    pub fn refresh_market_value(&mut self, deposit_reserve: &Reserve) -> ProgramResult {
+        let liquidity_amount = deposit_reserve
+            .collateral_exchange_rate()?
+            .decimal_collateral_to_liquidity(self.deposited_amount.into())?;
+        self.market_value = deposit_reserve.liquidity.market_value(liquidity_amount)?;
+        Ok(())
+    }
 }
 
 /// Obligation liquidity state
@@ -309,24 +376,29 @@ impl ObligationLiquidity {
         Ok(())
     }
 
-    /// Accrue interest
-    pub fn accrue_interest(&mut self, cumulative_borrow_rate_wads: Decimal) -> ProgramResult {
-        match cumulative_borrow_rate_wads.cmp(&self.cumulative_borrow_rate_wads) {
+    /// Growth factor of the borrowed amount since it last accrued interest,
+    /// given the borrow reserve's current cumulative borrow rate
+    pub fn realized_rate(&self, reserve_cumulative_rate: Decimal) -> Result<Rate, ProgramError> {
+        match reserve_cumulative_rate.cmp(&self.cumulative_borrow_rate_wads) {
             Ordering::Less => {
                 msg!("Interest rate cannot be negative");
-                return Err(LendingError::NegativeInterestRate.into());
-            }
-            Ordering::Equal => {}
-            Ordering::Greater => {
-                let compounded_interest_rate: Rate = cumulative_borrow_rate_wads
-                    .try_div(self.cumulative_borrow_rate_wads)?
-                    .try_into()?;
-
-                self.borrowed_amount_wads = self
-                    .borrowed_amount_wads
-                    .try_mul(compounded_interest_rate)?;
-                self.cumulative_borrow_rate_wads = cumulative_borrow_rate_wads;
+                Err(LendingError::NegativeInterestRate.into())
             }
+            Ordering::Equal => Ok(Rate::one()),
+            Ordering::Greater => reserve_cumulative_rate
+                .try_div(self.cumulative_borrow_rate_wads)?
+                .try_into(),
+        }
+    }
+
+    /// Accrue interest
+    pub fn accrue_interest(&mut self, cumulative_borrow_rate_wads: Decimal) -> ProgramResult {
+        let compounded_interest_rate = self.realized_rate(cumulative_borrow_rate_wads)?;
+        if cumulative_borrow_rate_wads > self.cumulative_borrow_rate_wads {
+            self.borrowed_amount_wads = self
+                .borrowed_amount_wads
+                .try_mul(compounded_interest_rate)?;
+            self.cumulative_borrow_rate_wads = cumulative_borrow_rate_wads;
         }
 
         Ok(())
@@ -512,6 +584,226 @@ mod test {
 
     const MAX_COMPOUNDED_INTEREST: u64 = 100; // 10,000%
 
+    #[test]
+    fn collateral_market_value() {
+        // 2 liquidity tokens per collateral token, with 6 decimals, at a
+        // market price of 25
+        let deposit_reserve = Reserve {
+            liquidity: ReserveLiquidity {
+                mint_decimals: 6,
+                available_amount: 2_000_000_000,
+                market_price: Decimal::from(25u64),
+                ..ReserveLiquidity::default()
+            },
+            collateral: ReserveCollateral {
+                mint_total_supply: 1_000_000_000,
+                ..ReserveCollateral::default()
+            },
+            ..Reserve::default()
+        };
+        let mut collateral = ObligationCollateral {
+            deposited_amount: 1_250_000,
+            ..ObligationCollateral::default()
+        };
+        collateral.refresh_market_value(&deposit_reserve).unwrap();
+        // 1.25 collateral tokens back 2.5 liquidity tokens, worth 62.5
+        assert_eq!(
+            collateral.market_value,
+            Decimal::from(125u64).try_div(2u64).unwrap()
+        );
+    }
+
+    #[test]
+    fn owner_survives_pack() {
+        let owner = Pubkey::new_unique();
+        let obligation = Obligation::new(InitObligationParams {
+            current_slot: 0,
+            lending_market: Pubkey::new_unique(),
+            owner,
+            deposits: vec![],
+            borrows: vec![],
+        });
+        assert_eq!(obligation.owner(), &owner);
+
+        let mut packed = [0u8; Obligation::LEN];
+        Obligation::pack(obligation, &mut packed).unwrap();
+        let unpacked = Obligation::unpack(&packed).unwrap();
+        assert_eq!(unpacked.owner(), &owner);
+    }
+
+    #[test]
+    fn max_withdraw_value_keeps_loan_healthy() {
+        // 200 deposited at a 50% loan to value ratio, 50 borrowed
+        let ltv = Rate::from_percent(50);
+        let obligation = Obligation {
+            deposited_value: Decimal::from(200u64),
+            allowed_borrow_value: Decimal::from(100u64),
+            borrowed_value: Decimal::from(50u64),
+            ..Obligation::default()
+        };
+        let max_withdraw_value = obligation.max_withdraw_value(ltv).unwrap();
+        assert_eq!(max_withdraw_value, Decimal::from(100u64));
+
+        // withdrawing all collateral would leave the borrow unbacked
+        assert!(obligation.deposited_value > max_withdraw_value);
+
+        // withdrawing the safe amount keeps the borrow within the new limit
+        let remaining_allowed = obligation
+            .deposited_value
+            .try_sub(max_withdraw_value)
+            .unwrap()
+            .try_mul(ltv)
+            .unwrap();
+        assert!(remaining_allowed >= obligation.borrowed_value);
+
+        // nothing can be withdrawn once the borrow limit is reached
+        let obligation = Obligation {
+            borrowed_value: Decimal::from(100u64),
+            ..obligation
+        };
+        assert_eq!(obligation.max_withdraw_value(ltv), Ok(Decimal::zero()));
+    }
+
+    #[test]
+    fn remaining_borrow_value() {
+        // 200 deposited at a 50% loan to value ratio, nothing borrowed
+        let obligation = Obligation {
+            deposited_value: Decimal::from(200u64),
+            allowed_borrow_value: Decimal::from(100u64),
+            ..Obligation::default()
+        };
+        assert_eq!(
+            obligation.remaining_borrow_value(),
+            Ok(Decimal::from(100u64))
+        );
+
+        let obligation = Obligation {
+            borrowed_value: Decimal::from(40u64),
+            ..obligation
+        };
+        assert_eq!(
+            obligation.remaining_borrow_value(),
+            Ok(Decimal::from(60u64))
+        );
+
+        // a maxed out obligation has no borrowing power left
+        let obligation = Obligation {
+            borrowed_value: Decimal::from(100u64),
+            ..obligation
+        };
+        assert_eq!(obligation.remaining_borrow_value(), Ok(Decimal::zero()));
+
+        // nor does one pushed past its limit by price moves
+        let obligation = Obligation {
+            borrowed_value: Decimal::from(150u64),
+            ..obligation
+        };
+        assert_eq!(obligation.remaining_borrow_value(), Ok(Decimal::zero()));
+    }
+
+    #[test]
+    fn realized_rate() {
+        let liquidity = ObligationLiquidity {
+            cumulative_borrow_rate_wads: Decimal::from(2u64),
+            ..ObligationLiquidity::new(Pubkey::new_unique())
+        };
+        assert_eq!(
+            liquidity.realized_rate(Decimal::from(2u64)),
+            Ok(Rate::one())
+        );
+        // the reserve rate grew by half since the snapshot
+        assert_eq!(
+            liquidity.realized_rate(Decimal::from(3u64)),
+            Ok(Rate::from_percent(150))
+        );
+        assert_eq!(
+            liquidity.realized_rate(Decimal::from(5u64)),
+            Ok(Rate::from_scaled_val(2_500_000_000_000_000_000))
+        );
+        assert_eq!(
+            liquidity.realized_rate(Decimal::one()),
+            Err(LendingError::NegativeInterestRate.into())
+        );
+
+        // accruing at the reserve rate applies the realized rate
+        let mut liquidity = ObligationLiquidity {
+            borrowed_amount_wads: Decimal::from(100u64),
+            ..liquidity
+        };
+        liquidity.accrue_interest(Decimal::from(3u64)).unwrap();
+        assert_eq!(liquidity.borrowed_amount_wads, Decimal::from(150u64));
+        assert_eq!(
+            liquidity.realized_rate(Decimal::from(3u64)),
+            Ok(Rate::one())
+        );
+    }
+
+    #[test]
+    fn liquidation_adjusted_collateral_value() {
+        let sol_reserve = Pubkey::new_unique();
+        let usdc_reserve = Pubkey::new_unique();
+        let thresholds = [(sol_reserve, 60), (usdc_reserve, 90)];
+
+        let mut obligation = Obligation::default();
+        assert_eq!(
+            obligation.liquidation_adjusted_collateral_value(&thresholds),
+            Ok(Decimal::zero())
+        );
+
+        // a single collateral at its own threshold
+        obligation.deposits.push(ObligationCollateral {
+            market_value: Decimal::from(100u64),
+            ..ObligationCollateral::new(sol_reserve)
+        });
+        assert_eq!(
+            obligation.liquidation_adjusted_collateral_value(&thresholds),
+            Ok(Decimal::from(60u64))
+        );
+
+        // each collateral is weighted by its own threshold
+        obligation.deposits.push(ObligationCollateral {
+            market_value: Decimal::from(200u64),
+            ..ObligationCollateral::new(usdc_reserve)
+        });
+        assert_eq!(
+            obligation.liquidation_adjusted_collateral_value(&thresholds),
+            Ok(Decimal::from(240u64))
+        );
+
+        // every deposit reserve needs a threshold
+        assert_eq!(
+            obligation.liquidation_adjusted_collateral_value(&thresholds[..1]),
+            Err(LendingError::InvalidObligationCollateral.into())
+        );
+    }
+
+    #[test]
+    fn bad_debt() {
+        assert_eq!(detect_bad_debt(&[]), Ok(Decimal::zero()));
+
+        let solvent = [
+            (Decimal::from(50u64), Decimal::from(100u64)),
+            (Decimal::from(100u64), Decimal::from(100u64)),
+            (Decimal::zero(), Decimal::zero()),
+        ];
+        assert_eq!(detect_bad_debt(&solvent), Ok(Decimal::zero()));
+
+        let insolvent = [
+            (Decimal::from(50u64), Decimal::from(100u64)),
+            (Decimal::from(120u64), Decimal::from(100u64)),
+            (Decimal::from(30u64), Decimal::zero()),
+        ];
+        assert_eq!(detect_bad_debt(&insolvent), Ok(Decimal::from(50u64)));
+
+        assert_eq!(
+            detect_bad_debt(&[
+                (Decimal::from(u64::MAX), Decimal::zero()),
+                (Decimal::from(u64::MAX), Decimal::zero()),
+            ]),
+            Decimal::from(u64::MAX).try_mul(2u64)
+        );
+    }
+
     #[test]
     fn obligation_accrue_interest_failure() {
         assert_eq!(
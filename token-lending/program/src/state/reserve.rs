@@ -127,6 +127,13 @@ impl Reserve {
         self.collateral.exchange_rate(total_liquidity)
     }
 
+    /// Maximum collateral that can be redeemed right now, limited by the
+    /// liquidity that is not lent out
+    pub fn max_redeemable_collateral(&self) -> Result<u64, ProgramError> {
+        self.collateral_exchange_rate()?
+            .liquidity_to_collateral(self.liquidity.withdrawable_amount())
+    }
+
     /// Update borrow rate and accrue interest
     pub fn accrue_interest(&mut self, current_slot: Slot) -> ProgramResult {
         let slots_elapsed = self.last_update.slots_elapsed(current_slot)?;
@@ -298,6 +305,55 @@ impl Reserve {
             withdraw_amount,
         })
     }
+
+    /// Largest repay amount accepted by a single liquidation, bounded by the
+    /// close factor and by the collateral available to seize
+    pub fn optimal_liquidation_repay(
+        &self,
+        obligation: &Obligation,
+        liquidity: &ObligationLiquidity,
+        collateral: &ObligationCollateral,
+    ) -> Result<u64, ProgramError> {
+        self.calculate_liquidation(u64::MAX, obligation, liquidity, collateral)
+            .map(|result| result.repay_amount)
+    }
+
+    /// Collateral value a borrower loses to the liquidation bonus, on top of
+    /// the value repaid, when `repay_value` of their borrow is liquidated
+    pub fn liquidation_penalty_value(&self, repay_value: Decimal) -> Result<Decimal, ProgramError> {
+        repay_value.try_mul(Rate::from_percent(self.config.liquidation_bonus))
+    }
+
+    /// Collateral value that must be deposited to borrow `borrow_value` in
+    /// quote currency at the given loan to value ratio, which must not be 0
+    pub fn required_collateral_value(
+        &self,
+        borrow_value: Decimal,
+        loan_to_value_ratio: u8,
+    ) -> Result<Decimal, ProgramError> {
+        if loan_to_value_ratio == 0 {
+            msg!("Collateral with a loan to value ratio of 0 cannot be borrowed against");
+            return Err(LendingError::InvalidConfig.into());
+        }
+        borrow_value.try_div(Rate::from_percent(loan_to_value_ratio))
+    }
+
+    /// Amount of this reserve's collateral tokens that must be deposited to
+    /// borrow `borrow_value` in quote currency, at the reserve's own loan to
+    /// value ratio, rounded up
+    pub fn required_collateral_amount(&self, borrow_value: Decimal) -> Result<u64, ProgramError> {
+        let collateral_value =
+            self.required_collateral_value(borrow_value, self.config.loan_to_value_ratio)?;
+        let decimals = 10u64
+            .checked_pow(self.liquidity.mint_decimals as u32)
+            .ok_or(LendingError::MathOverflow)?;
+        let liquidity_amount = collateral_value
+            .try_mul(decimals)?
+            .try_div(self.liquidity.market_price)?;
+        self.collateral_exchange_rate()?
+            .decimal_liquidity_to_collateral(liquidity_amount)?
+            .try_ceil_u64()
+    }
 }
 
 /// Initialize a reserve
@@ -392,6 +448,17 @@ impl ReserveLiquidity {
         Decimal::from(self.available_amount).try_add(self.borrowed_amount_wads)
     }
 
+    /// Liquidity that can be withdrawn, borrowed liquidity is excluded
+    pub fn withdrawable_amount(&self) -> u64 {
+        self.available_amount
+    }
+
+    /// Most liquidity that can be flash borrowed and repaid within one
+    /// instruction, borrowed liquidity is excluded
+    pub fn max_flash_loan(&self) -> u64 {
+        self.available_amount
+    }
+
     /// Add liquidity to available amount
     pub fn deposit(&mut self, liquidity_amount: u64) -> ProgramResult {
         self.available_amount = self
@@ -443,13 +510,30 @@ impl ReserveLiquidity {
         Ok(())
     }
 
+    /// Calculate the value of a liquidity amount in quote currency, at the
+    /// market price and scaled down by the liquidity mint decimals
+    pub fn market_value(&self, liquidity_amount_wads: Decimal) -> Result<Decimal, ProgramError> {
+        let decimals = 10u64
+            .checked_pow(self.mint_decimals as u32)
+            .ok_or(LendingError::MathOverflow)?;
+        liquidity_amount_wads
+            .try_mul(self.market_price)?
+            .try_div(decimals)
+    }
+
     /// Calculate the liquidity utilization rate of the reserve
     pub fn utilization_rate(&self) -> Result<Rate, ProgramError> {
+        self.utilization_rate_decimal()?.try_into()
+    }
+
+    /// Calculate the liquidity utilization rate of the reserve as a `Decimal`,
+    /// for reporting
+    pub fn utilization_rate_decimal(&self) -> Result<Decimal, ProgramError> {
         let total_supply = self.total_supply()?;
         if total_supply == Decimal::zero() {
-            return Ok(Rate::zero());
+            return Ok(Decimal::zero());
         }
-        self.borrowed_amount_wads.try_div(total_supply)?.try_into()
+        self.borrowed_amount_wads.try_div(total_supply)
     }
 
     /// Compound current borrow rate over elapsed slots
@@ -613,6 +697,9 @@ pub struct ReserveConfig {
     pub max_borrow_rate: u8,
     /// Program owner fees assessed, separate from gains due to interest accrual
     pub fees: ReserveFees,
+    /// Category of correlated assets, which may use the lending market's
+    /// elevated loan to value ratio for that category, 0 for none
+    pub emode_category: u8,
 }
 
 impl ReserveConfig {
@@ -657,9 +744,51 @@ impl ReserveConfig {
             msg!("Host fee percentage must be in range [0, 100]");
             return Err(LendingError::InvalidConfig.into());
         }
+        if usize::from(self.emode_category) > MAX_EMODE_CATEGORIES {
+            msg!("E-mode category must be in range [0, MAX_EMODE_CATEGORIES]");
+            return Err(LendingError::InvalidConfig.into());
+        }
 
         Ok(())
     }
+
+    /// Summarize the reserve configs as human-readable percentages, one per
+    /// line
+    pub fn to_display(&self) -> String {
+        [
+            format!(
+                "Optimal utilization rate: {}%",
+                self.optimal_utilization_rate
+            ),
+            format!("Loan to value ratio: {}%", self.loan_to_value_ratio),
+            format!("Liquidation bonus: {}%", self.liquidation_bonus),
+            format!("Liquidation threshold: {}%", self.liquidation_threshold),
+            format!("Min borrow rate: {}%", self.min_borrow_rate),
+            format!("Optimal borrow rate: {}%", self.optimal_borrow_rate),
+            format!("Max borrow rate: {}%", self.max_borrow_rate),
+            format!("Borrow fee: {}%", wad_to_percent(self.fees.borrow_fee_wad)),
+            format!(
+                "Flash loan fee: {}%",
+                wad_to_percent(self.fees.flash_loan_fee_wad)
+            ),
+            format!("Host fee: {}%", self.fees.host_fee_percentage),
+            format!("E-mode category: {}", self.emode_category),
+        ]
+        .join("\n")
+    }
+}
+
+/// Format a wad fraction as a percentage, without trailing zeros
+fn wad_to_percent(wad: u64) -> String {
+    const WAD_PER_PERCENT: u64 = WAD / 100;
+    let whole = wad / WAD_PER_PERCENT;
+    let fraction = wad % WAD_PER_PERCENT;
+    if fraction == 0 {
+        whole.to_string()
+    } else {
+        let fraction = format!("{:016}", fraction);
+        format!("{}.{}", whole, fraction.trim_end_matches('0'))
+    }
 }
 
 /// Additional fee information on a reserve
@@ -705,6 +834,27 @@ impl ReserveFees {
         )
     }
 
+    /// Split a total fee from `calculate_borrow_fees` or
+    /// `calculate_flash_loan_fees` into the owner and host payouts
+    ///
+    /// The host fee is part of the total fee. Without a host fee receiver it
+    /// is folded back into the owner fee, so the payouts always sum to the
+    /// total fee.
+    pub fn split_host_fee(
+        total_fee: u64,
+        host_fee: u64,
+        has_host_fee_receiver: bool,
+    ) -> Result<(u64, u64), ProgramError> {
+        if has_host_fee_receiver {
+            let owner_fee = total_fee
+                .checked_sub(host_fee)
+                .ok_or(LendingError::MathOverflow)?;
+            Ok((owner_fee, host_fee))
+        } else {
+            Ok((total_fee, 0))
+        }
+    }
+
     fn calculate_fees(
         &self,
         amount: Decimal,
@@ -771,7 +921,7 @@ impl IsInitialized for Reserve {
 }
 
 const RESERVE_LEN: usize = 571; // 1 + 8 + 1 + 32 + 32 + 1 + 32 + 32 + 32 + 8 + 16 + 16 + 16 + 32 + 8 + 32 + 1 +
-                                // 1 + 1 + 1 + 1 + 1 + 1 + 8 + 8 + 1 + 248
+                                // 1 + 1 + 1 + 1 + 1 + 1 + 8 + 8 + 1 + 1 + 247
 impl Pack for Reserve {
     const LEN: usize = RESERVE_LEN;
 
@@ -806,6 +956,7 @@ impl Pack for Reserve {
             config_fees_borrow_fee_wad,
             config_fees_flash_loan_fee_wad,
             config_fees_host_fee_percentage,
+            config_emode_category,
             _padding,
         ) = mut_array_refs![
             output,
@@ -835,7 +986,8 @@ impl Pack for Reserve {
             8,
             8,
             1,
-            248
+            1,
+            247
         ];
 
         // reserve
@@ -877,6 +1029,7 @@ impl Pack for Reserve {
         *config_fees_borrow_fee_wad = self.config.fees.borrow_fee_wad.to_le_bytes();
         *config_fees_flash_loan_fee_wad = self.config.fees.flash_loan_fee_wad.to_le_bytes();
         *config_fees_host_fee_percentage = self.config.fees.host_fee_percentage.to_le_bytes();
+        *config_emode_category = self.config.emode_category.to_le_bytes();
     }
 
     /// Unpacks a byte buffer into a [ReserveInfo](struct.ReserveInfo.html).
@@ -910,6 +1063,7 @@ impl Pack for Reserve {
             config_fees_borrow_fee_wad,
             config_fees_flash_loan_fee_wad,
             config_fees_host_fee_percentage,
+            config_emode_category,
             _padding,
         ) = array_refs![
             input,
@@ -939,7 +1093,8 @@ impl Pack for Reserve {
             8,
             8,
             1,
-            248
+            1,
+            247
         ];
 
         let version = u8::from_le_bytes(*version);
@@ -984,6 +1139,7 @@ impl Pack for Reserve {
                     flash_loan_fee_wad: u64::from_le_bytes(*config_fees_flash_loan_fee_wad),
                     host_fee_percentage: u8::from_le_bytes(*config_fees_host_fee_percentage),
                 },
+                emode_category: u8::from_le_bytes(*config_emode_category),
             },
         })
     }
@@ -1279,6 +1435,233 @@ mod test {
         }
     }
 
+    fn utilized_reserve(available_amount: u64, borrowed_amount: u64) -> Reserve {
+        Reserve {
+            liquidity: ReserveLiquidity {
+                available_amount,
+                borrowed_amount_wads: Decimal::from(borrowed_amount),
+                ..ReserveLiquidity::default()
+            },
+            collateral: ReserveCollateral {
+                mint_total_supply: 50,
+                ..ReserveCollateral::default()
+            },
+            ..Reserve::default()
+        }
+    }
+
+    #[test]
+    fn utilization_rate_decimal() {
+        assert_eq!(
+            utilized_reserve(0, 0)
+                .liquidity
+                .utilization_rate_decimal()
+                .unwrap(),
+            Decimal::zero()
+        );
+
+        // 1/3 does not terminate, both keep the same 18 decimal places
+        let liquidity = utilized_reserve(200, 100).liquidity;
+        let decimal = liquidity.utilization_rate_decimal().unwrap();
+        let rate = liquidity.utilization_rate().unwrap();
+        assert_eq!(decimal.to_string(), "0.333333333333333333");
+        assert_eq!(decimal.to_scaled_val().unwrap(), rate.to_scaled_val());
+    }
+
+    #[test]
+    fn withdrawable_fully_utilized() {
+        let reserve = utilized_reserve(0, 100);
+        assert_eq!(reserve.liquidity.withdrawable_amount(), 0);
+        assert_eq!(reserve.max_redeemable_collateral().unwrap(), 0);
+    }
+
+    #[test]
+    fn max_flash_loan_excludes_borrows() {
+        assert_eq!(utilized_reserve(40, 0).liquidity.max_flash_loan(), 40);
+        assert_eq!(utilized_reserve(40, 60).liquidity.max_flash_loan(), 40);
+        assert_eq!(utilized_reserve(0, 100).liquidity.max_flash_loan(), 0);
+    }
+
+    #[test]
+    fn withdrawable_partially_utilized() {
+        // 50 collateral tokens back 100 liquidity tokens, 2 liquidity per collateral
+        let reserve = utilized_reserve(40, 60);
+        assert_eq!(reserve.liquidity.withdrawable_amount(), 40);
+        assert_eq!(reserve.max_redeemable_collateral().unwrap(), 20);
+        assert_eq!(
+            reserve
+                .collateral_exchange_rate()
+                .unwrap()
+                .collateral_to_liquidity(20)
+                .unwrap(),
+            40
+        );
+    }
+
+    fn liquidation_reserve() -> Reserve {
+        Reserve {
+            config: ReserveConfig {
+                liquidation_bonus: 5,
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        }
+    }
+
+    fn unhealthy_position(
+        borrowed_amount: u64,
+        collateral_amount: u64,
+    ) -> (Obligation, ObligationLiquidity, ObligationCollateral) {
+        let liquidity = ObligationLiquidity {
+            borrowed_amount_wads: Decimal::from(borrowed_amount),
+            market_value: Decimal::from(borrowed_amount),
+            ..ObligationLiquidity::default()
+        };
+        let collateral = ObligationCollateral {
+            deposited_amount: collateral_amount,
+            market_value: Decimal::from(collateral_amount),
+            ..ObligationCollateral::default()
+        };
+        let obligation = Obligation {
+            deposits: vec![collateral.clone()],
+            borrows: vec![liquidity.clone()],
+            deposited_value: collateral.market_value,
+            borrowed_value: liquidity.market_value,
+            ..Obligation::default()
+        };
+        (obligation, liquidity, collateral)
+    }
+
+    #[test]
+    fn optimal_liquidation_repay_close_factor() {
+        let reserve = liquidation_reserve();
+
+        // close factor limits the repay to half of the borrow
+        let (obligation, liquidity, collateral) = unhealthy_position(100, 1_000);
+        assert_eq!(
+            reserve
+                .optimal_liquidation_repay(&obligation, &liquidity, &collateral)
+                .unwrap(),
+            50
+        );
+
+        // collateral worth 21 covers 20 repaid at a 5% bonus
+        let (obligation, liquidity, collateral) = unhealthy_position(100, 21);
+        assert_eq!(
+            reserve
+                .optimal_liquidation_repay(&obligation, &liquidity, &collateral)
+                .unwrap(),
+            20
+        );
+    }
+
+    #[test]
+    fn liquidation_close_factor_cap() {
+        let reserve = liquidation_reserve();
+
+        // a liquidator asking to repay 80% only settles half of the borrow
+        let (obligation, liquidity, collateral) = unhealthy_position(1_000_000, 10_000_000);
+        let result = reserve
+            .calculate_liquidation(800_000, &obligation, &liquidity, &collateral)
+            .unwrap();
+        assert_eq!(result.settle_amount, Decimal::from(500_000u64));
+        assert_eq!(result.repay_amount, 500_000);
+
+        // the cap applies per borrow, even when the obligation's other borrows
+        // make its total borrowed value much larger
+        let obligation = Obligation {
+            borrowed_value: Decimal::from(10_000_000u64),
+            ..obligation
+        };
+        assert_eq!(
+            obligation.max_liquidation_amount(&liquidity).unwrap(),
+            Decimal::from(500_000u64)
+        );
+    }
+
+    #[test]
+    fn optimal_liquidation_repay_close_out() {
+        let reserve = liquidation_reserve();
+
+        // borrows below the close amount are repaid in full
+        let (obligation, liquidity, collateral) =
+            unhealthy_position(LIQUIDATION_CLOSE_AMOUNT - 1, 1_000);
+        let repay = reserve
+            .optimal_liquidation_repay(&obligation, &liquidity, &collateral)
+            .unwrap();
+        assert_eq!(repay, LIQUIDATION_CLOSE_AMOUNT - 1);
+        assert_eq!(
+            reserve
+                .calculate_liquidation(u64::MAX, &obligation, &liquidity, &collateral)
+                .unwrap()
+                .settle_amount,
+            liquidity.borrowed_amount_wads
+        );
+    }
+
+    #[test]
+    fn reserve_config_display() {
+        let config = ReserveConfig {
+            optimal_utilization_rate: 80,
+            loan_to_value_ratio: 50,
+            liquidation_bonus: 5,
+            liquidation_threshold: 55,
+            min_borrow_rate: 0,
+            optimal_borrow_rate: 4,
+            max_borrow_rate: 30,
+            fees: ReserveFees {
+                borrow_fee_wad: 100_000_000_000,           // 0.00001%
+                flash_loan_fee_wad: 3_000_000_000_000_000, // 0.3%
+                host_fee_percentage: 20,
+            },
+            emode_category: 0,
+        };
+        assert_eq!(
+            config.to_display(),
+            "Optimal utilization rate: 80%\n\
+             Loan to value ratio: 50%\n\
+             Liquidation bonus: 5%\n\
+             Liquidation threshold: 55%\n\
+             Min borrow rate: 0%\n\
+             Optimal borrow rate: 4%\n\
+             Max borrow rate: 30%\n\
+             Borrow fee: 0.00001%\n\
+             Flash loan fee: 0.3%\n\
+             Host fee: 20%\n\
+             E-mode category: 0"
+        );
+
+        assert_eq!(wad_to_percent(0), "0");
+        assert_eq!(wad_to_percent(10_000_000_000_000_000), "1");
+        assert_eq!(wad_to_percent(WAD), "100");
+        assert_eq!(wad_to_percent(125_000_000_000_000_000), "12.5");
+    }
+
+    #[test]
+    fn pack_reserve_emode_category() {
+        let reserve = Reserve {
+            version: PROGRAM_VERSION,
+            config: ReserveConfig {
+                loan_to_value_ratio: 75,
+                liquidation_threshold: 80,
+                emode_category: 3,
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        };
+        let mut packed = [0u8; Reserve::LEN];
+        Reserve::pack(reserve.clone(), &mut packed).unwrap();
+        assert_eq!(Reserve::unpack(&packed).unwrap(), reserve);
+
+        let mut config = reserve.config;
+        config.validate().unwrap();
+        config.emode_category = MAX_EMODE_CATEGORIES as u8 + 1;
+        assert_eq!(
+            config.validate().unwrap_err(),
+            LendingError::InvalidConfig.into()
+        );
+    }
+
     #[test]
     fn borrow_fee_calculation_min_host() {
         let fees = ReserveFees {
@@ -1367,4 +1750,169 @@ mod test {
         assert_eq!(total_fee, 10); // 1% of 1000
         assert_eq!(host_fee, 0); // 0 host fee
     }
+
+    #[test]
+    fn split_host_fee_conserves_total() {
+        let fees = ReserveFees {
+            borrow_fee_wad: 10_000_000_000_000_000, // 1%
+            flash_loan_fee_wad: 0,
+            host_fee_percentage: 20,
+        };
+        let (total_fee, host_fee) = fees
+            .calculate_borrow_fees(Decimal::from(1000u64), FeeCalculation::Exclusive)
+            .unwrap();
+
+        // host receiver present, host takes its share
+        let (owner_fee, host_payout) =
+            ReserveFees::split_host_fee(total_fee, host_fee, true).unwrap();
+        assert_eq!(owner_fee, 8);
+        assert_eq!(host_payout, 2);
+        assert_eq!(owner_fee + host_payout, total_fee);
+
+        // host receiver absent, owner keeps the host share
+        let (owner_fee, host_payout) =
+            ReserveFees::split_host_fee(total_fee, host_fee, false).unwrap();
+        assert_eq!(owner_fee, total_fee);
+        assert_eq!(host_payout, 0);
+
+        // host fee can never exceed the total
+        assert_eq!(
+            ReserveFees::split_host_fee(1, 2, true).unwrap_err(),
+            LendingError::MathOverflow.into()
+        );
+    }
+
+    #[test]
+    fn liquidity_market_value() {
+        let mut liquidity = ReserveLiquidity {
+            mint_decimals: 6,
+            market_price: Decimal::from(2u64),
+            ..ReserveLiquidity::default()
+        };
+        assert_eq!(
+            liquidity.market_value(Decimal::zero()).unwrap(),
+            Decimal::zero()
+        );
+        assert_eq!(
+            liquidity.market_value(Decimal::from(1_500_000u64)).unwrap(),
+            Decimal::from(3u64)
+        );
+
+        liquidity.mint_decimals = 9;
+        liquidity.market_price = Decimal::from(20u64);
+        assert_eq!(
+            liquidity
+                .market_value(Decimal::from(250_000_000u64))
+                .unwrap(),
+            Decimal::from(5u64)
+        );
+        // fractional amounts in wads keep their precision
+        assert_eq!(
+            liquidity
+                .market_value(Decimal::from_scaled_val(WAD as u128 / 2))
+                .unwrap(),
+            Decimal::from_scaled_val(10 * WAD as u128 / 1_000_000_000)
+        );
+    }
+
+    #[test]
+    fn required_collateral_value() {
+        let reserve = Reserve::default();
+        assert_eq!(
+            reserve
+                .required_collateral_value(Decimal::from(100u64), 50)
+                .unwrap(),
+            Decimal::from(200u64)
+        );
+        assert_eq!(
+            reserve
+                .required_collateral_value(Decimal::from(100u64), 100)
+                .unwrap(),
+            Decimal::from(100u64)
+        );
+        assert_eq!(
+            reserve
+                .required_collateral_value(Decimal::from(100u64), 0)
+                .unwrap_err(),
+            LendingError::InvalidConfig.into()
+        );
+    }
+
+    #[test]
+    fn required_collateral_amount() {
+        let mut reserve = Reserve {
+            liquidity: ReserveLiquidity {
+                mint_decimals: 6,
+                market_price: Decimal::from(2u64),
+                available_amount: 1_000_000,
+                ..ReserveLiquidity::default()
+            },
+            collateral: ReserveCollateral {
+                mint_total_supply: 1_000_000,
+                ..ReserveCollateral::default()
+            },
+            config: ReserveConfig {
+                loan_to_value_ratio: 50,
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        };
+        // 200 of value is 100 tokens at a price of 2, one collateral token each
+        assert_eq!(
+            reserve
+                .required_collateral_amount(Decimal::from(100u64))
+                .unwrap(),
+            100_000_000
+        );
+
+        // once each collateral token is worth two liquidity tokens, half as
+        // many are needed
+        reserve.collateral.mint_total_supply = 500_000;
+        assert_eq!(
+            reserve
+                .required_collateral_amount(Decimal::from(100u64))
+                .unwrap(),
+            50_000_000
+        );
+
+        // partial collateral tokens round up
+        assert_eq!(
+            reserve
+                .required_collateral_amount(Decimal::from_scaled_val(WAD as u128 / 1_000_000))
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn liquidation_penalty_value() {
+        let mut reserve = Reserve {
+            config: ReserveConfig {
+                liquidation_bonus: 5,
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        };
+        let repay_value = Decimal::from(200u64);
+        assert_eq!(
+            reserve.liquidation_penalty_value(repay_value).unwrap(),
+            Decimal::from(10u64)
+        );
+        assert_eq!(
+            reserve.liquidation_penalty_value(Decimal::zero()).unwrap(),
+            Decimal::zero()
+        );
+
+        reserve.config.liquidation_bonus = 10;
+        assert_eq!(
+            reserve.liquidation_penalty_value(repay_value).unwrap(),
+            Decimal::from(20u64)
+        );
+        assert_eq!(
+            reserve
+                .liquidation_penalty_value(Decimal::from_percent(50))
+                .unwrap(),
+            Decimal::from_percent(5)
+        );
+    }
 }
@@ -54,6 +54,7 @@ pub const TEST_RESERVE_CONFIG: ReserveConfig = ReserveConfig {
         flash_loan_fee_wad: 3_000_000_000_000_000,
         host_fee_percentage: 20,
     },
+    emode_category: 0,
 };
 
 pub const SOL_PYTH_PRODUCT: &str = "3Mnn2fX6rQyUsyELYms1sBJyChWofzSNRoqYzvgMVz5E";
@@ -89,6 +90,13 @@ impl AddPacked for ProgramTest {
 }
 
 pub fn add_lending_market(test: &mut ProgramTest) -> TestLendingMarket {
+    add_lending_market_with_min_borrow_value(test, 0)
+}
+
+pub fn add_lending_market_with_min_borrow_value(
+    test: &mut ProgramTest,
+    min_borrow_value: u64,
+) -> TestLendingMarket {
     let lending_market_pubkey = Pubkey::new_unique();
     let (lending_market_authority, bump_seed) =
         Pubkey::find_program_address(&[lending_market_pubkey.as_ref()], &spl_token_lending::id());
@@ -108,6 +116,7 @@ pub fn add_lending_market(test: &mut ProgramTest) -> TestLendingMarket {
             quote_currency: QUOTE_CURRENCY,
             token_program_id: spl_token::id(),
             oracle_program_id,
+            min_borrow_value,
         }),
         &spl_token_lending::id(),
     );
@@ -499,6 +508,7 @@ impl TestLendingMarket {
                     spl_token_lending::id(),
                     lending_market_owner.pubkey(),
                     QUOTE_CURRENCY,
+                    0,
                     lending_market_pubkey,
                     oracle_program_id,
                 ),
@@ -613,6 +623,7 @@ impl TestLendingMarket {
                 liquidate_obligation(
                     spl_token_lending::id(),
                     liquidity_amount,
+                    0,
                     repay_reserve.user_liquidity_pubkey,
                     withdraw_reserve.user_collateral_pubkey,
                     repay_reserve.pubkey,
@@ -50,6 +50,7 @@ async fn test_already_initialized() {
             spl_token_lending::id(),
             existing_market.owner.pubkey(),
             existing_market.quote_currency,
+            0,
             existing_market.pubkey,
             existing_market.oracle_program_id,
         )],
@@ -7,11 +7,13 @@ use {
     helpers::*,
     solana_program_test::*,
     solana_sdk::{
+        instruction::InstructionError,
         signature::{Keypair, Signer},
-        transaction::Transaction,
+        transaction::{Transaction, TransactionError},
     },
     spl_token::instruction::approve,
     spl_token_lending::{
+        error::LendingError,
         instruction::{liquidate_obligation, refresh_obligation},
         processor::process_instruction,
         state::INITIAL_COLLATERAL_RATIO,
@@ -107,6 +109,7 @@ async fn test_success() {
     let initial_collateral_supply_balance =
         get_token_balance(&mut banks_client, sol_test_reserve.collateral_supply_pubkey).await;
 
+    // asking for one more collateral token than the liquidation yields fails
     let mut transaction = Transaction::new_with_payer(
         &[
             approve(
@@ -126,6 +129,57 @@ async fn test_success() {
             liquidate_obligation(
                 spl_token_lending::id(),
                 USDC_LIQUIDATION_AMOUNT_FRACTIONAL,
+                SOL_LIQUIDATION_AMOUNT_LAMPORTS + 1,
+                usdc_test_reserve.user_liquidity_pubkey,
+                sol_test_reserve.user_collateral_pubkey,
+                usdc_test_reserve.pubkey,
+                usdc_test_reserve.liquidity_supply_pubkey,
+                sol_test_reserve.pubkey,
+                sol_test_reserve.collateral_supply_pubkey,
+                test_obligation.pubkey,
+                lending_market.pubkey,
+                user_transfer_authority.pubkey(),
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(
+        &[&payer, &user_accounts_owner, &user_transfer_authority],
+        recent_blockhash,
+    );
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            2,
+            InstructionError::Custom(LendingError::ExceededSlippage as u32)
+        )
+    );
+
+    // exactly the yielded amount succeeds
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            approve(
+                &spl_token::id(),
+                &usdc_test_reserve.user_liquidity_pubkey,
+                &user_transfer_authority.pubkey(),
+                &user_accounts_owner.pubkey(),
+                &[],
+                USDC_LIQUIDATION_AMOUNT_FRACTIONAL,
+            )
+            .unwrap(),
+            refresh_obligation(
+                spl_token_lending::id(),
+                test_obligation.pubkey,
+                vec![sol_test_reserve.pubkey, usdc_test_reserve.pubkey],
+            ),
+            liquidate_obligation(
+                spl_token_lending::id(),
+                USDC_LIQUIDATION_AMOUNT_FRACTIONAL,
+                SOL_LIQUIDATION_AMOUNT_LAMPORTS,
                 usdc_test_reserve.user_liquidity_pubkey,
                 sol_test_reserve.user_collateral_pubkey,
                 usdc_test_reserve.pubkey,
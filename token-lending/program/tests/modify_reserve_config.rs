@@ -74,6 +74,7 @@ async fn modify_reserve_config_success() {
             flash_loan_fee_wad: 3_000_000_000_000_000,
             host_fee_percentage: 20,
         },
+        emode_category: 0,
     };
 
     let mut transaction = Transaction::new_with_payer(
@@ -155,6 +156,7 @@ async fn wrong_signer_of_lending_market_cannot_change_reserve_config() {
             flash_loan_fee_wad: 3_000_000_000_000_000,
             host_fee_percentage: 20,
         },
+        emode_category: 0,
     };
 
     let mut instruction = modify_reserve_config(
@@ -269,6 +271,7 @@ async fn owner_of_different_lending_market_cannot_change_reserve_config() {
             flash_loan_fee_wad: 3_000_000_000_000_000,
             host_fee_percentage: 20,
         },
+        emode_category: 0,
     };
 
     let mut transaction = Transaction::new_with_payer(
@@ -359,6 +362,7 @@ async fn correct_owner_providing_wrong_lending_market_fails() {
             flash_loan_fee_wad: 3_000_000_000_000_000,
             host_fee_percentage: 20,
         },
+        emode_category: 0,
     };
 
     let mut transaction = Transaction::new_with_payer(
@@ -0,0 +1,308 @@
+#![allow(clippy::arithmetic_side_effects)]
+#![cfg(feature = "test-sbf")]
+
+mod helpers;
+
+use {
+    helpers::*,
+    solana_program::pubkey::Pubkey,
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+    spl_token_lending::{
+        error::LendingError,
+        instruction::{refresh_reserve, update_reserve_price},
+        math::{Decimal, TryDiv},
+        processor::process_instruction,
+    },
+};
+
+const USDC_RESERVE_LIQUIDITY_FRACTIONAL: u64 = 100 * FRACTIONAL_TO_USDC;
+
+fn fixed_price_oracle() -> TestOracle {
+    TestOracle {
+        product_pubkey: Pubkey::default(),
+        price_pubkey: Pubkey::default(),
+        price: Decimal::from(1u64),
+    }
+}
+
+#[tokio::test]
+async fn test_success() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    test.set_compute_max_units(30_000);
+
+    let user_accounts_owner = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+    let usdc_mint = add_usdc_mint(&mut test);
+    let usdc_test_reserve = add_reserve(
+        &mut test,
+        &lending_market,
+        &fixed_price_oracle(),
+        &user_accounts_owner,
+        AddReserveArgs {
+            liquidity_amount: USDC_RESERVE_LIQUIDITY_FRACTIONAL,
+            liquidity_mint_decimals: usdc_mint.decimals,
+            liquidity_mint_pubkey: usdc_mint.pubkey,
+            config: TEST_RESERVE_CONFIG,
+            mark_fresh: true,
+            ..AddReserveArgs::default()
+        },
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    // a fractional price of 2.5
+    let new_price = Decimal::from(5u64).try_div(2).unwrap();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[update_reserve_price(
+            spl_token_lending::id(),
+            new_price.to_scaled_val().unwrap(),
+            usdc_test_reserve.pubkey,
+            lending_market.pubkey,
+            lending_market.owner.pubkey(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &lending_market.owner], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    let reserve = usdc_test_reserve.get_state(&mut banks_client).await;
+    assert_eq!(reserve.liquidity.market_price, new_price);
+    assert!(reserve.last_update.stale);
+
+    let mut transaction = Transaction::new_with_payer(
+        &[refresh_reserve(
+            spl_token_lending::id(),
+            usdc_test_reserve.pubkey,
+            Pubkey::default(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    let reserve = usdc_test_reserve.get_state(&mut banks_client).await;
+    assert_eq!(reserve.liquidity.market_price, new_price);
+    assert!(!reserve.last_update.stale);
+}
+
+#[tokio::test]
+async fn test_non_owner_cannot_update_price() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    test.set_compute_max_units(30_000);
+
+    let user_accounts_owner = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+    let usdc_mint = add_usdc_mint(&mut test);
+    let usdc_test_reserve = add_reserve(
+        &mut test,
+        &lending_market,
+        &fixed_price_oracle(),
+        &user_accounts_owner,
+        AddReserveArgs {
+            liquidity_amount: USDC_RESERVE_LIQUIDITY_FRACTIONAL,
+            liquidity_mint_decimals: usdc_mint.decimals,
+            liquidity_mint_pubkey: usdc_mint.pubkey,
+            config: TEST_RESERVE_CONFIG,
+            mark_fresh: true,
+            ..AddReserveArgs::default()
+        },
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let not_owner = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[update_reserve_price(
+            spl_token_lending::id(),
+            Decimal::from(2u64).to_scaled_val().unwrap(),
+            usdc_test_reserve.pubkey,
+            lending_market.pubkey,
+            not_owner.pubkey(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &not_owner], recent_blockhash);
+
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketOwner as u32)
+        )
+    );
+
+    let reserve = usdc_test_reserve.get_state(&mut banks_client).await;
+    assert_eq!(reserve.liquidity.market_price, Decimal::from(1u64));
+}
+
+#[tokio::test]
+async fn test_reserve_with_oracle_cannot_update_price() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    test.set_compute_max_units(30_000);
+
+    let user_accounts_owner = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+    let usdc_mint = add_usdc_mint(&mut test);
+    let usdc_oracle = add_usdc_oracle(&mut test);
+    let usdc_test_reserve = add_reserve(
+        &mut test,
+        &lending_market,
+        &usdc_oracle,
+        &user_accounts_owner,
+        AddReserveArgs {
+            liquidity_amount: USDC_RESERVE_LIQUIDITY_FRACTIONAL,
+            liquidity_mint_decimals: usdc_mint.decimals,
+            liquidity_mint_pubkey: usdc_mint.pubkey,
+            config: TEST_RESERVE_CONFIG,
+            mark_fresh: true,
+            ..AddReserveArgs::default()
+        },
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[update_reserve_price(
+            spl_token_lending::id(),
+            Decimal::from(2u64).to_scaled_val().unwrap(),
+            usdc_test_reserve.pubkey,
+            lending_market.pubkey,
+            lending_market.owner.pubkey(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &lending_market.owner], recent_blockhash);
+
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidOracleConfig as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_init_reserve_without_oracle() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    let user_accounts_owner = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+    let usdc_mint = add_usdc_mint(&mut test);
+
+    let (mut banks_client, payer, _recent_blockhash) = test.start().await;
+
+    let usdc_user_liquidity_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        usdc_mint.pubkey,
+        Some(&usdc_mint.authority),
+        &payer,
+        user_accounts_owner.pubkey(),
+        USDC_RESERVE_LIQUIDITY_FRACTIONAL,
+    )
+    .await;
+
+    let usdc_reserve = TestReserve::init(
+        "usdc".to_owned(),
+        &mut banks_client,
+        &lending_market,
+        &TestOracle {
+            product_pubkey: Pubkey::default(),
+            price_pubkey: Pubkey::default(),
+            price: Decimal::zero(),
+        },
+        USDC_RESERVE_LIQUIDITY_FRACTIONAL,
+        TEST_RESERVE_CONFIG,
+        usdc_mint.pubkey,
+        usdc_user_liquidity_account,
+        &payer,
+        &user_accounts_owner,
+    )
+    .await
+    .unwrap();
+
+    let reserve = usdc_reserve.get_state(&mut banks_client).await;
+    assert_eq!(reserve.liquidity.oracle_pubkey, Pubkey::default());
+    assert_eq!(reserve.liquidity.market_price, Decimal::zero());
+    assert!(reserve.last_update.stale);
+
+    // unpriced, so it can't be refreshed yet
+    let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[refresh_reserve(
+            spl_token_lending::id(),
+            usdc_reserve.pubkey,
+            Pubkey::default(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidOracleConfig as u32)
+        )
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            update_reserve_price(
+                spl_token_lending::id(),
+                Decimal::one().to_scaled_val().unwrap(),
+                usdc_reserve.pubkey,
+                lending_market.pubkey,
+                lending_market.owner.pubkey(),
+            ),
+            refresh_reserve(
+                spl_token_lending::id(),
+                usdc_reserve.pubkey,
+                Pubkey::default(),
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &lending_market.owner], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    let reserve = usdc_reserve.get_state(&mut banks_client).await;
+    assert_eq!(reserve.liquidity.market_price, Decimal::one());
+    assert!(!reserve.last_update.stale);
+}
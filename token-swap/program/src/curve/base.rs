@@ -3,12 +3,18 @@
 #[cfg(feature = "fuzz")]
 use arbitrary::Arbitrary;
 use {
-    crate::curve::{
-        calculator::{CurveCalculator, RoundDirection, SwapWithoutFeesResult, TradeDirection},
-        constant_price::ConstantPriceCurve,
-        constant_product::ConstantProductCurve,
-        fees::Fees,
-        offset::OffsetCurve,
+    crate::{
+        curve::{
+            calculator::{
+                CurveCalculator, RoundDirection, SwapWithoutFeesResult, TradeDirection,
+                TradingTokenResult,
+            },
+            constant_price::ConstantPriceCurve,
+            constant_product::ConstantProductCurve,
+            fees::Fees,
+            offset::OffsetCurve,
+        },
+        error::SwapError,
     },
     arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
     solana_program::{
@@ -105,6 +111,136 @@ impl SwapCurve {
         })
     }
 
+    /// Calculate how much source token, including fees, must be provided to
+    /// receive at least `destination_amount` of destination token, returning
+    /// the result of swapping that amount.
+    ///
+    /// The curve is inverted for the amount after fees, which is grossed up
+    /// by the trading fees. The fees are rounded on their own and charge at
+    /// least one token each, so the gross amount is topped up until it covers
+    /// them.
+    pub fn swap_exact_output(
+        &self,
+        destination_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+        fees: &Fees,
+    ) -> Option<SwapResult> {
+        let source_amount_less_fees = self.calculator.source_amount_for_exact_output(
+            destination_amount,
+            swap_source_amount,
+            swap_destination_amount,
+            trade_direction,
+        )?;
+        let mut source_amount = fees.pre_trading_fee_amount(source_amount_less_fees)?;
+        loop {
+            let total_fees = fees
+                .trading_fee(source_amount)?
+                .checked_add(fees.owner_trading_fee(source_amount)?)?;
+            let shortfall =
+                source_amount_less_fees.saturating_sub(source_amount.saturating_sub(total_fees));
+            if shortfall == 0 {
+                break;
+            }
+            source_amount = source_amount.checked_add(shortfall)?;
+        }
+        self.swap(
+            source_amount,
+            swap_source_amount,
+            swap_destination_amount,
+            trade_direction,
+            fees,
+        )
+    }
+
+    /// Get the reserve ratio after swapping `amount_in` into the pool, as
+    /// `(new_reserve_in, new_reserve_out)`. The price of the destination
+    /// token, in source tokens, is `new_reserve_in / new_reserve_out`.
+    pub fn marginal_price_after(
+        &self,
+        reserve_in: u64,
+        reserve_out: u64,
+        amount_in: u64,
+        trade_direction: TradeDirection,
+        fees: &Fees,
+    ) -> Option<(u64, u64)> {
+        if amount_in == 0 {
+            return Some((reserve_in, reserve_out));
+        }
+        let result = self.swap(
+            u128::from(amount_in),
+            u128::from(reserve_in),
+            u128::from(reserve_out),
+            trade_direction,
+            fees,
+        )?;
+        Some((
+            u64::try_from(result.new_swap_source_amount).ok()?,
+            u64::try_from(result.new_swap_destination_amount).ok()?,
+        ))
+    }
+
+    /// Replay a sequence of swaps against the reserves `(token_a, token_b)`,
+    /// returning the reserves after the last one. Each swap is given as
+    /// `(a_to_b, source_amount)`, with the source amount including fees.
+    ///
+    /// Returns `None` if any swap fails, e.g. on overflow or when it would
+    /// produce no destination tokens.
+    pub fn apply_swaps(
+        &self,
+        reserves: (u64, u64),
+        swaps: &[(bool, u64)],
+        fees: &Fees,
+    ) -> Option<(u64, u64)> {
+        swaps
+            .iter()
+            .try_fold(reserves, |(token_a, token_b), &(a_to_b, source_amount)| {
+                let (trade_direction, swap_source_amount, swap_destination_amount) = if a_to_b {
+                    (TradeDirection::AtoB, token_a, token_b)
+                } else {
+                    (TradeDirection::BtoA, token_b, token_a)
+                };
+                let result = self.swap(
+                    u128::from(source_amount),
+                    u128::from(swap_source_amount),
+                    u128::from(swap_destination_amount),
+                    trade_direction,
+                    fees,
+                )?;
+                let new_source = u64::try_from(result.new_swap_source_amount).ok()?;
+                let new_destination = u64::try_from(result.new_swap_destination_amount).ok()?;
+                Some(if a_to_b {
+                    (new_source, new_destination)
+                } else {
+                    (new_destination, new_source)
+                })
+            })
+    }
+
+    /// Get the amounts of token A and B backing `pool_tokens` pool tokens,
+    /// rounded down, as a read-only quote for valuing pool token positions
+    pub fn quote_pool_token(
+        &self,
+        pool_tokens: u128,
+        pool_token_supply: u128,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Result<TradingTokenResult, SwapError> {
+        if pool_token_supply == 0 {
+            return Err(SwapError::EmptySupply);
+        }
+        self.calculator
+            .pool_tokens_to_trading_tokens(
+                pool_tokens,
+                pool_token_supply,
+                swap_token_a_amount,
+                swap_token_b_amount,
+                RoundDirection::Floor,
+            )
+            .ok_or(SwapError::CalculationFailure)
+    }
+
     /// Get the amount of pool tokens for the deposited amount of token A or B
     pub fn deposit_single_token_type(
         &self,
@@ -287,6 +423,164 @@ mod test {
         assert_eq!(swap_curve, unpacked);
     }
 
+    #[test]
+    fn marginal_price_after_large_swap() {
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            ..Fees::default()
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let reserve_in = 1_000_000;
+        let reserve_out = 2_000_000;
+
+        // an empty swap leaves the spot price untouched
+        assert_eq!(
+            swap_curve
+                .marginal_price_after(reserve_in, reserve_out, 0, TradeDirection::AtoB, &fees)
+                .unwrap(),
+            (reserve_in, reserve_out)
+        );
+
+        let (small_in, small_out) = swap_curve
+            .marginal_price_after(reserve_in, reserve_out, 1_000, TradeDirection::AtoB, &fees)
+            .unwrap();
+        let (large_in, large_out) = swap_curve
+            .marginal_price_after(
+                reserve_in,
+                reserve_out,
+                500_000,
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        assert!(large_in <= reserve_in + 500_000);
+
+        // the destination token becomes more expensive, more so after a large
+        // swap: compare in / out ratios by cross-multiplying
+        let spot = u128::from(reserve_in) * u128::from(small_out);
+        assert!(u128::from(small_in) * u128::from(reserve_out) > spot);
+        assert!(
+            u128::from(large_in) * u128::from(small_out)
+                > u128::from(small_in) * u128::from(large_out)
+        );
+
+        // post-swap reserves that do not fit in a u64 are rejected
+        assert!(swap_curve
+            .marginal_price_after(u64::MAX, reserve_out, 1, TradeDirection::AtoB, &fees)
+            .is_none());
+    }
+
+    #[test]
+    fn apply_swaps_matches_manual_replay() {
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            ..Fees::default()
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let reserves = (1_000_000u64, 2_000_000u64);
+        let swaps = [
+            (true, 10_000),
+            (false, 55_555),
+            (true, 100),
+            (false, 300_000),
+        ];
+
+        let mut token_a = u128::from(reserves.0);
+        let mut token_b = u128::from(reserves.1);
+        for &(a_to_b, source_amount) in &swaps {
+            if a_to_b {
+                let result = swap_curve
+                    .swap(
+                        u128::from(source_amount),
+                        token_a,
+                        token_b,
+                        TradeDirection::AtoB,
+                        &fees,
+                    )
+                    .unwrap();
+                token_a = result.new_swap_source_amount;
+                token_b = result.new_swap_destination_amount;
+            } else {
+                let result = swap_curve
+                    .swap(
+                        u128::from(source_amount),
+                        token_b,
+                        token_a,
+                        TradeDirection::BtoA,
+                        &fees,
+                    )
+                    .unwrap();
+                token_b = result.new_swap_source_amount;
+                token_a = result.new_swap_destination_amount;
+            }
+        }
+        assert_eq!(
+            swap_curve.apply_swaps(reserves, &swaps, &fees).unwrap(),
+            (token_a as u64, token_b as u64)
+        );
+
+        // no swaps leaves the reserves untouched
+        assert_eq!(
+            swap_curve.apply_swaps(reserves, &[], &fees).unwrap(),
+            reserves
+        );
+
+        // any failing swap short-circuits the replay
+        assert!(swap_curve
+            .apply_swaps(reserves, &[(true, 10_000), (true, u64::MAX)], &fees)
+            .is_none());
+    }
+
+    #[test]
+    fn quote_pool_token() {
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        assert_eq!(
+            swap_curve.quote_pool_token(1, 0, 0, 0),
+            Err(SwapError::EmptySupply)
+        );
+        assert_eq!(
+            swap_curve.quote_pool_token(1, 0, 1_000, 2_000),
+            Err(SwapError::EmptySupply)
+        );
+
+        let pool_token_supply = 1_000_000_000;
+        let one_pool_token = 1_000_000;
+        assert_eq!(
+            swap_curve
+                .quote_pool_token(one_pool_token, pool_token_supply, 5_000_000, 20_000_000)
+                .unwrap(),
+            TradingTokenResult {
+                token_a_amount: 5_000,
+                token_b_amount: 20_000,
+            }
+        );
+        // amounts round down in favor of the pool
+        assert_eq!(
+            swap_curve
+                .quote_pool_token(1, pool_token_supply, 5_000_000, 20_000_000)
+                .unwrap(),
+            TradingTokenResult {
+                token_a_amount: 0,
+                token_b_amount: 0,
+            }
+        );
+    }
+
     #[test]
     fn constant_product_trade_fee() {
         // calculation on https://github.com/solana-labs/solana-program-library/issues/341
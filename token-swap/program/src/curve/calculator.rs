@@ -10,6 +10,10 @@ use {crate::error::SwapError, spl_math::precise_number::PreciseNumber, std::fmt:
 /// input amounts, and Balancer uses 100 * 10 ^ 18.
 pub const INITIAL_SWAP_POOL_AMOUNT: u128 = 1_000_000_000;
 
+/// Minimum amount of each required token a pool must hold at initialization,
+/// so that the initial price cannot be set with a handful of tokens
+pub const MIN_INITIAL_RESERVE: u64 = 100;
+
 /// Hardcode the number of token types in a pool, used to calculate the
 /// equivalent pool tokens for the owner trading fee.
 pub const TOKENS_IN_POOL: u128 = 2;
@@ -162,6 +166,9 @@ pub trait CurveCalculator: Debug + DynPack {
         if token_b_amount == 0 {
             return Err(SwapError::EmptySupply);
         }
+        if token_a_amount < MIN_INITIAL_RESERVE || token_b_amount < MIN_INITIAL_RESERVE {
+            return Err(SwapError::InsufficientInitialReserve);
+        }
         Ok(())
     }
 
@@ -173,6 +180,25 @@ pub trait CurveCalculator: Debug + DynPack {
         true
     }
 
+    /// Whether the curve can be inverted to find the source amount required
+    /// for an exact destination amount, see `source_amount_for_exact_output`.
+    fn allows_exact_output(&self) -> bool {
+        false
+    }
+
+    /// Calculate the least source token, before fees, that swaps for at least
+    /// `destination_amount` of destination token. Curves that cannot be
+    /// inverted return `None`.
+    fn source_amount_for_exact_output(
+        &self,
+        _destination_amount: u128,
+        _swap_source_amount: u128,
+        _swap_destination_amount: u128,
+        _trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        None
+    }
+
     /// Calculates the total normalized value of the curve given the liquidity
     /// parameters.
     ///
@@ -3,7 +3,7 @@ use {
     crate::{
         curve::calculator::{
             map_zero_to_none, CurveCalculator, DynPack, RoundDirection, SwapWithoutFeesResult,
-            TradeDirection, TradingTokenResult,
+            TradeDirection, TradingTokenResult, MIN_INITIAL_RESERVE,
         },
         error::SwapError,
     },
@@ -198,6 +198,9 @@ impl CurveCalculator for ConstantPriceCurve {
         if token_a_amount == 0 {
             return Err(SwapError::EmptySupply);
         }
+        if token_a_amount < MIN_INITIAL_RESERVE {
+            return Err(SwapError::InsufficientInitialReserve);
+        }
         Ok(())
     }
 
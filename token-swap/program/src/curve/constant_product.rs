@@ -2,9 +2,12 @@
 
 use {
     crate::{
-        curve::calculator::{
-            map_zero_to_none, CurveCalculator, DynPack, RoundDirection, SwapWithoutFeesResult,
-            TradeDirection, TradingTokenResult,
+        curve::{
+            calculator::{
+                map_zero_to_none, CurveCalculator, DynPack, RoundDirection, SwapWithoutFeesResult,
+                TradeDirection, TradingTokenResult,
+            },
+            fees::Fees,
         },
         error::SwapError,
     },
@@ -45,11 +48,33 @@ pub fn swap(
     })
 }
 
+/// The inverse of the constant product swap, the least source amount that
+/// `swap` exchanges for at least `destination_amount`.
+///
+/// The destination reserve must stay above zero, so `destination_amount` must
+/// be less than `swap_destination_amount`.
+pub fn source_amount_for_exact_output(
+    destination_amount: u128,
+    swap_source_amount: u128,
+    swap_destination_amount: u128,
+) -> Option<u128> {
+    if destination_amount == 0 {
+        return None;
+    }
+    let invariant = swap_source_amount.checked_mul(swap_destination_amount)?;
+    let new_swap_destination_amount =
+        map_zero_to_none(swap_destination_amount.checked_sub(destination_amount)?)?;
+    let (new_swap_source_amount, _) = invariant.checked_ceil_div(new_swap_destination_amount)?;
+    new_swap_source_amount.checked_sub(swap_source_amount)
+}
+
 /// Get the amount of trading tokens for the given amount of pool tokens,
 /// provided the total trading tokens and supply of pool tokens.
 ///
 /// The constant product implementation is a simple ratio calculation for how
-/// many trading tokens correspond to a certain number of pool tokens
+/// many trading tokens correspond to a certain number of pool tokens.
+/// Redeeming the entire supply returns the entire reserves, and an empty
+/// supply returns `None`.
 pub fn pool_tokens_to_trading_tokens(
     pool_tokens: u128,
     pool_token_supply: u128,
@@ -57,6 +82,16 @@ pub fn pool_tokens_to_trading_tokens(
     swap_token_b_amount: u128,
     round_direction: RoundDirection,
 ) -> Option<TradingTokenResult> {
+    if pool_token_supply == 0 {
+        return None;
+    }
+    // Skip the ratio, which may overflow for large reserves
+    if pool_tokens == pool_token_supply {
+        return Some(TradingTokenResult {
+            token_a_amount: swap_token_a_amount,
+            token_b_amount: swap_token_b_amount,
+        });
+    }
     let mut token_a_amount = pool_tokens
         .checked_mul(swap_token_a_amount)?
         .checked_div(pool_token_supply)?;
@@ -156,6 +191,56 @@ pub fn withdraw_single_token_type_exact_out(
     }
 }
 
+/// Get the amount of source token to swap first for a single-sided deposit,
+/// so that the rest of the source token and the swap output match the pool
+/// ratio after the swap.
+///
+/// With `f` the combined trade and owner fee fraction, `r` the swap source
+/// amount and `a` the source amount, both stay in the pool so the swapped
+/// amount `s` solves `(1 - f) s^2 + (2 - f) r s - a r = 0`.
+pub fn optimal_swap_amount_for_single_deposit(
+    swap_source_amount: u128,
+    source_amount: u128,
+    fees: &Fees,
+) -> Option<u128> {
+    if source_amount == 0 {
+        return Some(0);
+    }
+    let fee_fraction = |numerator: u64, denominator: u64| {
+        if numerator == 0 || denominator == 0 {
+            PreciseNumber::new(0)
+        } else {
+            PreciseNumber::new(numerator.into())?
+                .checked_div(&PreciseNumber::new(denominator.into())?)
+        }
+    };
+    let fee = fee_fraction(fees.trade_fee_numerator, fees.trade_fee_denominator)?.checked_add(
+        &fee_fraction(
+            fees.owner_trade_fee_numerator,
+            fees.owner_trade_fee_denominator,
+        )?,
+    )?;
+    let one = PreciseNumber::new(1)?;
+    let two = PreciseNumber::new(2)?;
+    let one_minus_fee = one.checked_sub(&fee)?;
+    let swap_source_amount = PreciseNumber::new(swap_source_amount)?;
+    let source_amount = PreciseNumber::new(source_amount)?;
+
+    let b = two.checked_sub(&fee)?.checked_mul(&swap_source_amount)?;
+    let discriminant = b.checked_mul(&b)?.checked_add(
+        &PreciseNumber::new(4)?
+            .checked_mul(&one_minus_fee)?
+            .checked_mul(&source_amount)?
+            .checked_mul(&swap_source_amount)?,
+    )?;
+    discriminant
+        .sqrt()?
+        .checked_sub(&b)?
+        .checked_div(&two.checked_mul(&one_minus_fee)?)?
+        .floor()?
+        .to_imprecise()
+}
+
 /// Calculates the total normalized value of the curve given the liquidity
 /// parameters.
 ///
@@ -184,6 +269,24 @@ impl CurveCalculator for ConstantProductCurve {
         swap(source_amount, swap_source_amount, swap_destination_amount)
     }
 
+    fn allows_exact_output(&self) -> bool {
+        true
+    }
+
+    fn source_amount_for_exact_output(
+        &self,
+        destination_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        _trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        source_amount_for_exact_output(
+            destination_amount,
+            swap_source_amount,
+            swap_destination_amount,
+        )
+    }
+
     /// The constant product implementation is a simple ratio calculation for
     /// how many trading tokens correspond to a certain number of pool
     /// tokens
@@ -281,6 +384,7 @@ impl DynPack for ConstantProductCurve {
 mod tests {
     use {
         super::*,
+        crate::curve::base::{CurveType, SwapCurve},
         crate::curve::calculator::{
             test::{
                 check_curve_value_from_swap, check_deposit_token_conversion,
@@ -288,9 +392,10 @@ mod tests {
                 check_withdraw_token_conversion, total_and_intermediate,
                 CONVERSION_BASIS_POINTS_GUARANTEE,
             },
-            RoundDirection, INITIAL_SWAP_POOL_AMOUNT,
+            RoundDirection, INITIAL_SWAP_POOL_AMOUNT, MIN_INITIAL_RESERVE,
         },
         proptest::prelude::*,
+        std::sync::Arc,
     };
 
     #[test]
@@ -299,6 +404,85 @@ mod tests {
         assert_eq!(calculator.new_pool_supply(), INITIAL_SWAP_POOL_AMOUNT);
     }
 
+    #[test]
+    fn validate_initial_supply() {
+        let calculator = ConstantProductCurve {};
+        assert_eq!(
+            calculator.validate_supply(0, MIN_INITIAL_RESERVE),
+            Err(SwapError::EmptySupply)
+        );
+        assert_eq!(
+            calculator.validate_supply(MIN_INITIAL_RESERVE - 1, MIN_INITIAL_RESERVE),
+            Err(SwapError::InsufficientInitialReserve)
+        );
+        assert_eq!(
+            calculator.validate_supply(MIN_INITIAL_RESERVE, MIN_INITIAL_RESERVE - 1),
+            Err(SwapError::InsufficientInitialReserve)
+        );
+        assert_eq!(
+            calculator.validate_supply(MIN_INITIAL_RESERVE, MIN_INITIAL_RESERVE),
+            Ok(())
+        );
+    }
+
+    fn check_single_deposit_split(
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        source_amount: u128,
+        fees: &Fees,
+    ) {
+        let swap_amount =
+            optimal_swap_amount_for_single_deposit(swap_source_amount, source_amount, fees)
+                .unwrap();
+        let result = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        }
+        .swap(
+            swap_amount,
+            swap_source_amount,
+            swap_destination_amount,
+            TradeDirection::AtoB,
+            fees,
+        )
+        .unwrap();
+        let remaining_source = source_amount - result.source_amount_swapped;
+        let received = result.destination_amount_swapped;
+
+        // depositing both sides at the post-swap ratio leaves nothing behind,
+        // up to integer rounding in the swap and fees, which stays within ten
+        // parts per million of either side
+        let source_leftover = remaining_source.saturating_sub(
+            received * result.new_swap_source_amount / result.new_swap_destination_amount,
+        );
+        let destination_leftover = received.saturating_sub(
+            remaining_source * result.new_swap_destination_amount / result.new_swap_source_amount,
+        );
+        assert!(source_leftover <= remaining_source / 100_000);
+        assert!(destination_leftover <= received / 100_000);
+    }
+
+    #[test]
+    fn single_deposit_split() {
+        let no_fees = Fees::default();
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            ..Fees::default()
+        };
+        assert_eq!(
+            optimal_swap_amount_for_single_deposit(1_000, 0, &fees),
+            Some(0)
+        );
+
+        check_single_deposit_split(1_000_000_000, 2_000_000_000, 100_000_000, &no_fees);
+        check_single_deposit_split(1_000_000_000, 2_000_000_000, 100_000_000, &fees);
+        check_single_deposit_split(1_000_000_000, 1_000_000, 5_000_000_000, &fees);
+        check_single_deposit_split(50_000_000, 70_000_000_000, 1_000_000, &fees);
+    }
+
     fn check_pool_token_rate(
         token_a: u128,
         token_b: u128,
@@ -328,6 +512,38 @@ mod tests {
         check_pool_token_rate(5, 501, 2, 10, 1, 101);
     }
 
+    #[test]
+    fn redeem_pool_token_supply() {
+        let calculator = ConstantProductCurve {};
+        for round_direction in [RoundDirection::Floor, RoundDirection::Ceiling] {
+            // the entire supply returns the entire reserves, even when the
+            // ratio itself would overflow
+            let results = calculator
+                .pool_tokens_to_trading_tokens(
+                    u128::MAX,
+                    u128::MAX,
+                    u64::MAX.into(),
+                    7,
+                    round_direction,
+                )
+                .unwrap();
+            assert_eq!(results.token_a_amount, u64::MAX.into());
+            assert_eq!(results.token_b_amount, 7);
+
+            // an empty supply cannot be redeemed
+            assert!(calculator
+                .pool_tokens_to_trading_tokens(0, 0, 100, 100, round_direction)
+                .is_none());
+        }
+
+        // a partial redemption rounds down
+        let results = calculator
+            .pool_tokens_to_trading_tokens(1, 3, 100, 200, RoundDirection::Floor)
+            .unwrap();
+        assert_eq!(results.token_a_amount, 33);
+        assert_eq!(results.token_b_amount, 66);
+    }
+
     #[test]
     fn fail_trading_token_conversion() {
         let calculator = ConstantProductCurve {};
@@ -492,6 +708,37 @@ mod tests {
         }
     }
 
+    proptest! {
+        #[test]
+        fn exact_output_round_trip(
+            swap_source_amount in 1..u64::MAX,
+            swap_destination_amount in 2..u64::MAX,
+            destination_amount in 1..u64::MAX,
+        ) {
+            prop_assume!(destination_amount < swap_destination_amount);
+            let swap_source_amount = swap_source_amount as u128;
+            let swap_destination_amount = swap_destination_amount as u128;
+            let destination_amount = destination_amount as u128;
+            let source_amount = source_amount_for_exact_output(
+                destination_amount,
+                swap_source_amount,
+                swap_destination_amount,
+            )
+            .unwrap();
+
+            // the computed input gets at least the requested output
+            let result = swap(source_amount, swap_source_amount, swap_destination_amount).unwrap();
+            prop_assert!(result.destination_amount_swapped >= destination_amount);
+
+            // and one token less does not
+            if let Some(result) =
+                swap(source_amount - 1, swap_source_amount, swap_destination_amount)
+            {
+                prop_assert!(result.destination_amount_swapped < destination_amount);
+            }
+        }
+    }
+
     proptest! {
         #[test]
         fn curve_value_does_not_decrease_from_swap(
@@ -42,6 +42,46 @@ pub struct Fees {
     pub host_fee_denominator: u64,
 }
 
+/// Fixed trade fee tiers a pool can be initialized with, to avoid arbitrary
+/// fee ratios
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FeeTier {
+    /// 0.05% trade fee
+    Bps5,
+    /// 0.30% trade fee
+    Bps30,
+    /// 1.00% trade fee
+    Bps100,
+}
+
+impl FeeTier {
+    /// Denominator shared by all tiers, in basis points
+    pub const DENOMINATOR: u64 = 10_000;
+
+    /// Trade fee numerator and denominator for the tier
+    pub fn trade_fee(&self) -> (u64, u64) {
+        let numerator = match self {
+            FeeTier::Bps5 => 5,
+            FeeTier::Bps30 => 30,
+            FeeTier::Bps100 => 100,
+        };
+        (numerator, Self::DENOMINATOR)
+    }
+}
+
+impl TryFrom<u8> for FeeTier {
+    type Error = SwapError;
+
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        match index {
+            0 => Ok(FeeTier::Bps5),
+            1 => Ok(FeeTier::Bps30),
+            2 => Ok(FeeTier::Bps100),
+            _ => Err(SwapError::InvalidFee),
+        }
+    }
+}
+
 /// Helper function for calculating swap fee
 pub fn calculate_fee(
     token_amount: u128,
@@ -163,6 +203,17 @@ impl Fees {
         )
     }
 
+    /// Replace the trade fee with the canonical fraction for the tier at
+    /// `fee_tier`, failing if the index is out of range
+    pub fn with_fee_tier(self, fee_tier: u8) -> Result<Self, SwapError> {
+        let (trade_fee_numerator, trade_fee_denominator) = FeeTier::try_from(fee_tier)?.trade_fee();
+        Ok(Self {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            ..self
+        })
+    }
+
     /// Validate that the fees are reasonable
     pub fn validate(&self) -> Result<(), SwapError> {
         validate_fraction(self.trade_fee_numerator, self.trade_fee_denominator)?;
@@ -279,4 +330,21 @@ mod tests {
         let unpacked = Fees::unpack_from_slice(&packed).unwrap();
         assert_eq!(fees, unpacked);
     }
+
+    #[test]
+    fn fee_tiers() {
+        let expected_bps = [5, 30, 100];
+        for (index, bps) in expected_bps.iter().enumerate() {
+            let fees = Fees::default().with_fee_tier(index as u8).unwrap();
+            assert_eq!(fees.trade_fee_numerator, *bps);
+            assert_eq!(fees.trade_fee_denominator, FeeTier::DENOMINATOR);
+            fees.validate().unwrap();
+        }
+
+        assert_eq!(
+            Fees::default().with_fee_tier(expected_bps.len() as u8),
+            Err(SwapError::InvalidFee)
+        );
+        assert_eq!(FeeTier::try_from(u8::MAX), Err(SwapError::InvalidFee));
+    }
 }
@@ -5,7 +5,7 @@ use {
         curve::{
             calculator::{
                 CurveCalculator, DynPack, RoundDirection, SwapWithoutFeesResult, TradeDirection,
-                TradingTokenResult,
+                TradingTokenResult, MIN_INITIAL_RESERVE,
             },
             constant_product::{
                 deposit_single_token_type, normalized_value, pool_tokens_to_trading_tokens, swap,
@@ -132,6 +132,9 @@ impl CurveCalculator for OffsetCurve {
         if token_a_amount == 0 {
             return Err(SwapError::EmptySupply);
         }
+        if token_a_amount < MIN_INITIAL_RESERVE {
+            return Err(SwapError::InsufficientInitialReserve);
+        }
         Ok(())
     }
 
@@ -117,6 +117,20 @@ pub enum SwapError {
     /// The pool fee account is invalid.
     #[error("The pool fee account is invalid")]
     InvalidFeeAccount,
+
+    // 30.
+    /// The initial token reserve is too small to launch the pool.
+    #[error("Initial token reserve is below the minimum")]
+    InsufficientInitialReserve,
+    /// The swap would move the pool price outside of its configured band.
+    #[error("Swap would move the pool price outside of its band")]
+    PriceOutOfBand,
+    /// The initial reserves do not match the reference pool's ratio.
+    #[error("Initial reserves do not match the reference pool ratio")]
+    ReferenceRatioMismatch,
+    /// The swap would decrease the constant product invariant.
+    #[error("Swap would decrease the pool invariant")]
+    InvariantDecreased,
 }
 impl From<SwapError> for ProgramError {
     fn from(e: SwapError) -> Self {
@@ -200,6 +214,18 @@ impl PrintProgramError for SwapError {
             SwapError::InvalidFeeAccount => {
                 msg!("Error: The pool fee account is invalid")
             }
+            SwapError::InsufficientInitialReserve => {
+                msg!("Error: Initial token reserve is below the minimum")
+            }
+            SwapError::PriceOutOfBand => {
+                msg!("Error: Swap would move the pool price outside of its band")
+            }
+            SwapError::ReferenceRatioMismatch => {
+                msg!("Error: Initial reserves do not match the reference pool ratio")
+            }
+            SwapError::InvariantDecreased => {
+                msg!("Error: Swap would decrease the pool invariant")
+            }
         }
     }
 }
@@ -0,0 +1,197 @@
+//! Events logged by the swap program for indexers
+
+use {
+    crate::error::SwapError,
+    solana_program::{log::sol_log_data, program_error::ProgramError, pubkey::Pubkey},
+    std::convert::TryInto,
+};
+
+/// Event recorded with `sol_log_data` after a successful swap, deposit or
+/// withdrawal.
+///
+/// The logged record is the event tag, followed by the little-endian amounts
+/// of the variant in declaration order, followed by the swap account address.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SwapEvent {
+    /// Tokens were swapped through the pool
+    Swap {
+        /// Source tokens taken from the user, including fees
+        amount_in: u64,
+        /// Destination tokens sent to the user
+        amount_out: u64,
+    },
+    /// Tokens were deposited into the pool in exchange for pool tokens
+    Deposit {
+        /// Token A deposited, 0 for a single-sided B deposit
+        token_a_amount: u64,
+        /// Token B deposited, 0 for a single-sided A deposit
+        token_b_amount: u64,
+        /// Pool tokens minted to the user
+        pool_token_amount: u64,
+    },
+    /// Tokens were withdrawn from the pool in exchange for pool tokens
+    Withdraw {
+        /// Pool tokens taken from the user, including the withdrawal fee
+        pool_token_amount: u64,
+        /// Token A withdrawn, 0 for a single-sided B withdrawal
+        token_a_amount: u64,
+        /// Token B withdrawn, 0 for a single-sided A withdrawal
+        token_b_amount: u64,
+    },
+}
+
+impl SwapEvent {
+    /// Packs the event for the given swap account into a byte buffer.
+    pub fn pack(&self, swap: &Pubkey) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(1 + 3 * 8 + 32);
+        match self {
+            Self::Swap {
+                amount_in,
+                amount_out,
+            } => {
+                buf.push(0);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&amount_out.to_le_bytes());
+            }
+            Self::Deposit {
+                token_a_amount,
+                token_b_amount,
+                pool_token_amount,
+            } => {
+                buf.push(1);
+                buf.extend_from_slice(&token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&token_b_amount.to_le_bytes());
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+            }
+            Self::Withdraw {
+                pool_token_amount,
+                token_a_amount,
+                token_b_amount,
+            } => {
+                buf.push(2);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                buf.extend_from_slice(&token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&token_b_amount.to_le_bytes());
+            }
+        }
+        buf.extend_from_slice(swap.as_ref());
+        buf
+    }
+
+    /// Unpacks a byte buffer into an event and the swap account it was
+    /// logged for.
+    pub fn unpack(input: &[u8]) -> Result<(Self, Pubkey), ProgramError> {
+        let (&tag, rest) = input.split_first().ok_or(SwapError::InvalidInstruction)?;
+        let (event, rest) = match tag {
+            0 => {
+                let (amount_in, rest) = Self::unpack_u64(rest)?;
+                let (amount_out, rest) = Self::unpack_u64(rest)?;
+                (
+                    Self::Swap {
+                        amount_in,
+                        amount_out,
+                    },
+                    rest,
+                )
+            }
+            1 => {
+                let (token_a_amount, rest) = Self::unpack_u64(rest)?;
+                let (token_b_amount, rest) = Self::unpack_u64(rest)?;
+                let (pool_token_amount, rest) = Self::unpack_u64(rest)?;
+                (
+                    Self::Deposit {
+                        token_a_amount,
+                        token_b_amount,
+                        pool_token_amount,
+                    },
+                    rest,
+                )
+            }
+            2 => {
+                let (pool_token_amount, rest) = Self::unpack_u64(rest)?;
+                let (token_a_amount, rest) = Self::unpack_u64(rest)?;
+                let (token_b_amount, rest) = Self::unpack_u64(rest)?;
+                (
+                    Self::Withdraw {
+                        pool_token_amount,
+                        token_a_amount,
+                        token_b_amount,
+                    },
+                    rest,
+                )
+            }
+            _ => return Err(SwapError::InvalidInstruction.into()),
+        };
+        let swap = rest
+            .try_into()
+            .map(Pubkey::new_from_array)
+            .map_err(|_| SwapError::InvalidInstruction)?;
+        Ok((event, swap))
+    }
+
+    /// Logs the event for the given swap account.
+    pub fn emit(&self, swap: &Pubkey) {
+        sol_log_data(&[&self.pack(swap)]);
+    }
+
+    fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+        if input.len() >= 8 {
+            let (amount, rest) = input.split_at(8);
+            let amount = amount
+                .try_into()
+                .map(u64::from_le_bytes)
+                .map_err(|_| SwapError::InvalidInstruction)?;
+            Ok((amount, rest))
+        } else {
+            Err(SwapError::InvalidInstruction.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_swap_event() {
+        let swap = Pubkey::new_unique();
+        let event = SwapEvent::Swap {
+            amount_in: 100,
+            amount_out: 42,
+        };
+        let packed = event.pack(&swap);
+        let mut expect = vec![0u8];
+        expect.extend_from_slice(&100u64.to_le_bytes());
+        expect.extend_from_slice(&42u64.to_le_bytes());
+        expect.extend_from_slice(swap.as_ref());
+        assert_eq!(packed, expect);
+        assert_eq!(SwapEvent::unpack(&packed).unwrap(), (event, swap));
+    }
+
+    #[test]
+    fn pack_deposit_and_withdraw_events() {
+        let swap = Pubkey::new_unique();
+        let deposit = SwapEvent::Deposit {
+            token_a_amount: 1,
+            token_b_amount: 2,
+            pool_token_amount: 3,
+        };
+        let packed = deposit.pack(&swap);
+        assert_eq!(packed.len(), 1 + 3 * 8 + 32);
+        assert_eq!(SwapEvent::unpack(&packed).unwrap(), (deposit, swap));
+
+        let withdraw = SwapEvent::Withdraw {
+            pool_token_amount: 3,
+            token_a_amount: 1,
+            token_b_amount: 0,
+        };
+        let packed = withdraw.pack(&swap);
+        assert_eq!(SwapEvent::unpack(&packed).unwrap(), (withdraw, swap));
+
+        // truncated records and unknown tags are rejected
+        assert!(SwapEvent::unpack(&packed[..packed.len() - 1]).is_err());
+        let mut unknown = packed;
+        unknown[0] = 3;
+        assert!(SwapEvent::unpack(&unknown).is_err());
+    }
+}
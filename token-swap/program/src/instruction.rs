@@ -14,6 +14,7 @@ use {
         program_error::ProgramError,
         program_pack::Pack,
         pubkey::Pubkey,
+        system_program,
     },
     std::{convert::TryInto, mem::size_of},
 };
@@ -27,6 +28,14 @@ pub struct Initialize {
     /// swap curve info for pool, including CurveType and anything
     /// else that may be required
     pub swap_curve: SwapCurve,
+    /// lowest pool price a swap may leave behind, 0 for no lower bound, see
+    /// [PRICE_BAND_PRECISION](../state/constant.PRICE_BAND_PRECISION.html)
+    pub price_band_low: u64,
+    /// highest pool price a swap may leave behind, 0 for no upper bound
+    pub price_band_high: u64,
+    /// index of a fixed [FeeTier](../curve/fees/enum.FeeTier.html) whose
+    /// trade fee replaces the one in `fees`, or `None` to use `fees` as given
+    pub fee_tier: Option<u8>,
 }
 
 /// Swap instruction data
@@ -94,6 +103,47 @@ pub struct WithdrawSingleTokenTypeExactAmountOut {
     pub maximum_pool_token_amount: u64,
 }
 
+/// DepositBoth instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepositBoth {
+    /// Maximum token A amount to deposit
+    pub maximum_token_a_amount: u64,
+    /// Maximum token B amount to deposit
+    pub maximum_token_b_amount: u64,
+    /// Minimum amount of pool tokens to receive, prevents excessive slippage
+    pub minimum_pool_token_amount: u64,
+}
+
+/// InitializeFromReference instruction data
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct InitializeFromReference {
+    /// all swap fees
+    pub fees: Fees,
+    /// swap curve info for pool, including CurveType and anything
+    /// else that may be required
+    pub swap_curve: SwapCurve,
+    /// Allowed deviation of the initial price from the reference pool's
+    /// price, relative to the reference price
+    pub ratio_tolerance_numerator: u64,
+    /// Denominator of the allowed deviation, 0 to require an exact match
+    pub ratio_tolerance_denominator: u64,
+}
+
+/// SwapExactOutput instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapExactOutput {
+    /// DESTINATION amount the user receives
+    pub output_amount: u64,
+    /// Maximum amount of SOURCE token to transfer, prevents excessive
+    /// slippage
+    pub maximum_input: u64,
+}
+
 /// Instructions supported by the token swap program.
 #[repr(C)]
 #[derive(Debug, PartialEq)]
@@ -136,6 +186,11 @@ pub enum SwapInstruction {
     ///   13. `[]` Pool Token program id
     ///   14. `[optional, writable]` Host fee account to receive additional
     ///       trading fees
+    ///
+    ///   A native SOL SOURCE account is synced before the transfer, so lamports
+    ///   sent to it earlier in the transaction are swapped as wrapped SOL. A
+    ///   native SOL DESTINATION account receives the lamports along with the
+    ///   tokens, and its owner can close it to unwrap them.
     Swap(Swap),
 
     ///   Deposit both types of tokens into the pool.  The output is a "pool"
@@ -157,6 +212,8 @@ pub enum SwapInstruction {
     ///   11. `[]` Token A program id
     ///   12. `[]` Token B program id
     ///   13. `[]` Pool Token program id
+    ///
+    ///   Native SOL source accounts are synced before the transfer.
     DepositAllTokenTypes(DepositAllTokenTypes),
 
     ///   Withdraw both types of tokens from the pool at the current ratio,
@@ -198,6 +255,8 @@ pub enum SwapInstruction {
     ///   8. `[]` Token (A|B) SOURCE mint
     ///   9. `[]` Token (A|B) SOURCE program id
     ///   10. `[]` Pool Token program id
+    ///
+    ///   Native SOL source accounts are synced before the transfer.
     DepositSingleTokenTypeExactAmountIn(DepositSingleTokenTypeExactAmountIn),
 
     ///   Withdraw one token type from the pool at the current ratio given the
@@ -217,6 +276,71 @@ pub enum SwapInstruction {
     ///   10. `[]` Pool Token program id
     ///   11. `[]` Token (A|B) DESTINATION program id
     WithdrawSingleTokenTypeExactAmountOut(WithdrawSingleTokenTypeExactAmountOut),
+
+    ///   Rewrite a swap account stored in an older layout at the latest
+    ///   version, filling any new fields with their defaults. Only the owner
+    ///   of the pool fee account may migrate the pool.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` Pool fee account
+    ///   2. `[writable, signer]` Owner of the pool fee account, pays for any
+    ///      additional rent
+    ///   3. `[]` System program
+    MigratePool,
+
+    ///   Deposit both types of tokens into the pool, given the maximum amount
+    ///   of each. Only the amounts matching the current ratio are
+    ///   transferred, and pool tokens are minted for the limiting side.
+    ///
+    ///   Accounts are the same as `DepositAllTokenTypes`:
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` swap authority
+    ///   2. `[]` user transfer authority
+    ///   3. `[writable]` token_a user transfer authority can transfer amount,
+    ///   4. `[writable]` token_b user transfer authority can transfer amount,
+    ///   5. `[writable]` token_a Base Account to deposit into.
+    ///   6. `[writable]` token_b Base Account to deposit into.
+    ///   7. `[writable]` Pool MINT account, swap authority is the owner.
+    ///   8. `[writable]` Pool Account to deposit the generated tokens, user is
+    ///      the owner.
+    ///   9. `[]` Token A mint
+    ///   10. `[]` Token B mint
+    ///   11. `[]` Token A program id
+    ///   12. `[]` Token B program id
+    ///   13. `[]` Pool Token program id
+    ///
+    ///   Native SOL source accounts are synced before the transfer.
+    DepositBoth(DepositBoth),
+
+    ///   Initializes a new swap whose initial reserves must be at the price of
+    ///   an existing pool for the same mints, within the given tolerance.
+    ///
+    ///   0. `[writable, signer]` New Token-swap to create.
+    ///   1. `[]` swap authority derived from
+    ///      `create_program_address(&[Token-swap account])`
+    ///   2. `[]` token_a Account. Must be non zero, owned by swap authority.
+    ///   3. `[]` token_b Account. Must be non zero, owned by swap authority.
+    ///   4. `[writable]` Pool Token Mint. Must be empty, owned by swap
+    ///      authority.
+    ///   5. `[]` Pool Token Account to deposit trading and withdraw fees. Must
+    ///      be empty, not owned by swap authority
+    ///   6. `[writable]` Pool Token Account to deposit the initial pool token
+    ///      supply. Must be empty, not owned by swap authority.
+    ///   7. `[]` Pool Token program id
+    ///   8. `[]` Reference Token-swap
+    ///   9. `[]` Reference token_a Base Account
+    ///   10. `[]` Reference token_b Base Account
+    InitializeFromReference(InitializeFromReference),
+
+    ///   Swap the tokens in the pool for an exact amount of DESTINATION token,
+    ///   transferring the least SOURCE token that provides it, including
+    ///   fees. Only supported by curves that can be inverted, currently the
+    ///   constant product curve. Rounding can give the user slightly more
+    ///   than the output amount, never less.
+    ///
+    ///   Accounts are the same as [Swap](enum.SwapInstruction.html).
+    SwapExactOutput(SwapExactOutput),
 }
 
 impl SwapInstruction {
@@ -226,11 +350,27 @@ impl SwapInstruction {
         let (&tag, rest) = input.split_first().ok_or(SwapError::InvalidInstruction)?;
         Ok(match tag {
             0 => {
-                if rest.len() >= Fees::LEN {
+                if rest.len() >= Fees::LEN + SwapCurve::LEN {
                     let (fees, rest) = rest.split_at(Fees::LEN);
                     let fees = Fees::unpack_unchecked(fees)?;
-                    let swap_curve = SwapCurve::unpack_unchecked(rest)?;
-                    Self::Initialize(Initialize { fees, swap_curve })
+                    let (swap_curve, rest) = rest.split_at(SwapCurve::LEN);
+                    let swap_curve = SwapCurve::unpack_unchecked(swap_curve)?;
+                    // the price band and fee tier are optional, to keep
+                    // accepting data from clients that predate them
+                    let (price_band_low, price_band_high, fee_tier) = if rest.is_empty() {
+                        (0, 0, None)
+                    } else {
+                        let (price_band_low, rest) = Self::unpack_u64(rest)?;
+                        let (price_band_high, rest) = Self::unpack_u64(rest)?;
+                        (price_band_low, price_band_high, rest.first().copied())
+                    };
+                    Self::Initialize(Initialize {
+                        fees,
+                        swap_curve,
+                        price_band_low,
+                        price_band_high,
+                        fee_tier,
+                    })
                 } else {
                     return Err(SwapError::InvalidInstruction.into());
                 }
@@ -279,6 +419,42 @@ impl SwapInstruction {
                     maximum_pool_token_amount,
                 })
             }
+            6 => Self::MigratePool,
+            7 => {
+                let (maximum_token_a_amount, rest) = Self::unpack_u64(rest)?;
+                let (maximum_token_b_amount, rest) = Self::unpack_u64(rest)?;
+                let (minimum_pool_token_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::DepositBoth(DepositBoth {
+                    maximum_token_a_amount,
+                    maximum_token_b_amount,
+                    minimum_pool_token_amount,
+                })
+            }
+            8 => {
+                if rest.len() < Fees::LEN + SwapCurve::LEN {
+                    return Err(SwapError::InvalidInstruction.into());
+                }
+                let (fees, rest) = rest.split_at(Fees::LEN);
+                let fees = Fees::unpack_unchecked(fees)?;
+                let (swap_curve, rest) = rest.split_at(SwapCurve::LEN);
+                let swap_curve = SwapCurve::unpack_unchecked(swap_curve)?;
+                let (ratio_tolerance_numerator, rest) = Self::unpack_u64(rest)?;
+                let (ratio_tolerance_denominator, _rest) = Self::unpack_u64(rest)?;
+                Self::InitializeFromReference(InitializeFromReference {
+                    fees,
+                    swap_curve,
+                    ratio_tolerance_numerator,
+                    ratio_tolerance_denominator,
+                })
+            }
+            9 => {
+                let (output_amount, rest) = Self::unpack_u64(rest)?;
+                let (maximum_input, _rest) = Self::unpack_u64(rest)?;
+                Self::SwapExactOutput(SwapExactOutput {
+                    output_amount,
+                    maximum_input,
+                })
+            }
             _ => return Err(SwapError::InvalidInstruction.into()),
         })
     }
@@ -301,7 +477,13 @@ impl SwapInstruction {
     pub fn pack(&self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(size_of::<Self>());
         match self {
-            Self::Initialize(Initialize { fees, swap_curve }) => {
+            Self::Initialize(Initialize {
+                fees,
+                swap_curve,
+                price_band_low,
+                price_band_high,
+                fee_tier,
+            }) => {
                 buf.push(0);
                 let mut fees_slice = [0u8; Fees::LEN];
                 Pack::pack_into_slice(fees, &mut fees_slice[..]);
@@ -309,6 +491,11 @@ impl SwapInstruction {
                 let mut swap_curve_slice = [0u8; SwapCurve::LEN];
                 Pack::pack_into_slice(swap_curve, &mut swap_curve_slice[..]);
                 buf.extend_from_slice(&swap_curve_slice);
+                buf.extend_from_slice(&price_band_low.to_le_bytes());
+                buf.extend_from_slice(&price_band_high.to_le_bytes());
+                if let Some(fee_tier) = fee_tier {
+                    buf.push(*fee_tier);
+                }
             }
             Self::Swap(Swap {
                 amount_in,
@@ -356,6 +543,41 @@ impl SwapInstruction {
                 buf.extend_from_slice(&destination_token_amount.to_le_bytes());
                 buf.extend_from_slice(&maximum_pool_token_amount.to_le_bytes());
             }
+            Self::MigratePool => buf.push(6),
+            Self::DepositBoth(DepositBoth {
+                maximum_token_a_amount,
+                maximum_token_b_amount,
+                minimum_pool_token_amount,
+            }) => {
+                buf.push(7);
+                buf.extend_from_slice(&maximum_token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&maximum_token_b_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_pool_token_amount.to_le_bytes());
+            }
+            Self::InitializeFromReference(InitializeFromReference {
+                fees,
+                swap_curve,
+                ratio_tolerance_numerator,
+                ratio_tolerance_denominator,
+            }) => {
+                buf.push(8);
+                let mut fees_slice = [0u8; Fees::LEN];
+                Pack::pack_into_slice(fees, &mut fees_slice[..]);
+                buf.extend_from_slice(&fees_slice);
+                let mut swap_curve_slice = [0u8; SwapCurve::LEN];
+                Pack::pack_into_slice(swap_curve, &mut swap_curve_slice[..]);
+                buf.extend_from_slice(&swap_curve_slice);
+                buf.extend_from_slice(&ratio_tolerance_numerator.to_le_bytes());
+                buf.extend_from_slice(&ratio_tolerance_denominator.to_le_bytes());
+            }
+            Self::SwapExactOutput(SwapExactOutput {
+                output_amount,
+                maximum_input,
+            }) => {
+                buf.push(9);
+                buf.extend_from_slice(&output_amount.to_le_bytes());
+                buf.extend_from_slice(&maximum_input.to_le_bytes());
+            }
         }
         buf
     }
@@ -375,7 +597,155 @@ pub fn initialize(
     fees: Fees,
     swap_curve: SwapCurve,
 ) -> Result<Instruction, ProgramError> {
-    let init_data = SwapInstruction::Initialize(Initialize { fees, swap_curve });
+    initialize_with_price_band(
+        program_id,
+        token_program_id,
+        swap_pubkey,
+        authority_pubkey,
+        token_a_pubkey,
+        token_b_pubkey,
+        pool_pubkey,
+        fee_pubkey,
+        destination_pubkey,
+        fees,
+        swap_curve,
+        0,
+        0,
+    )
+}
+
+/// Creates an 'initialize' instruction for a pool that halts swaps leaving
+/// its price outside of `[price_band_low, price_band_high]`.
+pub fn initialize_with_price_band(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    token_a_pubkey: &Pubkey,
+    token_b_pubkey: &Pubkey,
+    pool_pubkey: &Pubkey,
+    fee_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    fees: Fees,
+    swap_curve: SwapCurve,
+    price_band_low: u64,
+    price_band_high: u64,
+) -> Result<Instruction, ProgramError> {
+    initialize_with_data(
+        program_id,
+        token_program_id,
+        swap_pubkey,
+        authority_pubkey,
+        token_a_pubkey,
+        token_b_pubkey,
+        pool_pubkey,
+        fee_pubkey,
+        destination_pubkey,
+        Initialize {
+            fees,
+            swap_curve,
+            price_band_low,
+            price_band_high,
+            fee_tier: None,
+        },
+    )
+}
+
+/// Creates an 'initialize' instruction using the trade fee of one of the
+/// fixed [FeeTier](../curve/fees/enum.FeeTier.html)s. The other fees are
+/// taken from `fees`, and the program rejects an out-of-range `fee_tier`.
+pub fn initialize_with_fee_tier(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    token_a_pubkey: &Pubkey,
+    token_b_pubkey: &Pubkey,
+    pool_pubkey: &Pubkey,
+    fee_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    fee_tier: u8,
+    fees: Fees,
+    swap_curve: SwapCurve,
+) -> Result<Instruction, ProgramError> {
+    initialize_with_data(
+        program_id,
+        token_program_id,
+        swap_pubkey,
+        authority_pubkey,
+        token_a_pubkey,
+        token_b_pubkey,
+        pool_pubkey,
+        fee_pubkey,
+        destination_pubkey,
+        Initialize {
+            fees,
+            swap_curve,
+            price_band_low: 0,
+            price_band_high: 0,
+            fee_tier: Some(fee_tier),
+        },
+    )
+}
+
+fn initialize_with_data(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    token_a_pubkey: &Pubkey,
+    token_b_pubkey: &Pubkey,
+    pool_pubkey: &Pubkey,
+    fee_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    init_data: Initialize,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Initialize(init_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, true),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*token_a_pubkey, false),
+        AccountMeta::new_readonly(*token_b_pubkey, false),
+        AccountMeta::new(*pool_pubkey, false),
+        AccountMeta::new_readonly(*fee_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'initialize_from_reference' instruction, launching a pool at
+/// the price of the reference swap.
+pub fn initialize_from_reference(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    token_a_pubkey: &Pubkey,
+    token_b_pubkey: &Pubkey,
+    pool_pubkey: &Pubkey,
+    fee_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    reference_swap_pubkey: &Pubkey,
+    reference_token_a_pubkey: &Pubkey,
+    reference_token_b_pubkey: &Pubkey,
+    fees: Fees,
+    swap_curve: SwapCurve,
+    ratio_tolerance_numerator: u64,
+    ratio_tolerance_denominator: u64,
+) -> Result<Instruction, ProgramError> {
+    let init_data = SwapInstruction::InitializeFromReference(InitializeFromReference {
+        fees,
+        swap_curve,
+        ratio_tolerance_numerator,
+        ratio_tolerance_denominator,
+    });
     let data = init_data.pack();
 
     let accounts = vec![
@@ -387,6 +757,9 @@ pub fn initialize(
         AccountMeta::new_readonly(*fee_pubkey, false),
         AccountMeta::new(*destination_pubkey, false),
         AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(*reference_swap_pubkey, false),
+        AccountMeta::new_readonly(*reference_token_a_pubkey, false),
+        AccountMeta::new_readonly(*reference_token_b_pubkey, false),
     ];
 
     Ok(Instruction {
@@ -441,6 +814,51 @@ pub fn deposit_all_token_types(
     })
 }
 
+/// Creates a 'deposit_both' instruction.
+pub fn deposit_both(
+    program_id: &Pubkey,
+    token_a_program_id: &Pubkey,
+    token_b_program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    deposit_token_a_pubkey: &Pubkey,
+    deposit_token_b_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    token_a_mint_pubkey: &Pubkey,
+    token_b_mint_pubkey: &Pubkey,
+    instruction: DepositBoth,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::DepositBoth(instruction).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*deposit_token_a_pubkey, false),
+        AccountMeta::new(*deposit_token_b_pubkey, false),
+        AccountMeta::new(*swap_token_a_pubkey, false),
+        AccountMeta::new(*swap_token_b_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*token_a_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_b_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_a_program_id, false),
+        AccountMeta::new_readonly(*token_b_program_id, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
 /// Creates a 'withdraw_all_token_types' instruction.
 pub fn withdraw_all_token_types(
     program_id: &Pubkey,
@@ -589,7 +1007,90 @@ pub fn swap(
     instruction: Swap,
 ) -> Result<Instruction, ProgramError> {
     let data = SwapInstruction::Swap(instruction).pack();
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: swap_accounts(
+            source_token_program_id,
+            destination_token_program_id,
+            pool_token_program_id,
+            swap_pubkey,
+            authority_pubkey,
+            user_transfer_authority_pubkey,
+            source_pubkey,
+            swap_source_pubkey,
+            swap_destination_pubkey,
+            destination_pubkey,
+            pool_mint_pubkey,
+            pool_fee_pubkey,
+            source_mint_pubkey,
+            destination_mint_pubkey,
+            host_fee_pubkey,
+        ),
+        data,
+    })
+}
+
+/// Creates a 'swap_exact_output' instruction.
+pub fn swap_exact_output(
+    program_id: &Pubkey,
+    source_token_program_id: &Pubkey,
+    destination_token_program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_source_pubkey: &Pubkey,
+    swap_destination_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    pool_fee_pubkey: &Pubkey,
+    source_mint_pubkey: &Pubkey,
+    destination_mint_pubkey: &Pubkey,
+    host_fee_pubkey: Option<&Pubkey>,
+    instruction: SwapExactOutput,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::SwapExactOutput(instruction).pack();
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: swap_accounts(
+            source_token_program_id,
+            destination_token_program_id,
+            pool_token_program_id,
+            swap_pubkey,
+            authority_pubkey,
+            user_transfer_authority_pubkey,
+            source_pubkey,
+            swap_source_pubkey,
+            swap_destination_pubkey,
+            destination_pubkey,
+            pool_mint_pubkey,
+            pool_fee_pubkey,
+            source_mint_pubkey,
+            destination_mint_pubkey,
+            host_fee_pubkey,
+        ),
+        data,
+    })
+}
 
+fn swap_accounts(
+    source_token_program_id: &Pubkey,
+    destination_token_program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_source_pubkey: &Pubkey,
+    swap_destination_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    pool_fee_pubkey: &Pubkey,
+    source_mint_pubkey: &Pubkey,
+    destination_mint_pubkey: &Pubkey,
+    host_fee_pubkey: Option<&Pubkey>,
+) -> Vec<AccountMeta> {
     let mut accounts = vec![
         AccountMeta::new_readonly(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
@@ -609,6 +1110,38 @@ pub fn swap(
     if let Some(host_fee_pubkey) = host_fee_pubkey {
         accounts.push(AccountMeta::new(*host_fee_pubkey, false));
     }
+    accounts
+}
+
+/// Basis points in 100%, the denominator for slippage tolerances
+pub const BASIS_POINTS: u64 = 10_000;
+
+/// Computes the `minimum_amount_out` to pass in a 'swap' instruction for an
+/// expected output and a slippage tolerance in basis points, or `None` if the
+/// tolerance is above 100%
+pub fn min_out_with_slippage(expected_out: u64, slippage_bps: u64) -> Option<u64> {
+    let remaining_bps = BASIS_POINTS.checked_sub(slippage_bps)?;
+    let min_out = u128::from(expected_out)
+        .checked_mul(u128::from(remaining_bps))?
+        .checked_div(u128::from(BASIS_POINTS))?;
+    u64::try_from(min_out).ok()
+}
+
+/// Creates a 'migrate_pool' instruction.
+pub fn migrate_pool(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    pool_fee_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::MigratePool.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*pool_fee_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
 
     Ok(Instruction {
         program_id: *program_id,
@@ -663,7 +1196,15 @@ mod tests {
             curve_type,
             calculator,
         };
-        let check = SwapInstruction::Initialize(Initialize { fees, swap_curve });
+        let price_band_low: u64 = 900_000_000;
+        let price_band_high: u64 = 1_100_000_000;
+        let check = SwapInstruction::Initialize(Initialize {
+            fees: fees.clone(),
+            swap_curve: swap_curve.clone(),
+            price_band_low,
+            price_band_high,
+            fee_tier: None,
+        });
         let packed = check.pack();
         let mut expect = vec![0u8];
         expect.extend_from_slice(&trade_fee_numerator.to_le_bytes());
@@ -677,9 +1218,34 @@ mod tests {
         expect.push(curve_type as u8);
         expect.extend_from_slice(&token_b_offset.to_le_bytes());
         expect.extend_from_slice(&[0u8; 24]);
+        expect.extend_from_slice(&price_band_low.to_le_bytes());
+        expect.extend_from_slice(&price_band_high.to_le_bytes());
         assert_eq!(packed, expect);
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
+
+        // data without a price band leaves the band open
+        match SwapInstruction::unpack(&expect[..expect.len() - 16]).unwrap() {
+            SwapInstruction::Initialize(Initialize {
+                price_band_low,
+                price_band_high,
+                ..
+            }) => assert_eq!((price_band_low, price_band_high), (0, 0)),
+            _ => panic!("expected an Initialize instruction"),
+        }
+        assert!(SwapInstruction::unpack(&expect[..expect.len() - 8]).is_err());
+
+        // the fee tier index follows the price band
+        let check = SwapInstruction::Initialize(Initialize {
+            fees,
+            swap_curve,
+            price_band_low,
+            price_band_high,
+            fee_tier: Some(2),
+        });
+        expect.push(2);
+        assert_eq!(check.pack(), expect);
+        assert_eq!(SwapInstruction::unpack(&expect).unwrap(), check);
     }
 
     #[test]
@@ -699,6 +1265,23 @@ mod tests {
         assert_eq!(unpacked, check);
     }
 
+    #[test]
+    fn pack_swap_exact_output() {
+        let output_amount: u64 = 10;
+        let maximum_input: u64 = 25;
+        let check = SwapInstruction::SwapExactOutput(SwapExactOutput {
+            output_amount,
+            maximum_input,
+        });
+        let packed = check.pack();
+        let mut expect = vec![9];
+        expect.extend_from_slice(&output_amount.to_le_bytes());
+        expect.extend_from_slice(&maximum_input.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
     #[test]
     fn pack_deposit() {
         let pool_token_amount: u64 = 5;
@@ -776,4 +1359,83 @@ mod tests {
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
     }
+
+    #[test]
+    fn slippage_min_out() {
+        let expected_out = 1_234_567;
+        assert_eq!(min_out_with_slippage(expected_out, 0), Some(expected_out));
+        assert_eq!(min_out_with_slippage(expected_out, 100), Some(1_222_221));
+        assert_eq!(min_out_with_slippage(expected_out, BASIS_POINTS), Some(0));
+        assert_eq!(min_out_with_slippage(expected_out, BASIS_POINTS + 1), None);
+        assert_eq!(min_out_with_slippage(u64::MAX, 0), Some(u64::MAX));
+    }
+
+    #[test]
+    fn pack_migrate_pool() {
+        let check = SwapInstruction::MigratePool;
+        let packed = check.pack();
+        let expect = vec![6];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_deposit_both() {
+        let maximum_token_a_amount: u64 = 10;
+        let maximum_token_b_amount: u64 = 20;
+        let minimum_pool_token_amount: u64 = 5;
+        let check = SwapInstruction::DepositBoth(DepositBoth {
+            maximum_token_a_amount,
+            maximum_token_b_amount,
+            minimum_pool_token_amount,
+        });
+        let packed = check.pack();
+        let mut expect = vec![7];
+        expect.extend_from_slice(&maximum_token_a_amount.to_le_bytes());
+        expect.extend_from_slice(&maximum_token_b_amount.to_le_bytes());
+        expect.extend_from_slice(&minimum_pool_token_amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_initialize_from_reference() {
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 4,
+            ..Fees::default()
+        };
+        let token_b_offset: u64 = 1_000_000_000;
+        let curve_type = CurveType::Offset;
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator: Arc::new(OffsetCurve { token_b_offset }),
+        };
+        let ratio_tolerance_numerator: u64 = 1;
+        let ratio_tolerance_denominator: u64 = 100;
+        let check = SwapInstruction::InitializeFromReference(InitializeFromReference {
+            fees: fees.clone(),
+            swap_curve,
+            ratio_tolerance_numerator,
+            ratio_tolerance_denominator,
+        });
+        let packed = check.pack();
+        let mut expect = vec![8u8];
+        let mut fees_slice = [0u8; Fees::LEN];
+        fees.pack_into_slice(&mut fees_slice);
+        expect.extend_from_slice(&fees_slice);
+        expect.push(curve_type as u8);
+        expect.extend_from_slice(&token_b_offset.to_le_bytes());
+        expect.extend_from_slice(&[0u8; 24]);
+        expect.extend_from_slice(&ratio_tolerance_numerator.to_le_bytes());
+        expect.extend_from_slice(&ratio_tolerance_denominator.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        // the tolerance is required
+        assert!(SwapInstruction::unpack(&expect[..expect.len() - 8]).is_err());
+    }
 }
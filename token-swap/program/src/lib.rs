@@ -6,6 +6,7 @@
 pub mod constraints;
 pub mod curve;
 pub mod error;
+pub mod event;
 pub mod instruction;
 pub mod processor;
 pub mod state;
@@ -18,3 +19,11 @@ mod entrypoint;
 pub use solana_program;
 
 solana_program::declare_id!("SwapsVeCiPHMUAtzQWZw7RjsKjgCjhwU55QGu4U1Szw");
+
+/// The constant product invariant `k = token_a * token_b` of a pool's
+/// reserves, for monitoring that swaps never decrease it.
+///
+/// The product of two `u64` always fits in a `u128`.
+pub fn invariant_value(token_a: u64, token_b: u64) -> Option<u128> {
+    u128::from(token_a).checked_mul(u128::from(token_b))
+}
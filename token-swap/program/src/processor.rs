@@ -4,16 +4,18 @@ use {
     crate::{
         constraints::{SwapConstraints, SWAP_CONSTRAINTS},
         curve::{
-            base::SwapCurve,
+            base::{CurveType, SwapCurve},
             calculator::{RoundDirection, TradeDirection},
             fees::Fees,
         },
         error::SwapError,
+        event::SwapEvent,
         instruction::{
-            DepositAllTokenTypes, DepositSingleTokenTypeExactAmountIn, Initialize, Swap,
-            SwapInstruction, WithdrawAllTokenTypes, WithdrawSingleTokenTypeExactAmountOut,
+            DepositAllTokenTypes, DepositBoth, DepositSingleTokenTypeExactAmountIn, Initialize,
+            InitializeFromReference, Swap, SwapExactOutput, SwapInstruction, WithdrawAllTokenTypes,
+            WithdrawSingleTokenTypeExactAmountOut,
         },
-        state::{SwapState, SwapV1, SwapVersion},
+        state::{check_price_band, check_reference_ratio, SwapState, SwapV2, SwapVersion},
     },
     num_traits::FromPrimitive,
     solana_program::{
@@ -23,10 +25,12 @@ use {
         entrypoint::ProgramResult,
         instruction::Instruction,
         msg,
-        program::invoke_signed,
+        program::{invoke, invoke_signed},
         program_error::{PrintProgramError, ProgramError},
         program_option::COption,
         pubkey::Pubkey,
+        rent::Rent,
+        system_instruction,
         sysvar::Sysvar,
     },
     spl_token_2022::{
@@ -89,16 +93,36 @@ impl Processor {
         }
     }
 
+    /// Calculates the pool tokens minted for depositing `deposit` of a token
+    /// with `reserve` in the pool, proportionally to the pool supply and
+    /// rounded down. A pool without supply or reserve mints `deposit`.
+    pub fn pool_tokens_for_deposit(reserve: u64, pool_supply: u64, deposit: u64) -> Option<u64> {
+        if reserve == 0 || pool_supply == 0 {
+            return Some(deposit);
+        }
+        u128::from(pool_supply)
+            .checked_mul(u128::from(deposit))?
+            .checked_div(u128::from(reserve))?
+            .try_into()
+            .ok()
+    }
+
     /// Calculates the authority id by generating a program address.
     pub fn authority_id(
         program_id: &Pubkey,
         my_info: &Pubkey,
         bump_seed: u8,
     ) -> Result<Pubkey, SwapError> {
-        Pubkey::create_program_address(&[&my_info.to_bytes()[..32], &[bump_seed]], program_id)
+        let my_bytes = my_info.to_bytes();
+        Pubkey::create_program_address(&Self::authority_seeds(&my_bytes, &[bump_seed]), program_id)
             .or(Err(SwapError::InvalidProgramAddress))
     }
 
+    /// Seeds the swap authority is derived from and signs with
+    fn authority_seeds<'a>(swap_bytes: &'a [u8; 32], bump_seed: &'a [u8; 1]) -> [&'a [u8]; 2] {
+        [&swap_bytes[..], &bump_seed[..]]
+    }
+
     /// Issue a spl_token `Burn` instruction.
     pub fn token_burn<'a>(
         swap: &Pubkey,
@@ -110,7 +134,8 @@ impl Processor {
         amount: u64,
     ) -> Result<(), ProgramError> {
         let swap_bytes = swap.to_bytes();
-        let authority_signature_seeds = [&swap_bytes[..32], &[bump_seed]];
+        let bump_seed = [bump_seed];
+        let authority_signature_seeds = Self::authority_seeds(&swap_bytes, &bump_seed);
         let signers = &[&authority_signature_seeds[..]];
 
         let ix = spl_token_2022::instruction::burn(
@@ -140,7 +165,8 @@ impl Processor {
         amount: u64,
     ) -> Result<(), ProgramError> {
         let swap_bytes = swap.to_bytes();
-        let authority_signature_seeds = [&swap_bytes[..32], &[bump_seed]];
+        let bump_seed = [bump_seed];
+        let authority_signature_seeds = Self::authority_seeds(&swap_bytes, &bump_seed);
         let signers = &[&authority_signature_seeds[..]];
         let ix = spl_token_2022::instruction::mint_to(
             token_program.key,
@@ -172,7 +198,8 @@ impl Processor {
         decimals: u8,
     ) -> Result<(), ProgramError> {
         let swap_bytes = swap.to_bytes();
-        let authority_signature_seeds = [&swap_bytes[..32], &[bump_seed]];
+        let bump_seed = [bump_seed];
+        let authority_signature_seeds = Self::authority_seeds(&swap_bytes, &bump_seed);
         let signers = &[&authority_signature_seeds[..]];
         let ix = spl_token_2022::instruction::transfer_checked(
             token_program.key,
@@ -191,6 +218,32 @@ impl Processor {
         )
     }
 
+    /// Issue a spl_token `SyncNative` instruction.
+    pub fn token_sync_native<'a>(
+        token_program: AccountInfo<'a>,
+        account: AccountInfo<'a>,
+    ) -> Result<(), ProgramError> {
+        let ix = spl_token_2022::instruction::sync_native(token_program.key, account.key)?;
+        invoke_signed_wrapper::<TokenError>(&ix, &[account, token_program], &[])
+    }
+
+    /// Wraps lamports sent to a native SOL source account since it was last
+    /// synced, so users can transfer raw SOL to their wrapped account earlier
+    /// in the same transaction and spend it in the pool. Accounts that cannot
+    /// be unpacked are left for the token transfer to reject.
+    fn sync_native_source<'a>(
+        token_program: &AccountInfo<'a>,
+        source: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        let is_native = Self::unpack_token_account(source, token_program.key)
+            .map(|account| account.is_native())
+            .unwrap_or(false);
+        if is_native {
+            Self::token_sync_native(token_program.clone(), source.clone())?;
+        }
+        Ok(())
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn check_accounts(
         token_swap: &dyn SwapState,
@@ -248,6 +301,8 @@ impl Processor {
         program_id: &Pubkey,
         fees: Fees,
         swap_curve: SwapCurve,
+        price_band_low: u64,
+        price_band_high: u64,
         accounts: &[AccountInfo],
         swap_constraints: &Option<SwapConstraints>,
     ) -> ProgramResult {
@@ -349,6 +404,10 @@ impl Processor {
         }
         fees.validate()?;
         swap_curve.calculator.validate()?;
+        // an inverted band would reject every swap
+        if price_band_low != 0 && price_band_high != 0 && price_band_low > price_band_high {
+            return Err(SwapError::InvalidInput.into());
+        }
 
         let initial_amount = swap_curve.calculator.new_pool_supply();
 
@@ -362,7 +421,7 @@ impl Processor {
             to_u64(initial_amount)?,
         )?;
 
-        let obj = SwapVersion::SwapV1(SwapV1 {
+        let obj = SwapVersion::SwapV2(SwapV2 {
             is_initialized: true,
             bump_seed,
             token_program_id,
@@ -374,11 +433,77 @@ impl Processor {
             pool_fee_account: *fee_account_info.key,
             fees,
             swap_curve,
+            price_band_low,
+            price_band_high,
         });
         SwapVersion::pack(obj, &mut swap_info.data.borrow_mut())?;
         Ok(())
     }
 
+    /// Processes an [InitializeFromReference](enum.Instruction.html).
+    pub fn process_initialize_from_reference(
+        program_id: &Pubkey,
+        fees: Fees,
+        swap_curve: SwapCurve,
+        ratio_tolerance_numerator: u64,
+        ratio_tolerance_denominator: u64,
+        accounts: &[AccountInfo],
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let _swap_info = next_account_info(account_info_iter)?;
+        let _authority_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let _pool_mint_info = next_account_info(account_info_iter)?;
+        let _fee_account_info = next_account_info(account_info_iter)?;
+        let _destination_info = next_account_info(account_info_iter)?;
+        let pool_token_program_info = next_account_info(account_info_iter)?;
+        let reference_swap_info = next_account_info(account_info_iter)?;
+        let reference_token_a_info = next_account_info(account_info_iter)?;
+        let reference_token_b_info = next_account_info(account_info_iter)?;
+
+        if reference_swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let reference_swap = SwapVersion::unpack(&reference_swap_info.data.borrow())?;
+        if *reference_token_a_info.key != *reference_swap.token_a_account() {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *reference_token_b_info.key != *reference_swap.token_b_account() {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        let reference_token_a =
+            Self::unpack_token_account(reference_token_a_info, reference_swap.token_program_id())?;
+        let reference_token_b =
+            Self::unpack_token_account(reference_token_b_info, reference_swap.token_program_id())?;
+
+        let token_a = Self::unpack_token_account(token_a_info, pool_token_program_info.key)?;
+        let token_b = Self::unpack_token_account(token_b_info, pool_token_program_info.key)?;
+        if token_a.mint != *reference_swap.token_a_mint()
+            || token_b.mint != *reference_swap.token_b_mint()
+        {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        check_reference_ratio(
+            (reference_token_a.amount, reference_token_b.amount),
+            token_a.amount,
+            token_b.amount,
+            ratio_tolerance_numerator,
+            ratio_tolerance_denominator,
+        )?;
+
+        Self::process_initialize(
+            program_id,
+            fees,
+            swap_curve,
+            0,
+            0,
+            accounts,
+            swap_constraints,
+        )
+    }
+
     /// Processes an [Swap](enum.Instruction.html).
     pub fn process_swap(
         program_id: &Pubkey,
@@ -445,6 +570,18 @@ impl Processor {
             Self::unpack_token_account(swap_source_info, token_swap.token_program_id())?;
         let dest_account =
             Self::unpack_token_account(swap_destination_info, token_swap.token_program_id())?;
+
+        // the user accounts are checked here to report a bad account directly,
+        // rather than as a failure inside the token transfer
+        let source = Self::unpack_token_account(source_info, source_token_program_info.key)?;
+        let destination =
+            Self::unpack_token_account(destination_info, destination_token_program_info.key)?;
+        if source.mint != source_account.mint {
+            return Err(SwapError::InvalidInput.into());
+        }
+        if destination.mint != dest_account.mint {
+            return Err(SwapError::InvalidOutput.into());
+        }
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
 
         // Take transfer fees into account for actual amount transferred in
@@ -543,6 +680,29 @@ impl Processor {
             ),
         };
 
+        // fees stay in the pool, so a constant product swap can only grow k
+        if token_swap.swap_curve().curve_type == CurveType::ConstantProduct {
+            let invariant_before =
+                crate::invariant_value(source_account.amount, dest_account.amount)
+                    .ok_or(SwapError::CalculationFailure)?;
+            let invariant_after =
+                crate::invariant_value(to_u64(swap_token_a_amount)?, to_u64(swap_token_b_amount)?)
+                    .ok_or(SwapError::CalculationFailure)?;
+            if invariant_after < invariant_before {
+                return Err(SwapError::InvariantDecreased.into());
+            }
+        }
+
+        let price_band = token_swap.price_band();
+        if price_band != (0, 0) {
+            check_price_band(
+                price_band,
+                to_u64(swap_token_a_amount)?,
+                to_u64(swap_token_b_amount)?,
+            )?;
+        }
+
+        Self::sync_native_source(source_token_program_info, source_info)?;
         Self::token_transfer(
             swap_info.key,
             source_token_program_info.clone(),
@@ -624,9 +784,116 @@ impl Processor {
             destination_mint_decimals,
         )?;
 
+        SwapEvent::Swap {
+            amount_in: source_transfer_amount,
+            amount_out: destination_transfer_amount,
+        }
+        .emit(swap_info.key);
+
         Ok(())
     }
 
+    /// Processes a [SwapExactOutput](enum.Instruction.html).
+    ///
+    /// Finds the amount in that provides `output_amount` after the trading
+    /// fees and any transfer fees, then performs an ordinary swap of that
+    /// amount, which checks the accounts and the output.
+    pub fn process_swap_exact_output(
+        program_id: &Pubkey,
+        output_amount: u64,
+        maximum_input: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let _authority_info = next_account_info(account_info_iter)?;
+        let _user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let _source_info = next_account_info(account_info_iter)?;
+        let swap_source_info = next_account_info(account_info_iter)?;
+        let swap_destination_info = next_account_info(account_info_iter)?;
+        let _destination_info = next_account_info(account_info_iter)?;
+        let _pool_mint_info = next_account_info(account_info_iter)?;
+        let _pool_fee_account_info = next_account_info(account_info_iter)?;
+        let source_token_mint_info = next_account_info(account_info_iter)?;
+        let destination_token_mint_info = next_account_info(account_info_iter)?;
+
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        if !token_swap.swap_curve().calculator.allows_exact_output() {
+            return Err(SwapError::UnsupportedCurveOperation.into());
+        }
+        let trade_direction = if *swap_source_info.key == *token_swap.token_a_account() {
+            TradeDirection::AtoB
+        } else if *swap_source_info.key == *token_swap.token_b_account() {
+            TradeDirection::BtoA
+        } else {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        };
+        let source_account =
+            Self::unpack_token_account(swap_source_info, token_swap.token_program_id())?;
+        let dest_account =
+            Self::unpack_token_account(swap_destination_info, token_swap.token_program_id())?;
+
+        // The pool must send enough for the user to receive the output amount
+        // after the destination transfer fee
+        let amount_out = {
+            let destination_mint_data = destination_token_mint_info.data.borrow();
+            let destination_mint = Self::unpack_mint_with_extensions(
+                &destination_mint_data,
+                destination_token_mint_info.owner,
+                token_swap.token_program_id(),
+            )?;
+            if let Ok(transfer_fee_config) = destination_mint.get_extension::<TransferFeeConfig>() {
+                output_amount.saturating_add(
+                    transfer_fee_config
+                        .calculate_inverse_epoch_fee(Clock::get()?.epoch, output_amount)
+                        .ok_or(SwapError::FeeCalculationFailure)?,
+                )
+            } else {
+                output_amount
+            }
+        };
+
+        let result = token_swap
+            .swap_curve()
+            .swap_exact_output(
+                u128::from(amount_out),
+                u128::from(source_account.amount),
+                u128::from(dest_account.amount),
+                trade_direction,
+                token_swap.fees(),
+            )
+            .ok_or(SwapError::ZeroTradingTokens)?;
+
+        // The user must send enough for the pool to receive the source amount
+        // after the source transfer fee
+        let amount_in = {
+            let source_amount_swapped = to_u64(result.source_amount_swapped)?;
+            let source_mint_data = source_token_mint_info.data.borrow();
+            let source_mint = Self::unpack_mint_with_extensions(
+                &source_mint_data,
+                source_token_mint_info.owner,
+                token_swap.token_program_id(),
+            )?;
+            if let Ok(transfer_fee_config) = source_mint.get_extension::<TransferFeeConfig>() {
+                source_amount_swapped.saturating_add(
+                    transfer_fee_config
+                        .calculate_inverse_epoch_fee(Clock::get()?.epoch, source_amount_swapped)
+                        .ok_or(SwapError::FeeCalculationFailure)?,
+                )
+            } else {
+                source_amount_swapped
+            }
+        };
+        if amount_in > maximum_input {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+
+        Self::process_swap(program_id, amount_in, output_amount, accounts)
+    }
+
     /// Processes an [DepositAllTokenTypes](enum.Instruction.html).
     pub fn process_deposit_all_token_types(
         program_id: &Pubkey,
@@ -706,6 +973,8 @@ impl Processor {
 
         let pool_token_amount = to_u64(pool_token_amount)?;
 
+        Self::sync_native_source(token_a_program_info, source_a_info)?;
+        Self::sync_native_source(token_b_program_info, source_b_info)?;
         Self::token_transfer(
             swap_info.key,
             token_a_program_info.clone(),
@@ -738,9 +1007,88 @@ impl Processor {
             pool_token_amount,
         )?;
 
+        SwapEvent::Deposit {
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount,
+        }
+        .emit(swap_info.key);
+
         Ok(())
     }
 
+    /// Processes a [DepositBoth](enum.Instruction.html).
+    pub fn process_deposit_both(
+        program_id: &Pubkey,
+        maximum_token_a_amount: u64,
+        maximum_token_b_amount: u64,
+        minimum_pool_token_amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let _user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let source_a_info = next_account_info(account_info_iter)?;
+        let source_b_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let _dest_info = next_account_info(account_info_iter)?;
+        let _token_a_mint_info = next_account_info(account_info_iter)?;
+        let _token_b_mint_info = next_account_info(account_info_iter)?;
+        let _token_a_program_info = next_account_info(account_info_iter)?;
+        let _token_b_program_info = next_account_info(account_info_iter)?;
+        let pool_token_program_info = next_account_info(account_info_iter)?;
+
+        let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        Self::check_accounts(
+            token_swap.as_ref(),
+            program_id,
+            swap_info,
+            authority_info,
+            token_a_info,
+            token_b_info,
+            pool_mint_info,
+            pool_token_program_info,
+            Some(source_a_info),
+            Some(source_b_info),
+            None,
+        )?;
+
+        let token_a = Self::unpack_token_account(token_a_info, token_swap.token_program_id())?;
+        let token_b = Self::unpack_token_account(token_b_info, token_swap.token_program_id())?;
+        let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
+        let pool_mint_supply = u128::from(pool_mint.supply);
+        if pool_mint_supply == 0 || token_a.amount == 0 || token_b.amount == 0 {
+            return Err(SwapError::EmptySupply.into());
+        }
+
+        // Mint for the limiting side only, rounding down, so the proportional
+        // amounts required for the other side never exceed its maximum
+        let pool_tokens_for_a =
+            Self::pool_tokens_for_deposit(token_a.amount, pool_mint.supply, maximum_token_a_amount)
+                .ok_or(SwapError::CalculationFailure)?;
+        let pool_tokens_for_b =
+            Self::pool_tokens_for_deposit(token_b.amount, pool_mint.supply, maximum_token_b_amount)
+                .ok_or(SwapError::CalculationFailure)?;
+        let pool_token_amount = std::cmp::min(pool_tokens_for_a, pool_tokens_for_b);
+        if pool_token_amount == 0 {
+            return Err(SwapError::ZeroTradingTokens.into());
+        }
+        if pool_token_amount < minimum_pool_token_amount {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+
+        Self::process_deposit_all_token_types(
+            program_id,
+            pool_token_amount,
+            maximum_token_a_amount,
+            maximum_token_b_amount,
+            accounts,
+        )
+    }
+
     /// Processes an [WithdrawAllTokenTypes](enum.Instruction.html).
     pub fn process_withdraw_all_token_types(
         program_id: &Pubkey,
@@ -880,6 +1228,18 @@ impl Processor {
                 Self::unpack_mint(token_b_mint_info, token_swap.token_program_id())?.decimals,
             )?;
         }
+
+        SwapEvent::Withdraw {
+            pool_token_amount: to_u64(
+                pool_token_amount
+                    .checked_add(withdraw_fee)
+                    .ok_or(SwapError::CalculationFailure)?,
+            )?,
+            token_a_amount,
+            token_b_amount,
+        }
+        .emit(swap_info.key);
+
         Ok(())
     }
 
@@ -968,6 +1328,7 @@ impl Processor {
             return Err(SwapError::ZeroTradingTokens.into());
         }
 
+        Self::sync_native_source(source_token_program_info, source_info)?;
         match trade_direction {
             TradeDirection::AtoB => {
                 Self::token_transfer(
@@ -1008,6 +1369,17 @@ impl Processor {
             pool_token_amount,
         )?;
 
+        let (token_a_amount, token_b_amount) = match trade_direction {
+            TradeDirection::AtoB => (source_token_amount, 0),
+            TradeDirection::BtoA => (0, source_token_amount),
+        };
+        SwapEvent::Deposit {
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount,
+        }
+        .emit(swap_info.key);
+
         Ok(())
     }
 
@@ -1163,6 +1535,79 @@ impl Processor {
             }
         }
 
+        let (token_a_amount, token_b_amount) = match trade_direction {
+            TradeDirection::AtoB => (destination_token_amount, 0),
+            TradeDirection::BtoA => (0, destination_token_amount),
+        };
+        SwapEvent::Withdraw {
+            pool_token_amount: to_u64(pool_token_amount)?,
+            token_a_amount,
+            token_b_amount,
+        }
+        .emit(swap_info.key);
+
+        Ok(())
+    }
+
+    /// Processes a [MigratePool](enum.Instruction.html).
+    pub fn process_migrate_pool(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let pool_fee_account_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let token_swap = SwapVersion::migrate(&swap_info.data.borrow())?;
+        if !token_swap.is_initialized() {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        if *pool_fee_account_info.key != *token_swap.pool_fee_account() {
+            return Err(SwapError::IncorrectFeeAccount.into());
+        }
+        let fee_account =
+            Self::unpack_token_account(pool_fee_account_info, token_swap.token_program_id())?;
+        if let Some(swap_constraints) = swap_constraints {
+            let owner_key = swap_constraints
+                .owner_key
+                .unwrap()
+                .parse::<Pubkey>()
+                .map_err(|_| SwapError::InvalidOwner)?;
+            if *admin_info.key != owner_key {
+                return Err(SwapError::InvalidOwner.into());
+            }
+        }
+        if *admin_info.key != fee_account.owner {
+            return Err(SwapError::InvalidOwner.into());
+        }
+        if !admin_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let needed_len = SwapVersion::LATEST_LEN;
+        if swap_info.data_len() < needed_len {
+            swap_info.realloc(needed_len, false)?;
+            let lamports_diff = Rent::get()?
+                .minimum_balance(needed_len)
+                .saturating_sub(swap_info.lamports());
+            if lamports_diff > 0 {
+                invoke(
+                    &system_instruction::transfer(admin_info.key, swap_info.key, lamports_diff),
+                    &[
+                        admin_info.clone(),
+                        swap_info.clone(),
+                        system_program_info.clone(),
+                    ],
+                )?;
+            }
+        }
+        SwapVersion::pack(token_swap, &mut swap_info.data.borrow_mut())?;
         Ok(())
     }
 
@@ -1180,9 +1625,27 @@ impl Processor {
     ) -> ProgramResult {
         let instruction = SwapInstruction::unpack(input)?;
         match instruction {
-            SwapInstruction::Initialize(Initialize { fees, swap_curve }) => {
+            SwapInstruction::Initialize(Initialize {
+                fees,
+                swap_curve,
+                price_band_low,
+                price_band_high,
+                fee_tier,
+            }) => {
                 msg!("Instruction: Init");
-                Self::process_initialize(program_id, fees, swap_curve, accounts, swap_constraints)
+                let fees = match fee_tier {
+                    Some(fee_tier) => fees.with_fee_tier(fee_tier)?,
+                    None => fees,
+                };
+                Self::process_initialize(
+                    program_id,
+                    fees,
+                    swap_curve,
+                    price_band_low,
+                    price_band_high,
+                    accounts,
+                    swap_constraints,
+                )
             }
             SwapInstruction::Swap(Swap {
                 amount_in,
@@ -1191,6 +1654,13 @@ impl Processor {
                 msg!("Instruction: Swap");
                 Self::process_swap(program_id, amount_in, minimum_amount_out, accounts)
             }
+            SwapInstruction::SwapExactOutput(SwapExactOutput {
+                output_amount,
+                maximum_input,
+            }) => {
+                msg!("Instruction: SwapExactOutput");
+                Self::process_swap_exact_output(program_id, output_amount, maximum_input, accounts)
+            }
             SwapInstruction::DepositAllTokenTypes(DepositAllTokenTypes {
                 pool_token_amount,
                 maximum_token_a_amount,
@@ -1247,23 +1717,58 @@ impl Processor {
                     accounts,
                 )
             }
-        }
-    }
-}
-
-fn to_u64(val: u128) -> Result<u64, SwapError> {
-    val.try_into().map_err(|_| SwapError::ConversionFailure)
-}
-
-fn invoke_signed_wrapper<T>(
-    instruction: &Instruction,
-    account_infos: &[AccountInfo],
-    signers_seeds: &[&[&[u8]]],
-) -> Result<(), ProgramError>
-where
-    T: 'static + PrintProgramError + DecodeError<T> + FromPrimitive + Error,
-{
-    invoke_signed(instruction, account_infos, signers_seeds).inspect_err(|err| {
+            SwapInstruction::MigratePool => {
+                msg!("Instruction: MigratePool");
+                Self::process_migrate_pool(program_id, accounts, swap_constraints)
+            }
+            SwapInstruction::DepositBoth(DepositBoth {
+                maximum_token_a_amount,
+                maximum_token_b_amount,
+                minimum_pool_token_amount,
+            }) => {
+                msg!("Instruction: DepositBoth");
+                Self::process_deposit_both(
+                    program_id,
+                    maximum_token_a_amount,
+                    maximum_token_b_amount,
+                    minimum_pool_token_amount,
+                    accounts,
+                )
+            }
+            SwapInstruction::InitializeFromReference(InitializeFromReference {
+                fees,
+                swap_curve,
+                ratio_tolerance_numerator,
+                ratio_tolerance_denominator,
+            }) => {
+                msg!("Instruction: InitializeFromReference");
+                Self::process_initialize_from_reference(
+                    program_id,
+                    fees,
+                    swap_curve,
+                    ratio_tolerance_numerator,
+                    ratio_tolerance_denominator,
+                    accounts,
+                    swap_constraints,
+                )
+            }
+        }
+    }
+}
+
+fn to_u64(val: u128) -> Result<u64, SwapError> {
+    val.try_into().map_err(|_| SwapError::ConversionFailure)
+}
+
+fn invoke_signed_wrapper<T>(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
+) -> Result<(), ProgramError>
+where
+    T: 'static + PrintProgramError + DecodeError<T> + FromPrimitive + Error,
+{
+    invoke_signed(instruction, account_infos, signers_seeds).inspect_err(|err| {
         err.print::<T>();
     })
 }
@@ -1274,16 +1779,19 @@ mod tests {
         super::*,
         crate::{
             curve::{
-                base::CurveType,
-                calculator::{CurveCalculator, INITIAL_SWAP_POOL_AMOUNT},
+                calculator::{CurveCalculator, INITIAL_SWAP_POOL_AMOUNT, MIN_INITIAL_RESERVE},
                 constant_price::ConstantPriceCurve,
                 constant_product::ConstantProductCurve,
                 offset::OffsetCurve,
             },
             instruction::{
-                deposit_all_token_types, deposit_single_token_type_exact_amount_in, initialize,
-                swap, withdraw_all_token_types, withdraw_single_token_type_exact_amount_out,
+                deposit_all_token_types, deposit_both, deposit_single_token_type_exact_amount_in,
+                initialize, initialize_from_reference, initialize_with_fee_tier,
+                initialize_with_price_band, migrate_pool, swap, swap_exact_output,
+                withdraw_all_token_types, withdraw_single_token_type_exact_amount_out,
             },
+            invariant_value,
+            state::PRICE_BAND_PRECISION,
         },
         solana_program::{
             clock::Clock, entrypoint::SUCCESS, instruction::Instruction, program_pack::Pack,
@@ -1370,6 +1878,29 @@ mod tests {
             }
             SUCCESS
         }
+
+        fn sol_log_data(&self, fields: &[&[u8]]) {
+            LOGGED_DATA.with(|logged| {
+                logged
+                    .borrow_mut()
+                    .extend(fields.iter().map(|field| field.to_vec()))
+            });
+        }
+    }
+
+    thread_local! {
+        // Data logged through `sol_log_data` by the current test thread
+        static LOGGED_DATA: std::cell::RefCell<Vec<Vec<u8>>> = const { std::cell::RefCell::new(vec![]) };
+    }
+
+    fn take_swap_events(swap_key: &Pubkey) -> Vec<SwapEvent> {
+        LOGGED_DATA
+            .with(|logged| logged.take())
+            .iter()
+            .filter_map(|data| SwapEvent::unpack(data).ok())
+            .filter(|(_, key)| key == swap_key)
+            .map(|(event, _)| event)
+            .collect()
     }
 
     fn test_syscall_stubs() {
@@ -1516,8 +2047,82 @@ mod tests {
         }
 
         pub fn initialize_swap(&mut self) -> ProgramResult {
+            self.initialize_swap_with_price_band(0, 0)
+        }
+
+        pub fn initialize_swap_with_price_band(
+            &mut self,
+            price_band_low: u64,
+            price_band_high: u64,
+        ) -> ProgramResult {
             do_process_instruction(
-                initialize(
+                initialize_with_price_band(
+                    &SWAP_PROGRAM_ID,
+                    &self.pool_token_program_id,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    &self.pool_token_key,
+                    self.fees.clone(),
+                    self.swap_curve.clone(),
+                    price_band_low,
+                    price_band_high,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut SolanaAccount::default(),
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.pool_mint_account,
+                    &mut self.pool_fee_account,
+                    &mut self.pool_token_account,
+                    &mut SolanaAccount::default(),
+                ],
+            )
+        }
+
+        pub fn initialize_swap_with_fee_tier(&mut self, fee_tier: u8) -> ProgramResult {
+            do_process_instruction(
+                initialize_with_fee_tier(
+                    &SWAP_PROGRAM_ID,
+                    &self.pool_token_program_id,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    &self.pool_token_key,
+                    fee_tier,
+                    self.fees.clone(),
+                    self.swap_curve.clone(),
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut SolanaAccount::default(),
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.pool_mint_account,
+                    &mut self.pool_fee_account,
+                    &mut self.pool_token_account,
+                    &mut SolanaAccount::default(),
+                ],
+            )
+        }
+
+        pub fn initialize_swap_from_reference(
+            &mut self,
+            reference: &mut SwapAccountInfo,
+            ratio_tolerance_numerator: u64,
+            ratio_tolerance_denominator: u64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                initialize_from_reference(
                     &SWAP_PROGRAM_ID,
                     &self.pool_token_program_id,
                     &self.swap_key,
@@ -1527,8 +2132,13 @@ mod tests {
                     &self.pool_mint_key,
                     &self.pool_fee_key,
                     &self.pool_token_key,
+                    &reference.swap_key,
+                    &reference.token_a_key,
+                    &reference.token_b_key,
                     self.fees.clone(),
                     self.swap_curve.clone(),
+                    ratio_tolerance_numerator,
+                    ratio_tolerance_denominator,
                 )
                 .unwrap(),
                 vec![
@@ -1540,6 +2150,9 @@ mod tests {
                     &mut self.pool_fee_account,
                     &mut self.pool_token_account,
                     &mut SolanaAccount::default(),
+                    &mut reference.swap_account,
+                    &mut reference.token_a_account,
+                    &mut reference.token_b_account,
                 ],
             )
         }
@@ -1734,6 +2347,95 @@ mod tests {
             Ok(())
         }
 
+        #[allow(clippy::too_many_arguments)]
+        pub fn swap_exact_output(
+            &mut self,
+            user_key: &Pubkey,
+            user_source_key: &Pubkey,
+            user_source_account: &mut SolanaAccount,
+            swap_source_key: &Pubkey,
+            swap_destination_key: &Pubkey,
+            user_destination_key: &Pubkey,
+            user_destination_account: &mut SolanaAccount,
+            output_amount: u64,
+            maximum_input: u64,
+        ) -> ProgramResult {
+            let user_transfer_key = Pubkey::new_unique();
+            let source_token_program_id = self.get_token_program_id(swap_source_key);
+            let destination_token_program_id = self.get_token_program_id(swap_destination_key);
+            // approve moving up to the maximum from user source account
+            do_process_instruction(
+                approve(
+                    source_token_program_id,
+                    user_source_key,
+                    &user_transfer_key,
+                    user_key,
+                    &[],
+                    maximum_input,
+                )
+                .unwrap(),
+                vec![
+                    user_source_account,
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                ],
+            )
+            .unwrap();
+
+            let (source_mint_key, mut source_mint_account) = self.get_token_mint(swap_source_key);
+            let (destination_mint_key, mut destination_mint_account) =
+                self.get_token_mint(swap_destination_key);
+            let mut swap_source_account = self.get_token_account(swap_source_key).clone();
+            let mut swap_destination_account = self.get_token_account(swap_destination_key).clone();
+
+            do_process_instruction(
+                swap_exact_output(
+                    &SWAP_PROGRAM_ID,
+                    source_token_program_id,
+                    destination_token_program_id,
+                    &self.pool_token_program_id,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &user_transfer_key,
+                    user_source_key,
+                    swap_source_key,
+                    swap_destination_key,
+                    user_destination_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    &source_mint_key,
+                    &destination_mint_key,
+                    None,
+                    SwapExactOutput {
+                        output_amount,
+                        maximum_input,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                    user_source_account,
+                    &mut swap_source_account,
+                    &mut swap_destination_account,
+                    user_destination_account,
+                    &mut self.pool_mint_account,
+                    &mut self.pool_fee_account,
+                    &mut source_mint_account,
+                    &mut destination_mint_account,
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                ],
+            )?;
+
+            self.set_token_account(swap_source_key, swap_source_account);
+            self.set_token_account(swap_destination_key, swap_destination_account);
+
+            Ok(())
+        }
+
         #[allow(clippy::too_many_arguments)]
         pub fn deposit_all_token_types(
             &mut self,
@@ -1832,58 +2534,155 @@ mod tests {
         }
 
         #[allow(clippy::too_many_arguments)]
-        pub fn withdraw_all_token_types(
+        pub fn deposit_both(
             &mut self,
-            user_key: &Pubkey,
-            pool_key: &Pubkey,
-            pool_account: &mut SolanaAccount,
-            token_a_key: &Pubkey,
-            token_a_account: &mut SolanaAccount,
-            token_b_key: &Pubkey,
-            token_b_account: &mut SolanaAccount,
-            pool_token_amount: u64,
-            minimum_token_a_amount: u64,
-            minimum_token_b_amount: u64,
+            depositor_key: &Pubkey,
+            depositor_token_a_key: &Pubkey,
+            depositor_token_a_account: &mut SolanaAccount,
+            depositor_token_b_key: &Pubkey,
+            depositor_token_b_account: &mut SolanaAccount,
+            depositor_pool_key: &Pubkey,
+            depositor_pool_account: &mut SolanaAccount,
+            maximum_token_a_amount: u64,
+            maximum_token_b_amount: u64,
+            minimum_pool_token_amount: u64,
         ) -> ProgramResult {
-            let user_transfer_authority_key = Pubkey::new_unique();
-            let pool_token_program_id = pool_account.owner;
-            // approve user transfer authority to take out pool tokens
+            let user_transfer_authority = Pubkey::new_unique();
+            let token_a_program_id = depositor_token_a_account.owner;
             do_process_instruction(
                 approve(
-                    &pool_token_program_id,
-                    pool_key,
-                    &user_transfer_authority_key,
-                    user_key,
+                    &token_a_program_id,
+                    depositor_token_a_key,
+                    &user_transfer_authority,
+                    depositor_key,
                     &[],
-                    pool_token_amount,
+                    maximum_token_a_amount,
                 )
                 .unwrap(),
                 vec![
-                    pool_account,
+                    depositor_token_a_account,
                     &mut SolanaAccount::default(),
                     &mut SolanaAccount::default(),
                 ],
             )
             .unwrap();
 
-            // withdraw token a and b correctly
-            let token_a_program_id = token_a_account.owner;
-            let token_b_program_id = token_b_account.owner;
+            let token_b_program_id = depositor_token_b_account.owner;
             do_process_instruction(
-                withdraw_all_token_types(
+                approve(
+                    &token_b_program_id,
+                    depositor_token_b_key,
+                    &user_transfer_authority,
+                    depositor_key,
+                    &[],
+                    maximum_token_b_amount,
+                )
+                .unwrap(),
+                vec![
+                    depositor_token_b_account,
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                ],
+            )
+            .unwrap();
+
+            let pool_token_program_id = depositor_pool_account.owner;
+            do_process_instruction(
+                deposit_both(
                     &SWAP_PROGRAM_ID,
-                    &pool_token_program_id,
                     &token_a_program_id,
                     &token_b_program_id,
+                    &pool_token_program_id,
                     &self.swap_key,
                     &self.authority_key,
-                    &user_transfer_authority_key,
-                    &self.pool_mint_key,
-                    &self.pool_fee_key,
-                    pool_key,
+                    &user_transfer_authority,
+                    depositor_token_a_key,
+                    depositor_token_b_key,
                     &self.token_a_key,
                     &self.token_b_key,
-                    token_a_key,
+                    &self.pool_mint_key,
+                    depositor_pool_key,
+                    &self.token_a_mint_key,
+                    &self.token_b_mint_key,
+                    DepositBoth {
+                        maximum_token_a_amount,
+                        maximum_token_b_amount,
+                        minimum_pool_token_amount,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                    depositor_token_a_account,
+                    depositor_token_b_account,
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.pool_mint_account,
+                    depositor_pool_account,
+                    &mut self.token_a_mint_account,
+                    &mut self.token_b_mint_account,
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn withdraw_all_token_types(
+            &mut self,
+            user_key: &Pubkey,
+            pool_key: &Pubkey,
+            pool_account: &mut SolanaAccount,
+            token_a_key: &Pubkey,
+            token_a_account: &mut SolanaAccount,
+            token_b_key: &Pubkey,
+            token_b_account: &mut SolanaAccount,
+            pool_token_amount: u64,
+            minimum_token_a_amount: u64,
+            minimum_token_b_amount: u64,
+        ) -> ProgramResult {
+            let user_transfer_authority_key = Pubkey::new_unique();
+            let pool_token_program_id = pool_account.owner;
+            // approve user transfer authority to take out pool tokens
+            do_process_instruction(
+                approve(
+                    &pool_token_program_id,
+                    pool_key,
+                    &user_transfer_authority_key,
+                    user_key,
+                    &[],
+                    pool_token_amount,
+                )
+                .unwrap(),
+                vec![
+                    pool_account,
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                ],
+            )
+            .unwrap();
+
+            // withdraw token a and b correctly
+            let token_a_program_id = token_a_account.owner;
+            let token_b_program_id = token_b_account.owner;
+            do_process_instruction(
+                withdraw_all_token_types(
+                    &SWAP_PROGRAM_ID,
+                    &pool_token_program_id,
+                    &token_a_program_id,
+                    &token_b_program_id,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &user_transfer_authority_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    pool_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    token_a_key,
                     token_b_key,
                     &self.token_a_mint_key,
                     &self.token_b_mint_key,
@@ -2271,6 +3070,43 @@ mod tests {
         (mint_key, mint_account)
     }
 
+    #[test]
+    fn test_pool_tokens_for_deposit() {
+        // the first deposit mints its own amount
+        assert_eq!(Processor::pool_tokens_for_deposit(0, 0, 1_000), Some(1_000));
+
+        // later deposits mint in proportion to the reserve, rounding down
+        assert_eq!(
+            Processor::pool_tokens_for_deposit(1_000, 5_000, 100),
+            Some(500)
+        );
+        assert_eq!(
+            Processor::pool_tokens_for_deposit(3_000, 1_000, 100),
+            Some(33)
+        );
+        assert_eq!(Processor::pool_tokens_for_deposit(1_000, 5_000, 0), Some(0));
+
+        // the product is taken in u128, but the result must fit a u64
+        assert_eq!(
+            Processor::pool_tokens_for_deposit(u64::MAX, u64::MAX, u64::MAX),
+            Some(u64::MAX)
+        );
+        assert_eq!(Processor::pool_tokens_for_deposit(1, u64::MAX, 2), None);
+    }
+
+    #[test]
+    fn test_authority_id() {
+        for _ in 0..10 {
+            let swap_key = Pubkey::new_unique();
+            let (authority_key, bump_seed) =
+                Pubkey::find_program_address(&[&swap_key.to_bytes()[..]], &SWAP_PROGRAM_ID);
+            assert_eq!(
+                Processor::authority_id(&SWAP_PROGRAM_ID, &swap_key, bump_seed).unwrap(),
+                authority_key
+            );
+        }
+    }
+
     #[test_case(spl_token::id(); "token")]
     #[test_case(spl_token_2022::id(); "token-2022")]
     fn test_token_program_id_error(token_program_id: Pubkey) {
@@ -2686,6 +3522,44 @@ mod tests {
             accounts.token_b_account = old_account;
         }
 
+        // token A reserve below the minimum
+        {
+            let (_token_a_key, token_a_account) = mint_token(
+                &token_a_program_id,
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                MIN_INITIAL_RESERVE - 1,
+            );
+            let old_account = accounts.token_a_account;
+            accounts.token_a_account = token_a_account;
+            assert_eq!(
+                Err(SwapError::InsufficientInitialReserve.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_a_account = old_account;
+        }
+
+        // token B reserve below the minimum
+        {
+            let (_token_b_key, token_b_account) = mint_token(
+                &token_b_program_id,
+                &accounts.token_b_mint_key,
+                &mut accounts.token_b_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                MIN_INITIAL_RESERVE - 1,
+            );
+            let old_account = accounts.token_b_account;
+            accounts.token_b_account = token_b_account;
+            assert_eq!(
+                Err(SwapError::InsufficientInitialReserve.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_b_account = old_account;
+        }
+
         // invalid pool tokens
         {
             let old_mint = accounts.pool_mint_account;
@@ -3998,6 +4872,139 @@ mod tests {
         }
     }
 
+    #[test_case(spl_token::id(), spl_token::id(), spl_token::id(); "all-token")]
+    #[test_case(spl_token_2022::id(), spl_token_2022::id(), spl_token_2022::id(); "all-token-2022")]
+    #[test_case(spl_token::id(), spl_token_2022::id(), spl_token_2022::id(); "mixed-pool-token")]
+    #[test_case(spl_token_2022::id(), spl_token_2022::id(), spl_token::id(); "mixed-pool-token-2022")]
+    fn test_deposit_both(
+        pool_token_program_id: Pubkey,
+        token_a_program_id: Pubkey,
+        token_b_program_id: Pubkey,
+    ) {
+        let user_key = Pubkey::new_unique();
+        let depositor_key = Pubkey::new_unique();
+        let token_a_amount = 1000;
+        let token_b_amount = 9000;
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            Fees::default(),
+            SwapTransferFees::default(),
+            swap_curve,
+            token_a_amount,
+            token_b_amount,
+            &pool_token_program_id,
+            &token_a_program_id,
+            &token_b_program_id,
+        );
+        accounts.initialize_swap().unwrap();
+
+        // token A is the limiting side: 10% of the pool in A, but 20% in B
+        let deposit_a = token_a_amount / 10;
+        let deposit_b = token_b_amount / 5;
+        let pool_amount = INITIAL_SWAP_POOL_AMOUNT / 10;
+
+        // slippage exceeded
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.deposit_both(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    deposit_b,
+                    (pool_amount + 1).try_into().unwrap(),
+                )
+            );
+        }
+
+        // nothing to deposit on one side
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(SwapError::ZeroTradingTokens.into()),
+                accounts.deposit_both(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    0,
+                    deposit_b,
+                    0,
+                )
+            );
+        }
+
+        // correct deposit, only the proportional part of B is taken
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            accounts
+                .deposit_both(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    deposit_b,
+                    pool_amount.try_into().unwrap(),
+                )
+                .unwrap();
+
+            let swap_token_a =
+                StateWithExtensions::<Account>::unpack(&accounts.token_a_account.data).unwrap();
+            assert_eq!(swap_token_a.base.amount, token_a_amount + deposit_a);
+            let swap_token_b =
+                StateWithExtensions::<Account>::unpack(&accounts.token_b_account.data).unwrap();
+            assert_eq!(
+                swap_token_b.base.amount,
+                token_b_amount + token_b_amount / 10
+            );
+            let token_a = StateWithExtensions::<Account>::unpack(&token_a_account.data).unwrap();
+            assert_eq!(token_a.base.amount, 0);
+            let token_b = StateWithExtensions::<Account>::unpack(&token_b_account.data).unwrap();
+            assert_eq!(token_b.base.amount, deposit_b - token_b_amount / 10);
+            let pool_account = StateWithExtensions::<Account>::unpack(&pool_account.data).unwrap();
+            assert_eq!(u128::from(pool_account.base.amount), pool_amount);
+        }
+    }
+
     #[test_case(spl_token::id(), spl_token::id(), spl_token::id(); "all-token")]
     #[test_case(spl_token_2022::id(), spl_token_2022::id(), spl_token_2022::id(); "all-token-2022")]
     #[test_case(spl_token::id(), spl_token_2022::id(), spl_token_2022::id(); "mixed-pool-token")]
@@ -6907,7 +7914,7 @@ mod tests {
                 _pool_account,
             ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
             assert_eq!(
-                Err(TokenError::MintMismatch.into()),
+                Err(SwapError::InvalidInput.into()),
                 accounts.swap(
                     &swapper_key,
                     &token_b_key,
@@ -7078,18 +8085,196 @@ mod tests {
             );
         }
 
-        // output token value 0
+        // user source is not a token account
         {
             let (
                 token_a_key,
-                mut token_a_account,
+                _token_a_account,
                 token_b_key,
                 mut token_b_account,
                 _pool_key,
                 _pool_account,
             ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let mut not_token_account = SolanaAccount::new(0, 10, &token_a_program_id);
             assert_eq!(
-                Err(SwapError::ZeroTradingTokens.into()),
+                Err(SwapError::ExpectedAccount.into()),
+                do_process_instruction(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &token_a_program_id,
+                        &token_b_program_id,
+                        &pool_token_program_id,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_b_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &accounts.token_a_mint_key,
+                        &accounts.token_b_mint_key,
+                        None,
+                        Swap {
+                            amount_in: initial_a,
+                            minimum_amount_out: minimum_token_b_amount,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                        &mut not_token_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.token_a_mint_account,
+                        &mut accounts.token_b_mint_account,
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                    ],
+                ),
+            );
+        }
+
+        // user destination is not a token account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                _token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let mut not_token_account = SolanaAccount::new(0, 10, &token_b_program_id);
+            assert_eq!(
+                Err(SwapError::ExpectedAccount.into()),
+                do_process_instruction(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &token_a_program_id,
+                        &token_b_program_id,
+                        &pool_token_program_id,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_b_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &accounts.token_a_mint_key,
+                        &accounts.token_b_mint_key,
+                        None,
+                        Swap {
+                            amount_in: initial_a,
+                            minimum_amount_out: minimum_token_b_amount,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                        &mut token_a_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut not_token_account,
+                        &mut accounts.pool_mint_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.token_a_mint_account,
+                        &mut accounts.token_b_mint_account,
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                    ],
+                ),
+            );
+        }
+
+        // user destination holds the source mint
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let (
+                other_token_a_key,
+                mut other_token_a_account,
+                _token_b_key,
+                _token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(SwapError::InvalidOutput.into()),
+                do_process_instruction(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &token_a_program_id,
+                        &token_b_program_id,
+                        &pool_token_program_id,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &other_token_a_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &accounts.token_a_mint_key,
+                        &accounts.token_b_mint_key,
+                        None,
+                        Swap {
+                            amount_in: initial_a,
+                            minimum_amount_out: minimum_token_b_amount,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                        &mut token_a_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut other_token_a_account,
+                        &mut accounts.pool_mint_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.token_a_mint_account,
+                        &mut accounts.token_b_mint_account,
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                    ],
+                ),
+            );
+        }
+
+        // output token value 0
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(SwapError::ZeroTradingTokens.into()),
                 accounts.swap(
                     &swapper_key,
                     &token_b_key,
@@ -8373,4 +9558,973 @@ mod tests {
             &token_b_program_id,
         );
     }
+
+    #[test_case(spl_token::id(); "token")]
+    #[test_case(spl_token_2022::id(); "token-2022")]
+    fn test_initialize_from_reference(token_program_id: Pubkey) {
+        let user_key = Pubkey::new_unique();
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let mut reference = SwapAccountInfo::new(
+            &user_key,
+            Fees::default(),
+            SwapTransferFees::default(),
+            swap_curve.clone(),
+            1_000_000,
+            2_000_000,
+            &token_program_id,
+            &token_program_id,
+            &token_program_id,
+        );
+        reference.initialize_swap().unwrap();
+
+        // a tenth of the reference reserves, with a 1% tolerance
+        let token_a_amount = 100_000;
+        for (token_b_amount, expected) in [
+            (200_000, Ok(())),
+            (202_000, Ok(())),
+            (198_000, Ok(())),
+            (202_001, Err(SwapError::ReferenceRatioMismatch.into())),
+            (100_000, Err(SwapError::ReferenceRatioMismatch.into())),
+        ] {
+            let mut accounts = SwapAccountInfo::new(
+                &user_key,
+                Fees::default(),
+                SwapTransferFees::default(),
+                swap_curve.clone(),
+                token_a_amount,
+                token_b_amount,
+                &token_program_id,
+                &token_program_id,
+                &token_program_id,
+            );
+
+            // a pool of unrelated mints has no reference price
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.initialize_swap_from_reference(&mut reference, 1, 100)
+            );
+
+            // hold the reference pool's mints instead
+            let (token_a_key, token_a_account) = mint_token(
+                &token_program_id,
+                &reference.token_a_mint_key,
+                &mut reference.token_a_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                token_a_amount,
+            );
+            let (token_b_key, token_b_account) = mint_token(
+                &token_program_id,
+                &reference.token_b_mint_key,
+                &mut reference.token_b_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                token_b_amount,
+            );
+            accounts.token_a_key = token_a_key;
+            accounts.token_a_account = token_a_account;
+            accounts.token_b_key = token_b_key;
+            accounts.token_b_account = token_b_account;
+
+            assert_eq!(
+                expected,
+                accounts.initialize_swap_from_reference(&mut reference, 1, 100)
+            );
+            if expected.is_ok() {
+                let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+                assert_eq!(*swap_state.token_a_mint(), reference.token_a_mint_key);
+                assert_eq!(*swap_state.token_b_mint(), reference.token_b_mint_key);
+            }
+        }
+
+        // the reference token accounts must be the reference pool's own
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            Fees::default(),
+            SwapTransferFees::default(),
+            swap_curve,
+            1_000,
+            2_000,
+            &token_program_id,
+            &token_program_id,
+            &token_program_id,
+        );
+        std::mem::swap(&mut reference.token_a_key, &mut reference.token_b_key);
+        assert_eq!(
+            Err(SwapError::IncorrectSwapAccount.into()),
+            accounts.initialize_swap_from_reference(&mut reference, 1, 100)
+        );
+    }
+
+    #[test_case(spl_token::id(); "token")]
+    #[test_case(spl_token_2022::id(); "token-2022")]
+    fn test_swap_price_band(token_program_id: Pubkey) {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+
+        // without fees, swapping 1_000_000 of either token into the pool leaves
+        // reserves of 2_000_000 and 500_000, so a price of 0.25 after an A to B
+        // swap and of 4 after a B to A swap
+        let low_edge = PRICE_BAND_PRECISION / 4;
+        let high_edge = PRICE_BAND_PRECISION * 4;
+        let amount_in = 1_000_000;
+
+        for (trade_direction, price_band, expected) in [
+            (
+                TradeDirection::AtoB,
+                (low_edge + 1, 0),
+                Err(SwapError::PriceOutOfBand.into()),
+            ),
+            (TradeDirection::AtoB, (low_edge, 0), Ok(())),
+            (TradeDirection::AtoB, (low_edge, high_edge), Ok(())),
+            (
+                TradeDirection::BtoA,
+                (0, high_edge - 1),
+                Err(SwapError::PriceOutOfBand.into()),
+            ),
+            (TradeDirection::BtoA, (0, high_edge), Ok(())),
+            (TradeDirection::BtoA, (low_edge, high_edge), Ok(())),
+        ] {
+            let swap_curve = SwapCurve {
+                curve_type: CurveType::ConstantProduct,
+                calculator: Arc::new(ConstantProductCurve {}),
+            };
+            let mut accounts = SwapAccountInfo::new(
+                &user_key,
+                Fees::default(),
+                SwapTransferFees::default(),
+                swap_curve,
+                token_a_amount,
+                token_b_amount,
+                &token_program_id,
+                &token_program_id,
+                &token_program_id,
+            );
+            accounts
+                .initialize_swap_with_price_band(price_band.0, price_band.1)
+                .unwrap();
+
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, amount_in, amount_in, 0);
+            let swap_token_a_key = accounts.token_a_key;
+            let swap_token_b_key = accounts.token_b_key;
+            let result = match trade_direction {
+                TradeDirection::AtoB => accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    amount_in,
+                    0,
+                ),
+                TradeDirection::BtoA => accounts.swap(
+                    &swapper_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &swap_token_b_key,
+                    &swap_token_a_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    amount_in,
+                    0,
+                ),
+            };
+            assert_eq!(result, expected);
+        }
+
+        // an inverted band is rejected at initialization
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            Fees::default(),
+            SwapTransferFees::default(),
+            SwapCurve {
+                curve_type: CurveType::ConstantProduct,
+                calculator: Arc::new(ConstantProductCurve {}),
+            },
+            token_a_amount,
+            token_b_amount,
+            &token_program_id,
+            &token_program_id,
+            &token_program_id,
+        );
+        assert_eq!(
+            accounts.initialize_swap_with_price_band(high_edge, low_edge),
+            Err(SwapError::InvalidInput.into())
+        );
+    }
+
+    #[test_case(spl_token::id(); "token")]
+    #[test_case(spl_token_2022::id(); "token-2022")]
+    fn test_initialize_with_fee_tier(token_program_id: Pubkey) {
+        let user_key = Pubkey::new_unique();
+        let new_accounts = || {
+            SwapAccountInfo::new(
+                &user_key,
+                Fees {
+                    trade_fee_numerator: 1,
+                    trade_fee_denominator: 2,
+                    owner_trade_fee_numerator: 1,
+                    owner_trade_fee_denominator: 100,
+                    ..Fees::default()
+                },
+                SwapTransferFees::default(),
+                SwapCurve {
+                    curve_type: CurveType::ConstantProduct,
+                    calculator: Arc::new(ConstantProductCurve {}),
+                },
+                1_000,
+                2_000,
+                &token_program_id,
+                &token_program_id,
+                &token_program_id,
+            )
+        };
+
+        // the tier's trade fee replaces the one given, the other fees are kept
+        let mut accounts = new_accounts();
+        accounts.initialize_swap_with_fee_tier(1).unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_state.fees().trade_fee_numerator, 30);
+        assert_eq!(swap_state.fees().trade_fee_denominator, 10_000);
+        assert_eq!(swap_state.fees().owner_trade_fee_numerator, 1);
+        assert_eq!(swap_state.fees().owner_trade_fee_denominator, 100);
+
+        // an out-of-range tier index is rejected by the program
+        let mut accounts = new_accounts();
+        assert_eq!(
+            accounts.initialize_swap_with_fee_tier(3),
+            Err(SwapError::InvalidFee.into())
+        );
+    }
+
+    #[test_case(spl_token::id(); "token")]
+    #[test_case(spl_token_2022::id(); "token-2022")]
+    fn test_swap_owner_fee_split(token_program_id: Pubkey) {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            ..Fees::default()
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 2_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            SwapTransferFees::default(),
+            swap_curve.clone(),
+            token_a_amount,
+            token_b_amount,
+            &token_program_id,
+            &token_program_id,
+            &token_program_id,
+        );
+        accounts.initialize_swap().unwrap();
+        let initial_supply = StateWithExtensions::<Mint>::unpack(&accounts.pool_mint_account.data)
+            .unwrap()
+            .base
+            .supply;
+
+        let amount_in = 100_000;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, amount_in, 0, 0);
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                amount_in,
+                0,
+            )
+            .unwrap();
+
+        let results = swap_curve
+            .swap(
+                amount_in.into(),
+                token_a_amount.into(),
+                token_b_amount.into(),
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        assert!(results.trade_fee > 0);
+        assert!(results.owner_fee > 0);
+
+        // both fees stay in the reserves
+        let swap_token_a =
+            StateWithExtensions::<Account>::unpack(&accounts.token_a_account.data).unwrap();
+        assert_eq!(swap_token_a.base.amount, token_a_amount + amount_in);
+
+        // the owner's cut is minted as pool tokens to the pool fee account
+        let new_token_a_amount = swap_token_a.base.amount;
+        let new_token_b_amount =
+            StateWithExtensions::<Account>::unpack(&accounts.token_b_account.data)
+                .unwrap()
+                .base
+                .amount;
+        let owner_cut = swap_curve
+            .calculator
+            .withdraw_single_token_type_exact_out(
+                results.owner_fee,
+                new_token_a_amount.into(),
+                new_token_b_amount.into(),
+                initial_supply.into(),
+                TradeDirection::AtoB,
+                RoundDirection::Floor,
+            )
+            .unwrap();
+        let fee_account =
+            StateWithExtensions::<Account>::unpack(&accounts.pool_fee_account.data).unwrap();
+        assert_eq!(u128::from(fee_account.base.amount), owner_cut);
+
+        // no pool tokens are minted for the trade fee, so it accrues to
+        // liquidity providers
+        let pool_mint =
+            StateWithExtensions::<Mint>::unpack(&accounts.pool_mint_account.data).unwrap();
+        assert_eq!(
+            pool_mint.base.supply,
+            initial_supply + fee_account.base.amount
+        );
+    }
+
+    #[test_case(spl_token::id(); "token")]
+    #[test_case(spl_token_2022::id(); "token-2022")]
+    fn test_swap_event(token_program_id: Pubkey) {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            ..Fees::default()
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees,
+            SwapTransferFees::default(),
+            swap_curve,
+            1_000_000,
+            2_000_000,
+            &token_program_id,
+            &token_program_id,
+            &token_program_id,
+        );
+        accounts.initialize_swap().unwrap();
+
+        let amount_in = 100_000;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, amount_in, 0, 0);
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        take_swap_events(&accounts.swap_key);
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                amount_in,
+                0,
+            )
+            .unwrap();
+
+        let amount_out = StateWithExtensions::<Account>::unpack(&token_b_account.data)
+            .unwrap()
+            .base
+            .amount;
+        assert!(amount_out > 0);
+        assert_eq!(
+            take_swap_events(&accounts.swap_key),
+            vec![SwapEvent::Swap {
+                amount_in,
+                amount_out,
+            }]
+        );
+    }
+
+    #[test_case(spl_token::id(); "token")]
+    #[test_case(spl_token_2022::id(); "token-2022")]
+    fn test_swap_grows_invariant(token_program_id: Pubkey) {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            ..Fees::default()
+        };
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees,
+            SwapTransferFees::default(),
+            SwapCurve {
+                curve_type: CurveType::ConstantProduct,
+                calculator: Arc::new(ConstantProductCurve {}),
+            },
+            1_000_000,
+            2_000_000,
+            &token_program_id,
+            &token_program_id,
+            &token_program_id,
+        );
+        accounts.initialize_swap().unwrap();
+
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, 100_000, 0, 0);
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let pool_invariant = |accounts: &SwapAccountInfo| {
+            invariant_value(
+                token_amount(&accounts.token_a_account),
+                token_amount(&accounts.token_b_account),
+            )
+            .unwrap()
+        };
+
+        let invariant_before = pool_invariant(&accounts);
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                50_000,
+                0,
+            )
+            .unwrap();
+        assert!(pool_invariant(&accounts) > invariant_before);
+
+        // reserves at the top of the range do not overflow
+        assert_eq!(
+            invariant_value(u64::MAX, u64::MAX),
+            Some(u128::from(u64::MAX) * u128::from(u64::MAX))
+        );
+    }
+
+    #[test_case(spl_token::id(); "token")]
+    #[test_case(spl_token_2022::id(); "token-2022")]
+    fn test_swap_exact_output(token_program_id: Pubkey) {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            ..Fees::default()
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            SwapTransferFees::default(),
+            swap_curve.clone(),
+            1_000_000,
+            2_000_000,
+            &token_program_id,
+            &token_program_id,
+            &token_program_id,
+        );
+        accounts.initialize_swap().unwrap();
+
+        let initial_a = 500_000;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, 0, 0);
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+
+        let output_amount = 100_000;
+        let expected = swap_curve
+            .swap_exact_output(
+                u128::from(output_amount),
+                1_000_000,
+                2_000_000,
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        let expected_in = to_u64(expected.source_amount_swapped).unwrap();
+
+        // a maximum below the required input is rejected
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            accounts.swap_exact_output(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                output_amount,
+                expected_in - 1,
+            )
+        );
+
+        accounts
+            .swap_exact_output(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                output_amount,
+                expected_in,
+            )
+            .unwrap();
+        let spent = initial_a - token_amount(&token_a_account);
+        let received = token_amount(&token_b_account);
+        assert_eq!(spent, expected_in);
+        assert!(received >= output_amount);
+        assert_eq!(u128::from(received), expected.destination_amount_swapped);
+
+        // curves that cannot be inverted do not support exact output
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees,
+            SwapTransferFees::default(),
+            SwapCurve {
+                curve_type: CurveType::ConstantPrice,
+                calculator: Arc::new(ConstantPriceCurve { token_b_price: 1 }),
+            },
+            1_000_000,
+            1_000_000,
+            &token_program_id,
+            &token_program_id,
+            &token_program_id,
+        );
+        accounts.initialize_swap().unwrap();
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, 0, 0);
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        assert_eq!(
+            Err(SwapError::UnsupportedCurveOperation.into()),
+            accounts.swap_exact_output(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                output_amount,
+                initial_a,
+            )
+        );
+    }
+
+    fn create_native_mint(program_id: &Pubkey) -> (Pubkey, SolanaAccount) {
+        let mint_key = if *program_id == spl_token_2022::id() {
+            spl_token_2022::native_mint::id()
+        } else {
+            spl_token::native_mint::id()
+        };
+        let mut mint_account = SolanaAccount::new(
+            Rent::default().minimum_balance(Mint::LEN),
+            Mint::LEN,
+            program_id,
+        );
+        Mint::pack(
+            Mint {
+                decimals: spl_token::native_mint::DECIMALS,
+                is_initialized: true,
+                ..Mint::default()
+            },
+            &mut mint_account.data,
+        )
+        .unwrap();
+        (mint_key, mint_account)
+    }
+
+    fn create_native_account(
+        program_id: &Pubkey,
+        mint_key: &Pubkey,
+        mint_account: &mut SolanaAccount,
+        account_owner_key: &Pubkey,
+        lamports: u64,
+    ) -> (Pubkey, SolanaAccount) {
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(lamports, Account::LEN, program_id);
+        let mut rent_sysvar_account = create_account_for_test(&Rent::free());
+        do_process_instruction(
+            initialize_account(program_id, &account_key, mint_key, account_owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                mint_account,
+                &mut SolanaAccount::default(),
+                &mut rent_sysvar_account,
+            ],
+        )
+        .unwrap();
+        (account_key, account_account)
+    }
+
+    fn token_amount(account: &SolanaAccount) -> u64 {
+        StateWithExtensions::<Account>::unpack(&account.data)
+            .unwrap()
+            .base
+            .amount
+    }
+
+    #[test_case(spl_token::id(); "token")]
+    #[test_case(spl_token_2022::id(); "token-2022")]
+    fn test_native_sol_side(token_program_id: Pubkey) {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let token_a_amount = 1_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            Fees::default(),
+            SwapTransferFees::default(),
+            swap_curve,
+            token_a_amount,
+            2_000_000,
+            &token_program_id,
+            &token_program_id,
+            &token_program_id,
+        );
+
+        // token A is wrapped SOL
+        let (native_mint_key, native_mint_account) = create_native_mint(&token_program_id);
+        accounts.token_a_mint_key = native_mint_key;
+        accounts.token_a_mint_account = native_mint_account;
+        let (token_a_key, token_a_account) = create_native_account(
+            &token_program_id,
+            &native_mint_key,
+            &mut accounts.token_a_mint_account,
+            &accounts.authority_key,
+            token_a_amount,
+        );
+        accounts.token_a_key = token_a_key;
+        accounts.token_a_account = token_a_account;
+        accounts.initialize_swap().unwrap();
+
+        // raw SOL sent to an empty wrapped account is synced and swapped
+        let amount_in = 100_000;
+        let (source_key, mut source_account) = create_native_account(
+            &token_program_id,
+            &native_mint_key,
+            &mut accounts.token_a_mint_account,
+            &swapper_key,
+            0,
+        );
+        source_account.lamports += amount_in;
+        let (token_b_key, mut token_b_account) = mint_token(
+            &token_program_id,
+            &accounts.token_b_mint_key,
+            &mut accounts.token_b_mint_account,
+            &user_key,
+            &swapper_key,
+            0,
+        );
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        accounts
+            .swap(
+                &swapper_key,
+                &source_key,
+                &mut source_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                amount_in,
+                0,
+            )
+            .unwrap();
+        assert_eq!(source_account.lamports, 0);
+        assert_eq!(token_amount(&source_account), 0);
+        assert_eq!(
+            accounts.token_a_account.lamports,
+            token_a_amount + amount_in
+        );
+        assert_eq!(
+            token_amount(&accounts.token_a_account),
+            token_a_amount + amount_in
+        );
+        let token_b_amount = token_amount(&token_b_account);
+        assert!(token_b_amount > 0);
+
+        // swapping back moves lamports out along with the wrapped SOL
+        let (destination_key, mut destination_account) = create_native_account(
+            &token_program_id,
+            &native_mint_key,
+            &mut accounts.token_a_mint_account,
+            &swapper_key,
+            0,
+        );
+        accounts
+            .swap(
+                &swapper_key,
+                &token_b_key,
+                &mut token_b_account,
+                &swap_token_b_key,
+                &swap_token_a_key,
+                &destination_key,
+                &mut destination_account,
+                token_b_amount,
+                0,
+            )
+            .unwrap();
+        let amount_out = token_amount(&destination_account);
+        assert!(amount_out > 0);
+        assert_eq!(destination_account.lamports, amount_out);
+        assert_eq!(
+            accounts.token_a_account.lamports,
+            token_a_amount + amount_in - amount_out
+        );
+
+        // raw SOL can be deposited the same way
+        let deposit_amount = 10_000;
+        source_account.lamports += deposit_amount;
+        let (pool_key, mut pool_account) = mint_token(
+            &token_program_id,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &accounts.authority_key,
+            &swapper_key,
+            0,
+        );
+        accounts
+            .deposit_single_token_type_exact_amount_in(
+                &swapper_key,
+                &source_key,
+                &mut source_account,
+                &pool_key,
+                &mut pool_account,
+                deposit_amount,
+                0,
+            )
+            .unwrap();
+        assert_eq!(source_account.lamports, 0);
+        assert!(token_amount(&pool_account) > 0);
+        assert_eq!(
+            accounts.token_a_account.lamports,
+            token_a_amount + amount_in - amount_out + deposit_amount
+        );
+    }
+
+    #[test_case(spl_token::id(); "token")]
+    #[test_case(spl_token_2022::id(); "token-2022")]
+    fn test_migrate_pool(token_program_id: Pubkey) {
+        let user_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 2,
+            owner_trade_fee_numerator: 1,
+            owner_trade_fee_denominator: 10,
+            owner_withdraw_fee_numerator: 1,
+            owner_withdraw_fee_denominator: 5,
+            host_fee_numerator: 7,
+            host_fee_denominator: 100,
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees,
+            SwapTransferFees::default(),
+            swap_curve,
+            1000,
+            2000,
+            &token_program_id,
+            &token_program_id,
+            &token_program_id,
+        );
+
+        // uninitialized swap
+        {
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                do_process_instruction(
+                    migrate_pool(
+                        &SWAP_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &accounts.pool_fee_key,
+                        &user_key,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut accounts.pool_fee_account,
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                    ],
+                )
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+        let initialized_data = accounts.swap_account.data.clone();
+
+        // wrong fee account
+        {
+            assert_eq!(
+                Err(SwapError::IncorrectFeeAccount.into()),
+                do_process_instruction(
+                    migrate_pool(
+                        &SWAP_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &accounts.pool_token_key,
+                        &user_key,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut accounts.pool_token_account,
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                    ],
+                )
+            );
+        }
+
+        // admin is not the owner of the fee account
+        {
+            let wrong_admin_key = Pubkey::new_unique();
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                do_process_instruction(
+                    migrate_pool(
+                        &SWAP_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &accounts.pool_fee_key,
+                        &wrong_admin_key,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut accounts.pool_fee_account,
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                    ],
+                )
+            );
+        }
+
+        // admin did not sign
+        {
+            let mut instruction = migrate_pool(
+                &SWAP_PROGRAM_ID,
+                &accounts.swap_key,
+                &accounts.pool_fee_key,
+                &user_key,
+            )
+            .unwrap();
+            instruction.accounts[2].is_signer = false;
+            assert_eq!(
+                Err(ProgramError::MissingRequiredSignature),
+                do_process_instruction(
+                    instruction,
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut accounts.pool_fee_account,
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                    ],
+                )
+            );
+        }
+
+        // pool already at the latest version is rewritten unchanged
+        {
+            do_process_instruction(
+                migrate_pool(
+                    &SWAP_PROGRAM_ID,
+                    &accounts.swap_key,
+                    &accounts.pool_fee_key,
+                    &user_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.swap_account,
+                    &mut accounts.pool_fee_account,
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(accounts.swap_account.data, initialized_data);
+            assert_eq!(accounts.swap_account.data[0], SwapVersion::LATEST_VERSION);
+        }
+    }
 }
@@ -14,6 +14,7 @@ use {
         program_pack::{IsInitialized, Pack, Sealed},
         pubkey::Pubkey,
     },
+    spl_math::{approximations::sqrt, uint::U256},
     spl_token_2022::{
         extension::StateWithExtensions,
         state::{Account, AccountState},
@@ -52,13 +53,115 @@ pub trait SwapState {
     fn fees(&self) -> &Fees;
     /// Curve associated with swap
     fn swap_curve(&self) -> &SwapCurve;
+
+    /// Lowest and highest allowed pool price, as
+    /// `(price_band_low, price_band_high)`, see
+    /// [PRICE_BAND_PRECISION](constant.PRICE_BAND_PRECISION.html)
+    fn price_band(&self) -> (u64, u64);
+}
+
+/// Precision of the pool price band. A price is the amount of token B for
+/// `PRICE_BAND_PRECISION` of token A, in raw token amounts, and a bound of 0
+/// leaves that side of the band open.
+pub const PRICE_BAND_PRECISION: u64 = 1_000_000_000;
+
+/// Check that the price implied by the given reserves is within the
+/// `(price_band_low, price_band_high)` band, inclusive.
+pub fn check_price_band(
+    price_band: (u64, u64),
+    token_a_amount: u64,
+    token_b_amount: u64,
+) -> Result<(), ProgramError> {
+    let (price_band_low, price_band_high) = price_band;
+    // compare price_band_* / PRICE_BAND_PRECISION against b / a by
+    // cross-multiplying, which cannot overflow in u128
+    let scaled_b = u128::from(token_b_amount) * u128::from(PRICE_BAND_PRECISION);
+    let token_a_amount = u128::from(token_a_amount);
+    if price_band_low != 0 && scaled_b < u128::from(price_band_low) * token_a_amount {
+        return Err(SwapError::PriceOutOfBand.into());
+    }
+    if price_band_high != 0 && scaled_b > u128::from(price_band_high) * token_a_amount {
+        return Err(SwapError::PriceOutOfBand.into());
+    }
+    Ok(())
+}
+
+/// Split `total_value`, in token B, into the `(token_a_amount,
+/// token_b_amount)` reserves of a constant product pool priced at `price`
+/// token B for `scale` token A, so that the pool offers no arbitrage at that
+/// price. Such a pool holds half of its value on each side, and both amounts
+/// round down.
+pub fn reserves_for_price(total_value: u64, price: u64, scale: u64) -> Option<(u64, u64)> {
+    if price == 0 || scale == 0 {
+        return None;
+    }
+    let token_b_amount = total_value / 2;
+    let token_a_amount = u128::from(token_b_amount)
+        .checked_mul(u128::from(scale))?
+        .checked_div(u128::from(price))?;
+    Some((u64::try_from(token_a_amount).ok()?, token_b_amount))
+}
+
+/// Square root of the price of token A in token B, in `scale` units, so
+/// `scale * sqrt(b / a)`, computed from the geometric mean of the reserves as
+/// `scale * sqrt(a * b) / a` and rounded down. This is not a price itself: it
+/// only matches the spot price `scale * b / a` for a balanced pool.
+pub fn geometric_mean_price(token_a_amount: u64, token_b_amount: u64, scale: u64) -> Option<u64> {
+    if token_a_amount == 0 {
+        return None;
+    }
+    let geometric_mean = sqrt(u128::from(token_a_amount).checked_mul(u128::from(token_b_amount))?)?;
+    let price = geometric_mean
+        .checked_mul(u128::from(scale))?
+        .checked_div(u128::from(token_a_amount))?;
+    u64::try_from(price).ok()
+}
+
+/// Check that the price implied by the new reserves is within
+/// `tolerance_numerator / tolerance_denominator` of the price implied by the
+/// reference reserves, relative to the reference price. A zero denominator
+/// requires the prices to match exactly.
+pub fn check_reference_ratio(
+    reference_amounts: (u64, u64),
+    token_a_amount: u64,
+    token_b_amount: u64,
+    tolerance_numerator: u64,
+    tolerance_denominator: u64,
+) -> Result<(), ProgramError> {
+    let (reference_a_amount, reference_b_amount) = reference_amounts;
+    if reference_a_amount == 0 || reference_b_amount == 0 {
+        return Err(SwapError::EmptySupply.into());
+    }
+    // |b / a - ref_b / ref_a| <= tolerance * ref_b / ref_a, with both sides
+    // multiplied through by a * ref_a, which cannot overflow in U256
+    let new_cross = U256::from(token_b_amount) * U256::from(reference_a_amount);
+    let reference_cross = U256::from(reference_b_amount) * U256::from(token_a_amount);
+    let difference = if new_cross > reference_cross {
+        new_cross - reference_cross
+    } else {
+        reference_cross - new_cross
+    };
+    let within_tolerance = if tolerance_denominator == 0 {
+        difference.is_zero()
+    } else {
+        difference * U256::from(tolerance_denominator)
+            <= reference_cross * U256::from(tolerance_numerator)
+    };
+    if within_tolerance {
+        Ok(())
+    } else {
+        Err(SwapError::ReferenceRatioMismatch.into())
+    }
 }
 
 /// All versions of SwapState
 #[enum_dispatch(SwapState)]
+#[derive(Debug, PartialEq)]
 pub enum SwapVersion {
-    /// Latest version, used for all new swaps
+    /// Original version, only found in pools that have not been migrated
     SwapV1,
+    /// Latest version, used for all new swaps
+    SwapV2,
 }
 
 /// SwapVersion does not implement program_pack::Pack because there are size
@@ -66,7 +169,10 @@ pub enum SwapVersion {
 /// special implementations are provided here
 impl SwapVersion {
     /// Size of the latest version of the SwapState
-    pub const LATEST_LEN: usize = 1 + SwapV1::LEN; // add one for the version enum
+    pub const LATEST_LEN: usize = 1 + SwapV2::LEN; // add one for the version enum
+
+    /// Version byte of the latest SwapState
+    pub const LATEST_VERSION: u8 = 2;
 
     /// Pack a swap into a byte array, based on its version
     pub fn pack(src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
@@ -75,6 +181,10 @@ impl SwapVersion {
                 dst[0] = 1;
                 SwapV1::pack(swap_info, &mut dst[1..])
             }
+            Self::SwapV2(swap_info) => {
+                dst[0] = 2;
+                SwapV2::pack(swap_info, &mut dst[1..])
+            }
         }
     }
 
@@ -86,6 +196,21 @@ impl SwapVersion {
             .ok_or(ProgramError::InvalidAccountData)?;
         match version {
             1 => Ok(Arc::new(SwapV1::unpack(rest)?)),
+            2 => Ok(Arc::new(SwapV2::unpack(rest)?)),
+            _ => Err(ProgramError::UninitializedAccount),
+        }
+    }
+
+    /// Unpack the swap account based on its version and convert it to the
+    /// latest version, filling any fields missing from older versions with
+    /// their defaults
+    pub fn migrate(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&version, rest) = input
+            .split_first()
+            .ok_or(ProgramError::InvalidAccountData)?;
+        match version {
+            1 => Ok(Self::SwapV2(SwapV1::unpack(rest)?.into())),
+            2 => Ok(Self::SwapV2(SwapV2::unpack(rest)?)),
             _ => Err(ProgramError::UninitializedAccount),
         }
     }
@@ -179,22 +304,7 @@ impl SwapState for SwapV1 {
     }
 
     fn check_pool_fee_info(&self, pool_fee_info: &AccountInfo) -> Result<(), ProgramError> {
-        let data = &pool_fee_info.data.borrow();
-        let token_account =
-            StateWithExtensions::<Account>::unpack(data).map_err(|err| match err {
-                ProgramError::InvalidAccountData | ProgramError::UninitializedAccount => {
-                    SwapError::InvalidFeeAccount.into()
-                }
-                _ => err,
-            })?;
-        if pool_fee_info.owner != &self.token_program_id
-            || token_account.base.state != AccountState::Initialized
-            || token_account.base.mint != self.pool_mint
-        {
-            msg!("Pool fee account is not owned by token program, is not initialized, or does not match stake pool's mint");
-            return Err(SwapError::InvalidFeeAccount.into());
-        }
-        Ok(())
+        check_pool_fee_info(&self.token_program_id, &self.pool_mint, pool_fee_info)
     }
 
     fn fees(&self) -> &Fees {
@@ -204,6 +314,32 @@ impl SwapState for SwapV1 {
     fn swap_curve(&self) -> &SwapCurve {
         &self.swap_curve
     }
+
+    fn price_band(&self) -> (u64, u64) {
+        (0, 0)
+    }
+}
+
+fn check_pool_fee_info(
+    token_program_id: &Pubkey,
+    pool_mint: &Pubkey,
+    pool_fee_info: &AccountInfo,
+) -> Result<(), ProgramError> {
+    let data = &pool_fee_info.data.borrow();
+    let token_account = StateWithExtensions::<Account>::unpack(data).map_err(|err| match err {
+        ProgramError::InvalidAccountData | ProgramError::UninitializedAccount => {
+            SwapError::InvalidFeeAccount.into()
+        }
+        _ => err,
+    })?;
+    if pool_fee_info.owner != token_program_id
+        || token_account.base.state != AccountState::Initialized
+        || token_account.base.mint != *pool_mint
+    {
+        msg!("Pool fee account is not owned by token program, is not initialized, or does not match stake pool's mint");
+        return Err(SwapError::InvalidFeeAccount.into());
+    }
+    Ok(())
 }
 
 impl Sealed for SwapV1 {}
@@ -281,6 +417,209 @@ impl Pack for SwapV1 {
     }
 }
 
+/// Program states, adding a price band to [SwapV1](struct.SwapV1.html).
+#[repr(C)]
+#[derive(Debug, Default, PartialEq)]
+pub struct SwapV2 {
+    /// Initialized state.
+    pub is_initialized: bool,
+    /// Bump seed used in program address.
+    /// The program address is created deterministically with the bump seed,
+    /// swap program id, and swap account pubkey.  This program address has
+    /// authority over the swap's token A account, token B account, and pool
+    /// token mint.
+    pub bump_seed: u8,
+
+    /// Program ID of the tokens being exchanged.
+    pub token_program_id: Pubkey,
+
+    /// Token A
+    pub token_a: Pubkey,
+    /// Token B
+    pub token_b: Pubkey,
+
+    /// Pool tokens are issued when A or B tokens are deposited.
+    /// Pool tokens can be withdrawn back to the original A or B token.
+    pub pool_mint: Pubkey,
+
+    /// Mint information for token A
+    pub token_a_mint: Pubkey,
+    /// Mint information for token B
+    pub token_b_mint: Pubkey,
+
+    /// Pool token account to receive trading and / or withdrawal fees
+    pub pool_fee_account: Pubkey,
+
+    /// All fee information
+    pub fees: Fees,
+
+    /// Swap curve parameters, to be unpacked and used by the SwapCurve, which
+    /// calculates swaps, deposits, and withdrawals
+    pub swap_curve: SwapCurve,
+
+    /// Lowest pool price a swap may leave behind, 0 for no lower bound
+    pub price_band_low: u64,
+    /// Highest pool price a swap may leave behind, 0 for no upper bound
+    pub price_band_high: u64,
+}
+
+impl From<SwapV1> for SwapV2 {
+    fn from(swap: SwapV1) -> Self {
+        Self {
+            is_initialized: swap.is_initialized,
+            bump_seed: swap.bump_seed,
+            token_program_id: swap.token_program_id,
+            token_a: swap.token_a,
+            token_b: swap.token_b,
+            pool_mint: swap.pool_mint,
+            token_a_mint: swap.token_a_mint,
+            token_b_mint: swap.token_b_mint,
+            pool_fee_account: swap.pool_fee_account,
+            fees: swap.fees,
+            swap_curve: swap.swap_curve,
+            price_band_low: 0,
+            price_band_high: 0,
+        }
+    }
+}
+
+impl SwapState for SwapV2 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+
+    fn bump_seed(&self) -> u8 {
+        self.bump_seed
+    }
+
+    fn token_program_id(&self) -> &Pubkey {
+        &self.token_program_id
+    }
+
+    fn token_a_account(&self) -> &Pubkey {
+        &self.token_a
+    }
+
+    fn token_b_account(&self) -> &Pubkey {
+        &self.token_b
+    }
+
+    fn pool_mint(&self) -> &Pubkey {
+        &self.pool_mint
+    }
+
+    fn token_a_mint(&self) -> &Pubkey {
+        &self.token_a_mint
+    }
+
+    fn token_b_mint(&self) -> &Pubkey {
+        &self.token_b_mint
+    }
+
+    fn pool_fee_account(&self) -> &Pubkey {
+        &self.pool_fee_account
+    }
+
+    fn check_pool_fee_info(&self, pool_fee_info: &AccountInfo) -> Result<(), ProgramError> {
+        check_pool_fee_info(&self.token_program_id, &self.pool_mint, pool_fee_info)
+    }
+
+    fn fees(&self) -> &Fees {
+        &self.fees
+    }
+
+    fn swap_curve(&self) -> &SwapCurve {
+        &self.swap_curve
+    }
+
+    fn price_band(&self) -> (u64, u64) {
+        (self.price_band_low, self.price_band_high)
+    }
+}
+
+impl Sealed for SwapV2 {}
+impl IsInitialized for SwapV2 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SwapV2 {
+    const LEN: usize = 339;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 339];
+        let (
+            is_initialized,
+            bump_seed,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            price_band_low,
+            price_band_high,
+        ) = mut_array_refs![output, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 33, 8, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        bump_seed[0] = self.bump_seed;
+        token_program_id.copy_from_slice(self.token_program_id.as_ref());
+        token_a.copy_from_slice(self.token_a.as_ref());
+        token_b.copy_from_slice(self.token_b.as_ref());
+        pool_mint.copy_from_slice(self.pool_mint.as_ref());
+        token_a_mint.copy_from_slice(self.token_a_mint.as_ref());
+        token_b_mint.copy_from_slice(self.token_b_mint.as_ref());
+        pool_fee_account.copy_from_slice(self.pool_fee_account.as_ref());
+        self.fees.pack_into_slice(&mut fees[..]);
+        self.swap_curve.pack_into_slice(&mut swap_curve[..]);
+        *price_band_low = self.price_band_low.to_le_bytes();
+        *price_band_high = self.price_band_high.to_le_bytes();
+    }
+
+    /// Unpacks a byte buffer into a [SwapV2](struct.SwapV2.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 339];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            bump_seed,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            price_band_low,
+            price_band_high,
+        ) = array_refs![input, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 33, 8, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            bump_seed: bump_seed[0],
+            token_program_id: Pubkey::new_from_array(*token_program_id),
+            token_a: Pubkey::new_from_array(*token_a),
+            token_b: Pubkey::new_from_array(*token_b),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            token_a_mint: Pubkey::new_from_array(*token_a_mint),
+            token_b_mint: Pubkey::new_from_array(*token_b_mint),
+            pool_fee_account: Pubkey::new_from_array(*pool_fee_account),
+            fees: Fees::unpack_from_slice(fees)?,
+            swap_curve: SwapCurve::unpack_from_slice(swap_curve)?,
+            price_band_low: u64::from_le_bytes(*price_band_low),
+            price_band_high: u64::from_le_bytes(*price_band_high),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use {super::*, crate::curve::offset::OffsetCurve, std::convert::TryInto};
@@ -305,6 +644,9 @@ mod tests {
     const TEST_TOKEN_B_MINT: Pubkey = Pubkey::new_from_array([6u8; 32]);
     const TEST_POOL_FEE_ACCOUNT: Pubkey = Pubkey::new_from_array([7u8; 32]);
 
+    const TEST_PRICE_BAND_LOW: u64 = 500_000_000;
+    const TEST_PRICE_BAND_HIGH: u64 = 2_000_000_000;
+
     const TEST_CURVE_TYPE: u8 = 2;
     const TEST_TOKEN_B_OFFSET: u64 = 1_000_000_000;
     const TEST_CURVE: OffsetCurve = OffsetCurve {
@@ -319,7 +661,7 @@ mod tests {
             curve_type,
             calculator,
         };
-        let swap_info = SwapVersion::SwapV1(SwapV1 {
+        let swap_info = SwapVersion::SwapV2(SwapV2 {
             is_initialized: true,
             bump_seed: TEST_BUMP_SEED,
             token_program_id: TEST_TOKEN_PROGRAM_ID,
@@ -331,6 +673,8 @@ mod tests {
             pool_fee_account: TEST_POOL_FEE_ACCOUNT,
             fees: TEST_FEES,
             swap_curve: swap_curve.clone(),
+            price_band_low: TEST_PRICE_BAND_LOW,
+            price_band_high: TEST_PRICE_BAND_HIGH,
         });
 
         let mut packed = [0u8; SwapVersion::LATEST_LEN];
@@ -348,6 +692,62 @@ mod tests {
         assert_eq!(*unpacked.pool_fee_account(), TEST_POOL_FEE_ACCOUNT);
         assert_eq!(*unpacked.fees(), TEST_FEES);
         assert_eq!(*unpacked.swap_curve(), swap_curve);
+        assert_eq!(
+            unpacked.price_band(),
+            (TEST_PRICE_BAND_LOW, TEST_PRICE_BAND_HIGH)
+        );
+    }
+
+    #[test]
+    fn swap_version_migrate() {
+        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
+        let calculator = Arc::new(TEST_CURVE);
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator,
+        };
+        let swap_info = SwapV1 {
+            is_initialized: true,
+            bump_seed: TEST_BUMP_SEED,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve,
+        };
+
+        let mut packed = vec![1u8];
+        packed.extend_from_slice(&[0u8; SwapV1::LEN]);
+        SwapV1::pack_into_slice(&swap_info, &mut packed[1..]);
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert_eq!(unpacked.price_band(), (0, 0));
+        let migrated = SwapVersion::migrate(&packed).unwrap();
+        assert_eq!(migrated, SwapVersion::SwapV2(swap_info.into()));
+
+        // the v1 fields keep their layout, and the price band is left open
+        let mut repacked = [0u8; SwapVersion::LATEST_LEN];
+        SwapVersion::pack(migrated, &mut repacked).unwrap();
+        assert_eq!(repacked[0], SwapVersion::LATEST_VERSION);
+        assert_eq!(repacked[1..packed.len()], packed[1..]);
+        assert_eq!(repacked[packed.len()..], [0u8; 16]);
+        assert_eq!(
+            SwapVersion::migrate(&repacked).unwrap(),
+            SwapVersion::migrate(&packed).unwrap()
+        );
+
+        packed[0] = 0;
+        assert_eq!(
+            SwapVersion::migrate(&packed).unwrap_err(),
+            ProgramError::UninitializedAccount
+        );
+        assert_eq!(
+            SwapVersion::migrate(&[]).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
     }
 
     #[test]
@@ -406,4 +806,123 @@ mod tests {
         let err = SwapV1::unpack(&packed).unwrap_err();
         assert_eq!(err, ProgramError::UninitializedAccount);
     }
+
+    #[test]
+    fn price_band_edges() {
+        let band = (TEST_PRICE_BAND_LOW, TEST_PRICE_BAND_HIGH);
+
+        // both edges are inside the band
+        check_price_band(band, 1_000, 500).unwrap();
+        check_price_band(band, 1_000, 2_000).unwrap();
+        check_price_band(band, 1_000, 1_000).unwrap();
+
+        // just outside of either edge
+        assert_eq!(
+            check_price_band(band, 1_000, 499).unwrap_err(),
+            SwapError::PriceOutOfBand.into()
+        );
+        assert_eq!(
+            check_price_band(band, 1_000, 2_001).unwrap_err(),
+            SwapError::PriceOutOfBand.into()
+        );
+
+        // an open side accepts any price
+        check_price_band((0, TEST_PRICE_BAND_HIGH), 1_000, 1).unwrap();
+        check_price_band((TEST_PRICE_BAND_LOW, 0), 1, u64::MAX).unwrap();
+        check_price_band((0, 0), u64::MAX, 0).unwrap();
+    }
+
+    #[test]
+    fn reserves_for_target_price() {
+        // 1:1, the value splits evenly
+        assert_eq!(
+            reserves_for_price(2_000, PRICE_BAND_PRECISION, PRICE_BAND_PRECISION),
+            Some((1_000, 1_000))
+        );
+        // 4 token B per token A, a quarter as much token A for the same value
+        let (token_a_amount, token_b_amount) =
+            reserves_for_price(2_000, 4 * PRICE_BAND_PRECISION, PRICE_BAND_PRECISION).unwrap();
+        assert_eq!((token_a_amount, token_b_amount), (250, 1_000));
+        check_price_band(
+            (4 * PRICE_BAND_PRECISION, 4 * PRICE_BAND_PRECISION),
+            token_a_amount,
+            token_b_amount,
+        )
+        .unwrap();
+
+        // odd values and inexact splits round down
+        assert_eq!(reserves_for_price(2_001, 3, 1), Some((333, 1_000)));
+
+        // cheap token A can need more than a u64
+        assert_eq!(reserves_for_price(u64::MAX, 1, 4), None);
+        assert_eq!(reserves_for_price(2_000, 0, 1), None);
+        assert_eq!(reserves_for_price(2_000, 1, 0), None);
+    }
+
+    #[test]
+    fn geometric_mean_price_against_spot() {
+        let spot_price =
+            |a: u64, b: u64| u128::from(b) * u128::from(PRICE_BAND_PRECISION) / u128::from(a);
+
+        // balanced pools, the prices coincide
+        for amount in [1, 1_000, u64::MAX] {
+            assert_eq!(
+                geometric_mean_price(amount, amount, PRICE_BAND_PRECISION),
+                Some(PRICE_BAND_PRECISION)
+            );
+            assert_eq!(spot_price(amount, amount), u128::from(PRICE_BAND_PRECISION));
+        }
+
+        // imbalanced pools, the result is the square root of the spot price,
+        // in scale units
+        assert_eq!(
+            spot_price(1_000, 4_000),
+            4 * u128::from(PRICE_BAND_PRECISION)
+        );
+        assert_eq!(
+            geometric_mean_price(1_000, 4_000, PRICE_BAND_PRECISION),
+            Some(2 * PRICE_BAND_PRECISION)
+        );
+        assert_eq!(spot_price(9_000, 1_000), 111_111_111);
+        assert_eq!(
+            geometric_mean_price(9_000, 1_000, PRICE_BAND_PRECISION),
+            Some(333_333_333)
+        );
+
+        // empty token A reserve, or a price past a u64
+        assert_eq!(geometric_mean_price(0, 1_000, PRICE_BAND_PRECISION), None);
+        assert_eq!(geometric_mean_price(1, u64::MAX, u64::MAX), None);
+    }
+
+    #[test]
+    fn reference_ratio_tolerance() {
+        let reference = (1_000, 2_000);
+
+        // same ratio at any scale, even without tolerance
+        check_reference_ratio(reference, 1_000, 2_000, 0, 0).unwrap();
+        check_reference_ratio(reference, 500, 1_000, 0, 0).unwrap();
+        check_reference_ratio(reference, u64::MAX / 2, u64::MAX - 1, 0, 0).unwrap();
+        assert_eq!(
+            check_reference_ratio(reference, 1_000, 2_001, 0, 0).unwrap_err(),
+            SwapError::ReferenceRatioMismatch.into()
+        );
+
+        // 1% tolerance on either side of the reference price
+        check_reference_ratio(reference, 1_000, 2_020, 1, 100).unwrap();
+        check_reference_ratio(reference, 1_000, 1_980, 1, 100).unwrap();
+        assert_eq!(
+            check_reference_ratio(reference, 1_000, 2_021, 1, 100).unwrap_err(),
+            SwapError::ReferenceRatioMismatch.into()
+        );
+        assert_eq!(
+            check_reference_ratio(reference, 1_000, 1_979, 1, 100).unwrap_err(),
+            SwapError::ReferenceRatioMismatch.into()
+        );
+
+        // an empty reference has no price
+        assert_eq!(
+            check_reference_ratio((0, 2_000), 1_000, 2_000, 1, 100).unwrap_err(),
+            SwapError::EmptySupply.into()
+        );
+    }
 }
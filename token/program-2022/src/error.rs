@@ -269,6 +269,9 @@ pub enum TokenError {
     /// Transferring, minting, and burning is paused on this mint
     #[error("Transferring, minting, and burning is paused on this mint")]
     MintPaused,
+    /// Extension value is longer than a TLV entry can describe
+    #[error("Extension value is longer than a TLV entry can describe")]
+    ExtensionValueTooLong,
 }
 impl From<TokenError> for ProgramError {
     fn from(e: TokenError) -> Self {
@@ -465,6 +468,9 @@ impl PrintProgramError for TokenError {
             TokenError::MintPaused => {
                 msg!("Transferring, minting, and burning is paused on this mint")
             }
+            TokenError::ExtensionValueTooLong => {
+                msg!("Extension value is longer than a TLV entry can describe")
+            }
         }
     }
 }
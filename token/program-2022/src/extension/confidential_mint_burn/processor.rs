@@ -30,7 +30,10 @@ use {
         pubkey::Pubkey,
     },
     solana_zk_sdk::{
-        encryption::pod::{auth_encryption::PodAeCiphertext, elgamal::PodElGamalPubkey},
+        encryption::pod::{
+            auth_encryption::PodAeCiphertext,
+            elgamal::{PodElGamalCiphertext, PodElGamalPubkey},
+        },
         zk_elgamal_proof_program::proof_data::{
             CiphertextCiphertextEqualityProofContext, CiphertextCiphertextEqualityProofData,
         },
@@ -55,6 +58,29 @@ fn process_initialize_mint(accounts: &[AccountInfo], data: &InitializeMintData)
     Ok(())
 }
 
+/// Checks that a supply ElGamal pubkey rotation proof starts from the mint's
+/// current supply. A mint without a supply pubkey may set one as long as its
+/// confidential supply is still zero, in which case the proof's first pubkey
+/// is not checked.
+#[cfg(feature = "zk-ops")]
+fn check_supply_rotation(
+    mint_burn_extension: &ConfidentialMintBurn,
+    proof_context: &CiphertextCiphertextEqualityProofContext,
+) -> ProgramResult {
+    // `supply_elgamal_pubkey` is a plain pod pubkey, all zeroes when unset
+    if mint_burn_extension.supply_elgamal_pubkey == PodElGamalPubkey::default() {
+        if mint_burn_extension.confidential_supply != PodElGamalCiphertext::default() {
+            return Err(TokenError::InvalidState.into());
+        }
+    } else if mint_burn_extension.supply_elgamal_pubkey != proof_context.first_pubkey {
+        return Err(TokenError::ConfidentialTransferElGamalPubkeyMismatch.into());
+    }
+    if mint_burn_extension.confidential_supply != proof_context.first_ciphertext {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
 /// Processes an [`RotateSupplyElGamal`] instruction.
 #[cfg(feature = "zk-ops")]
 fn process_rotate_supply_elgamal_pubkey(
@@ -80,18 +106,7 @@ fn process_rotate_supply_elgamal_pubkey(
         None,
     )?;
 
-    let supply_elgamal_pubkey: Option<PodElGamalPubkey> =
-        mint_burn_extension.supply_elgamal_pubkey.into();
-    let Some(supply_elgamal_pubkey) = supply_elgamal_pubkey else {
-        return Err(TokenError::InvalidState.into());
-    };
-
-    if !supply_elgamal_pubkey.eq(&proof_context.first_pubkey) {
-        return Err(TokenError::ConfidentialTransferElGamalPubkeyMismatch.into());
-    }
-    if mint_burn_extension.confidential_supply != proof_context.first_ciphertext {
-        return Err(ProgramError::InvalidInstructionData);
-    }
+    check_supply_rotation(mint_burn_extension, &proof_context)?;
 
     let authority_info = next_account_info(account_info_iter)?;
     let authority_info_data_len = authority_info.data_len();
@@ -268,11 +283,33 @@ fn process_confidential_mint(
             .map_err(|_| ProgramError::InvalidAccountData)?,
     )
     .ok_or(TokenError::CiphertextArithmeticFailed)?;
+    verify_supply_consistency(
+        &mint_burn_extension.confidential_supply,
+        &proof_context.new_supply_ciphertext,
+    )?;
     mint_burn_extension.decryptable_supply = data.new_decryptable_supply;
 
     Ok(())
 }
 
+/// Checks that the supply ciphertext computed on chain matches the new supply
+/// ciphertext that the equality proof was generated for.
+///
+/// The equality proof ties `new_supply_ciphertext` to the supply amount the
+/// client used to produce the new `decryptable_supply`. The AE ciphertext
+/// itself cannot be inspected on chain.
+#[cfg(feature = "zk-ops")]
+fn verify_supply_consistency(
+    computed_supply: &PodElGamalCiphertext,
+    proof_context_supply: &PodElGamalCiphertext,
+) -> ProgramResult {
+    if computed_supply != proof_context_supply {
+        msg!("New supply ciphertext does not match the proof context");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
 /// Processes a [`ConfidentialBurn`] instruction.
 #[cfg(feature = "zk-ops")]
 fn process_confidential_burn(
@@ -443,3 +480,100 @@ pub(crate) fn process_instruction(
         }
     }
 }
+
+#[cfg(all(test, feature = "zk-ops"))]
+mod tests {
+    use {
+        super::*,
+        solana_zk_sdk::{
+            encryption::{
+                elgamal::{ElGamalCiphertext, ElGamalKeypair},
+                pedersen::PedersenOpening,
+            },
+            zk_elgamal_proof_program::proof_data::ZkProofData,
+        },
+    };
+
+    fn rotation_proof_context(
+        first_keypair: &ElGamalKeypair,
+        first_ciphertext: &ElGamalCiphertext,
+        second_keypair: &ElGamalKeypair,
+        amount: u64,
+    ) -> CiphertextCiphertextEqualityProofContext {
+        let second_opening = PedersenOpening::new_rand();
+        let second_ciphertext = second_keypair
+            .pubkey()
+            .encrypt_with(amount, &second_opening);
+        *CiphertextCiphertextEqualityProofData::new(
+            first_keypair,
+            second_keypair.pubkey(),
+            first_ciphertext,
+            &second_ciphertext,
+            &second_opening,
+            amount,
+        )
+        .unwrap()
+        .context_data()
+    }
+
+    #[test]
+    fn rotate_supply_pubkey_from_none() {
+        let new_keypair = ElGamalKeypair::new_rand();
+        let zero_supply = ElGamalCiphertext::try_from(PodElGamalCiphertext::default()).unwrap();
+        // any first keypair works, the zero ciphertext decrypts to 0 under all
+        let proof_context =
+            rotation_proof_context(&ElGamalKeypair::new_rand(), &zero_supply, &new_keypair, 0);
+
+        let mut mint_burn_extension = ConfidentialMintBurn::default();
+        check_supply_rotation(&mint_burn_extension, &proof_context).unwrap();
+        assert_eq!(proof_context.second_pubkey, (*new_keypair.pubkey()).into());
+
+        // a mint without a supply pubkey must not have a supply yet
+        mint_burn_extension.confidential_supply = new_keypair.pubkey().encrypt(0_u64).into();
+        assert_eq!(
+            check_supply_rotation(&mint_burn_extension, &proof_context),
+            Err(TokenError::InvalidState.into())
+        );
+
+        // once set, the current pubkey must match the proof
+        let current_keypair = ElGamalKeypair::new_rand();
+        let current_supply = current_keypair.pubkey().encrypt(10_u64);
+        let mint_burn_extension = ConfidentialMintBurn {
+            confidential_supply: current_supply.into(),
+            supply_elgamal_pubkey: (*current_keypair.pubkey()).into(),
+            ..ConfidentialMintBurn::default()
+        };
+        let proof_context =
+            rotation_proof_context(&current_keypair, &current_supply, &new_keypair, 10);
+        check_supply_rotation(&mint_burn_extension, &proof_context).unwrap();
+        let proof_context = rotation_proof_context(&new_keypair, &current_supply, &new_keypair, 10);
+        assert_eq!(
+            check_supply_rotation(&mint_burn_extension, &proof_context),
+            Err(TokenError::ConfidentialTransferElGamalPubkeyMismatch.into())
+        );
+    }
+
+    #[test]
+    fn supply_consistency() {
+        let supply_keypair = ElGamalKeypair::new_rand();
+        let supply_pubkey = supply_keypair.pubkey();
+        let current_supply: PodElGamalCiphertext = supply_pubkey.encrypt(100_u64).into();
+        let mint_amount_lo: PodElGamalCiphertext = supply_pubkey.encrypt(5_u64).into();
+        let mint_amount_hi: PodElGamalCiphertext = supply_pubkey.encrypt(1_u64).into();
+
+        let computed_supply = ciphertext_arithmetic::add_with_lo_hi(
+            &current_supply,
+            &mint_amount_lo,
+            &mint_amount_hi,
+        )
+        .unwrap();
+        verify_supply_consistency(&computed_supply, &computed_supply).unwrap();
+
+        // same supply amount, but not the ciphertext the proof was generated for
+        let other_supply: PodElGamalCiphertext = supply_pubkey.encrypt(65_641_u64).into();
+        assert_eq!(
+            verify_supply_consistency(&computed_supply, &other_supply),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+}
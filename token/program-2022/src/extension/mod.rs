@@ -112,6 +112,12 @@ impl TryFrom<usize> for Length {
     }
 }
 
+/// Maximum length of a single extension value, as limited by the `Length`
+/// of its TLV entry
+pub const fn max_extension_value_len() -> usize {
+    u16::MAX as usize
+}
+
 /// Helper function to get the current `TlvIndices` from the current spot
 fn get_tlv_indices(type_start: usize) -> TlvIndices {
     let length_start = type_start.saturating_add(size_of::<ExtensionType>());
@@ -123,10 +129,67 @@ fn get_tlv_indices(type_start: usize) -> TlvIndices {
     }
 }
 
+/// Checks if an account data length could belong to either a `Multisig` or an
+/// account with extensions, `Multisig::LEN`. Accounts with extensions are
+/// never given that length, so data of that length is always a multisig.
+pub const fn is_ambiguous_length(len: usize) -> bool {
+    len == Multisig::LEN
+}
+
+/// Classifies account data by its length alone. Only the base `Mint`,
+/// `Account`, and `Multisig` lengths can be identified this way; accounts with
+/// extensions store their type after the base state, so any other length
+/// returns `AccountType::Uninitialized`.
+pub const fn account_type_from_len(len: usize) -> AccountType {
+    if is_ambiguous_length(len) {
+        AccountType::Multisig
+    } else if len == Mint::LEN {
+        AccountType::Mint
+    } else if len == Account::LEN {
+        AccountType::Account
+    } else {
+        AccountType::Uninitialized
+    }
+}
+
+/// Checks that the account type byte of account data agrees with its base
+/// state.
+///
+/// Data of a base `Mint`, `Account`, or `Multisig` length stores no type, so
+/// it is always consistent. Longer data must hold a type after
+/// `Account::LEN` bytes. Mints are padded to that length with zeroes, while an
+/// initialized account always has a non-zero state inside the same range, so
+/// the padding tells which type is expected. An account's type may be written
+/// by an extension initializer before `InitializeAccount`, so an all-zero base
+/// may also be labeled as an account.
+pub fn validate_account_type_consistency(buffer: &[u8]) -> Result<(), ProgramError> {
+    if account_type_from_len(buffer.len()) != AccountType::Uninitialized {
+        return Ok(());
+    }
+    if buffer.len() <= BASE_ACCOUNT_LENGTH {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let account_type = AccountType::try_from(buffer[BASE_ACCOUNT_LENGTH])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    let base = &buffer[..BASE_ACCOUNT_LENGTH];
+    let padding_is_empty = base[Mint::LEN..].iter().all(|&b| b == 0);
+    let consistent = match account_type {
+        AccountType::Uninitialized => true,
+        AccountType::Mint => padding_is_empty,
+        AccountType::Account => !padding_is_empty || base.iter().all(|&b| b == 0),
+        AccountType::Multisig => false,
+    };
+    if consistent {
+        Ok(())
+    } else {
+        Err(ProgramError::InvalidAccountData)
+    }
+}
+
 /// Helper function to tack on the size of an extension bytes if an account with
 /// extensions is exactly the size of a multisig
 const fn adjust_len_for_multisig(account_len: usize) -> usize {
-    if account_len == Multisig::LEN {
+    if is_ambiguous_length(account_len) {
         account_len.saturating_add(size_of::<ExtensionType>())
     } else {
         account_len
@@ -149,42 +212,105 @@ struct TlvIndices {
     pub length_start: usize,
     pub value_start: usize,
 }
+
+/// A written TLV entry, with its indices in the TLV buffer
+#[derive(Debug)]
+struct TlvEntry<'data> {
+    extension_type: ExtensionType,
+    tlv_indices: TlvIndices,
+    value: &'data [u8],
+}
+
+/// Iterator over the written entries of a TLV buffer, in order.
+///
+/// Iteration ends at an `Uninitialized` type, since nothing is written after
+/// one, or when there aren't enough bytes left to store another type. An entry
+/// whose length or value doesn't fit in the buffer is malformed, and yields an
+/// error.
+struct TlvIter<'data> {
+    tlv_data: &'data [u8],
+    start_index: usize,
+}
+impl<'data> TlvIter<'data> {
+    fn new(tlv_data: &'data [u8]) -> Self {
+        Self {
+            tlv_data,
+            start_index: 0,
+        }
+    }
+
+    /// Start of the next entry, which is the number of bytes used by the
+    /// entries once iteration has ended
+    fn used_len(&self) -> usize {
+        self.start_index
+    }
+
+    fn next_entry(&mut self) -> Result<Option<TlvEntry<'data>>, ProgramError> {
+        let tlv_data = self.tlv_data;
+        let tlv_indices = get_tlv_indices(self.start_index);
+        if tlv_data.len() < tlv_indices.length_start {
+            // There aren't enough bytes to store the next type, which means we
+            // got to the end. The last byte could be used during a realloc!
+            return Ok(None);
+        }
+        let extension_type =
+            ExtensionType::try_from(&tlv_data[tlv_indices.type_start..tlv_indices.length_start])?;
+        if extension_type == ExtensionType::Uninitialized {
+            return Ok(None);
+        }
+        if tlv_data.len() < tlv_indices.value_start {
+            // not enough bytes to store the length, malformed
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let length =
+            pod_from_bytes::<Length>(&tlv_data[tlv_indices.length_start..tlv_indices.value_start])?;
+        let value_end_index = tlv_indices.value_start.saturating_add(usize::from(*length));
+        if value_end_index > tlv_data.len() {
+            // value blows past the size of the slice, malformed
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let value = &tlv_data[tlv_indices.value_start..value_end_index];
+        self.start_index = value_end_index;
+        Ok(Some(TlvEntry {
+            extension_type,
+            tlv_indices,
+            value,
+        }))
+    }
+}
+impl<'data> Iterator for TlvIter<'data> {
+    type Item = Result<TlvEntry<'data>, ProgramError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry().transpose()
+    }
+}
+
 fn get_extension_indices<V: Extension>(
     tlv_data: &[u8],
     init: bool,
 ) -> Result<TlvIndices, ProgramError> {
-    let mut start_index = 0;
     let v_account_type = V::TYPE.get_account_type();
-    while start_index < tlv_data.len() {
-        let tlv_indices = get_tlv_indices(start_index);
-        if tlv_data.len() < tlv_indices.value_start {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        let extension_type =
-            ExtensionType::try_from(&tlv_data[tlv_indices.type_start..tlv_indices.length_start])?;
-        let account_type = extension_type.get_account_type();
-        if extension_type == V::TYPE {
+    let mut tlv_iter = TlvIter::new(tlv_data);
+    for entry in tlv_iter.by_ref() {
+        let entry = entry?;
+        if entry.extension_type == V::TYPE {
             // found an instance of the extension that we're initializing, return!
-            return Ok(tlv_indices);
-        // got to an empty spot, init here, or error if we're searching, since
-        // nothing is written after an Uninitialized spot
-        } else if extension_type == ExtensionType::Uninitialized {
-            if init {
-                return Ok(tlv_indices);
-            } else {
-                return Err(TokenError::ExtensionNotFound.into());
-            }
-        } else if v_account_type != account_type {
+            return Ok(entry.tlv_indices);
+        } else if v_account_type != entry.extension_type.get_account_type() {
             return Err(TokenError::ExtensionTypeMismatch.into());
-        } else {
-            let length = pod_from_bytes::<Length>(
-                &tlv_data[tlv_indices.length_start..tlv_indices.value_start],
-            )?;
-            let value_end_index = tlv_indices.value_start.saturating_add(usize::from(*length));
-            start_index = value_end_index;
         }
     }
-    Err(ProgramError::InvalidAccountData)
+    // got to an empty spot, init here, or error if we're searching, since
+    // nothing is written after an Uninitialized spot
+    let tlv_indices = get_tlv_indices(tlv_iter.used_len());
+    if tlv_data.len() < tlv_indices.value_start {
+        Err(ProgramError::InvalidAccountData)
+    } else if init {
+        Ok(tlv_indices)
+    } else {
+        Err(TokenError::ExtensionNotFound.into())
+    }
 }
 
 /// Basic information about the TLV buffer, collected from iterating through all
@@ -203,47 +329,50 @@ struct TlvDataInfo {
 /// Fetches basic information about the TLV buffer by iterating through all
 /// TLV entries.
 fn get_tlv_data_info(tlv_data: &[u8]) -> Result<TlvDataInfo, ProgramError> {
+    let mut tlv_iter = TlvIter::new(tlv_data);
+    let extension_types = tlv_iter
+        .by_ref()
+        .map(|entry| entry.map(|entry| entry.extension_type))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(TlvDataInfo {
+        extension_types,
+        used_len: tlv_iter.used_len(),
+    })
+}
+
+/// Collects the type and value bytes of every TLV entry, in the order they
+/// appear in the buffer
+fn get_extension_value_slices(
+    tlv_data: &[u8],
+) -> Result<Vec<(ExtensionType, &[u8])>, ProgramError> {
+    TlvIter::new(tlv_data)
+        .map(|entry| entry.map(|entry| (entry.extension_type, entry.value)))
+        .collect()
+}
+
+/// Walks all TLV entries, checking that each type is known and belongs to the
+/// base state, that no type is repeated, and that each value fits in the
+/// buffer with the expected length for sized types.
+fn validate_tlv_data<S: BaseState>(tlv_data: &[u8]) -> Result<(), ProgramError> {
     let mut extension_types = vec![];
-    let mut start_index = 0;
-    while start_index < tlv_data.len() {
-        let tlv_indices = get_tlv_indices(start_index);
-        if tlv_data.len() < tlv_indices.length_start {
-            // There aren't enough bytes to store the next type, which means we
-            // got to the end. The last byte could be used during a realloc!
-            return Ok(TlvDataInfo {
-                extension_types,
-                used_len: tlv_indices.type_start,
-            });
+    for entry in TlvIter::new(tlv_data) {
+        let TlvEntry {
+            extension_type,
+            value,
+            ..
+        } = entry?;
+        if extension_type.get_account_type() != S::ACCOUNT_TYPE {
+            return Err(TokenError::ExtensionBaseMismatch.into());
         }
-        let extension_type =
-            ExtensionType::try_from(&tlv_data[tlv_indices.type_start..tlv_indices.length_start])?;
-        if extension_type == ExtensionType::Uninitialized {
-            return Ok(TlvDataInfo {
-                extension_types,
-                used_len: tlv_indices.type_start,
-            });
-        } else {
-            if tlv_data.len() < tlv_indices.value_start {
-                // not enough bytes to store the length, malformed
-                return Err(ProgramError::InvalidAccountData);
-            }
-            extension_types.push(extension_type);
-            let length = pod_from_bytes::<Length>(
-                &tlv_data[tlv_indices.length_start..tlv_indices.value_start],
-            )?;
-
-            let value_end_index = tlv_indices.value_start.saturating_add(usize::from(*length));
-            if value_end_index > tlv_data.len() {
-                // value blows past the size of the slice, malformed
-                return Err(ProgramError::InvalidAccountData);
-            }
-            start_index = value_end_index;
+        if extension_types.contains(&extension_type) {
+            return Err(TokenError::ExtensionAlreadyInitialized.into());
         }
+        if extension_type.sized() && value.len() != extension_type.try_get_type_len()? {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        extension_types.push(extension_type);
     }
-    Ok(TlvDataInfo {
-        extension_types,
-        used_len: start_index,
-    })
+    Ok(())
 }
 
 fn get_first_extension_type(tlv_data: &[u8]) -> Result<Option<ExtensionType>, ProgramError> {
@@ -265,7 +394,7 @@ fn get_first_extension_type(tlv_data: &[u8]) -> Result<Option<ExtensionType>, Pr
 }
 
 fn check_min_len_and_not_multisig(input: &[u8], minimum_len: usize) -> Result<(), ProgramError> {
-    if input.len() == Multisig::LEN || input.len() < minimum_len {
+    if is_ambiguous_length(input.len()) || input.len() < minimum_len {
         Err(ProgramError::InvalidAccountData)
     } else {
         Ok(())
@@ -305,13 +434,23 @@ const BASE_ACCOUNT_LENGTH: usize = Account::LEN;
 /// any account with extensions
 const BASE_ACCOUNT_AND_TYPE_LENGTH: usize = BASE_ACCOUNT_LENGTH + size_of::<AccountType>();
 
+/// Start of the extension region, the account type followed by the TLV
+/// entries, relative to the end of the base state `S`.
+///
+/// The region always begins at `Account::LEN`, the larger of the two
+/// extensible bases, so that a mint's extension data can never be mistaken
+/// for an account's. Smaller bases are zero-padded up to that length.
+fn tlv_start_index<S: BaseState>() -> usize {
+    BASE_ACCOUNT_LENGTH.saturating_sub(S::SIZE_OF)
+}
+
 fn type_and_tlv_indices<S: BaseState>(
     rest_input: &[u8],
 ) -> Result<Option<(usize, usize)>, ProgramError> {
     if rest_input.is_empty() {
         Ok(None)
     } else {
-        let account_type_index = BASE_ACCOUNT_LENGTH.saturating_sub(S::SIZE_OF);
+        let account_type_index = tlv_start_index::<S>();
         // check padding is all zeroes
         let tlv_start_index = account_type_index.saturating_add(size_of::<AccountType>());
         if rest_input.len() <= tlv_start_index {
@@ -338,7 +477,7 @@ fn is_initialized_account(input: &[u8]) -> Result<bool, ProgramError> {
 
 fn get_extension_bytes<S: BaseState, V: Extension>(tlv_data: &[u8]) -> Result<&[u8], ProgramError> {
     if V::TYPE.get_account_type() != S::ACCOUNT_TYPE {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(TokenError::ExtensionBaseMismatch.into());
     }
     let TlvIndices {
         type_start: _,
@@ -359,7 +498,7 @@ fn get_extension_bytes_mut<S: BaseState, V: Extension>(
     tlv_data: &mut [u8],
 ) -> Result<&mut [u8], ProgramError> {
     if V::TYPE.get_account_type() != S::ACCOUNT_TYPE {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(TokenError::ExtensionBaseMismatch.into());
     }
     let TlvIndices {
         type_start: _,
@@ -431,6 +570,32 @@ pub trait BaseStateWithExtensions<S: BaseState> {
         get_tlv_data_info(self.get_tlv_data()).map(|x| x.extension_types)
     }
 
+    /// Check that the TLV data is self-consistent: every extension type is
+    /// known and matches the base state, no type appears twice, and every
+    /// value fits in the buffer with the expected length for sized types.
+    ///
+    /// The account type itself is checked when the state is unpacked.
+    fn validate(&self) -> Result<(), ProgramError> {
+        validate_tlv_data::<S>(self.get_tlv_data())
+    }
+
+    /// Iterates through the TLV entries, returning each type with its raw
+    /// value bytes
+    fn get_extension_value_slices(&self) -> Result<Vec<(ExtensionType, &[u8])>, ProgramError> {
+        get_extension_value_slices(self.get_tlv_data())
+    }
+
+    /// Counts the TLV entries with the given extension type. A well-formed
+    /// account has at most one, so anything higher indicates corrupt data.
+    fn count_extension(&self, extension_type: ExtensionType) -> Result<usize, ProgramError> {
+        get_tlv_data_info(self.get_tlv_data()).map(|x| {
+            x.extension_types
+                .iter()
+                .filter(|t| **t == extension_type)
+                .count()
+        })
+    }
+
     /// Get just the first extension type, useful to track mixed initialization
     fn get_first_extension_type(&self) -> Result<Option<ExtensionType>, ProgramError> {
         get_first_extension_type(self.get_tlv_data())
@@ -531,6 +696,22 @@ impl<'data, S: BaseState + Pack> StateWithExtensions<'data, S> {
         let tlv_data = unpack_tlv_data::<S>(rest)?;
         Ok(Self { base, tlv_data })
     }
+
+    /// Unpack base state, leaving the extension data as a slice
+    ///
+    /// Does not check if the base state is initialized, which is useful for
+    /// clients inspecting accounts that are in the middle of being set up.
+    pub fn unpack_unchecked(input: &'data [u8]) -> Result<Self, ProgramError> {
+        check_min_len_and_not_multisig(input, S::SIZE_OF)?;
+        let (base_data, rest) = input.split_at(S::SIZE_OF);
+        let base = S::unpack_unchecked(base_data)?;
+        let tlv_data = if base.is_initialized() {
+            unpack_tlv_data::<S>(rest)?
+        } else {
+            unpack_uninitialized_tlv_data::<S>(rest)?
+        };
+        Ok(Self { base, tlv_data })
+    }
 }
 impl<'a, S: BaseState + Pack> BaseStateWithExtensions<S> for StateWithExtensions<'a, S> {
     fn get_tlv_data(&self) -> &[u8] {
@@ -642,6 +823,27 @@ pub trait BaseStateWithExtensionsMut<S: BaseState>: BaseStateWithExtensions<S> {
         &mut self,
         length: usize,
     ) -> Result<&mut [u8], ProgramError> {
+        self.resize_extension::<V>(length)
+    }
+
+    /// Resize the TLV entry for the given extension to `new_len` bytes,
+    /// shifting all following entries and rewriting the entry's length.
+    ///
+    /// Unlike `realloc`, this also works on fixed-length extensions, for
+    /// example to migrate one to a variable-length layout. Once resized, a
+    /// `Pod` extension can no longer be read with `get_extension`, so the
+    /// caller is responsible for writing the new value into the returned
+    /// bytes.
+    ///
+    /// Returns an error if the extension is not present, or if there is not
+    /// enough space in the buffer for the growth.
+    fn resize_extension<V: Extension>(
+        &mut self,
+        new_len: usize,
+    ) -> Result<&mut [u8], ProgramError> {
+        if new_len > max_extension_value_len() {
+            return Err(TokenError::ExtensionValueTooLong.into());
+        }
         let tlv_data = self.get_tlv_data_mut();
         let TlvIndices {
             type_start: _,
@@ -655,8 +857,8 @@ pub trait BaseStateWithExtensionsMut<S: BaseState>: BaseStateWithExtensions<S> {
         let old_length = usize::from(*length_ref);
 
         // Length check to avoid a panic later in `copy_within`
-        if old_length < length {
-            let new_tlv_len = tlv_len.saturating_add(length.saturating_sub(old_length));
+        if old_length < new_len {
+            let new_tlv_len = tlv_len.saturating_add(new_len.saturating_sub(old_length));
             if new_tlv_len > data_len {
                 return Err(ProgramError::InvalidAccountData);
             }
@@ -664,15 +866,15 @@ pub trait BaseStateWithExtensionsMut<S: BaseState>: BaseStateWithExtensions<S> {
 
         // write new length after the check, to avoid getting into a bad situation
         // if trying to recover from an error
-        *length_ref = Length::try_from(length)?;
+        *length_ref = Length::try_from(new_len)?;
 
         let old_value_end = value_start.saturating_add(old_length);
-        let new_value_end = value_start.saturating_add(length);
+        let new_value_end = value_start.saturating_add(new_len);
         tlv_data.copy_within(old_value_end..tlv_len, new_value_end);
-        match old_length.cmp(&length) {
+        match old_length.cmp(&new_len) {
             Ordering::Greater => {
                 // realloc to smaller, zero out the end
-                let new_tlv_len = tlv_len.saturating_sub(old_length.saturating_sub(length));
+                let new_tlv_len = tlv_len.saturating_sub(old_length.saturating_sub(new_len));
                 tlv_data[new_tlv_len..tlv_len].fill(0);
             }
             Ordering::Less => {
@@ -706,7 +908,10 @@ pub trait BaseStateWithExtensionsMut<S: BaseState>: BaseStateWithExtensions<S> {
         overwrite: bool,
     ) -> Result<&mut [u8], ProgramError> {
         if V::TYPE.get_account_type() != S::ACCOUNT_TYPE {
-            return Err(ProgramError::InvalidAccountData);
+            return Err(TokenError::ExtensionBaseMismatch.into());
+        }
+        if length > max_extension_value_len() {
+            return Err(TokenError::ExtensionValueTooLong.into());
         }
         let tlv_data = self.get_tlv_data_mut();
         let TlvIndices {
@@ -873,6 +1078,59 @@ impl<'data, S: BaseState + Pack> StateWithExtensionsMut<'data, S> {
     pub fn pack_base(&mut self) {
         S::pack_into_slice(&self.base, self.base_data);
     }
+
+    /// Replaces and packs the base state, then writes the account type only
+    /// if it is not already set. The TLV data is never touched.
+    pub fn pack_base_and_type(&mut self, new_base: S) -> Result<(), ProgramError> {
+        self.base = new_base;
+        self.pack_base();
+        match self.account_type.first() {
+            Some(&account_type) if account_type != u8::from(S::ACCOUNT_TYPE) => {
+                self.init_account_type()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Borrow the raw base state bytes, which only reflect changes to `base`
+    /// after `pack_base` is called
+    pub fn base_data_slice(&self) -> &[u8] {
+        self.base_data
+    }
+
+    /// Borrow the raw TLV bytes following the account type
+    pub fn tlv_data_slice(&self) -> &[u8] {
+        self.tlv_data
+    }
+
+    /// Copy the full account data into an owned buffer, which can be unpacked
+    /// again. As with `base_data_slice`, changes to `base` are only included
+    /// after `pack_base` is called.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.base_data.to_vec();
+        if !self.account_type.is_empty() {
+            bytes.resize(BASE_ACCOUNT_LENGTH, 0);
+            bytes.extend_from_slice(self.account_type);
+            bytes.extend_from_slice(self.tlv_data);
+        }
+        bytes
+    }
+
+    /// Compute a checksum over the initialized TLV entries, so that callers
+    /// can cheaply detect whether extensions changed between two reads.
+    /// This is FNV-1a, so it is not suitable for anything adversarial.
+    pub fn tlv_checksum(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+        let used_len = get_tlv_data_info(self.tlv_data)
+            .map(|info| info.used_len)
+            .unwrap_or(self.tlv_data.len());
+        self.tlv_data[..used_len]
+            .iter()
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+            })
+    }
 }
 impl<'a, S: BaseState> BaseStateWithExtensions<S> for StateWithExtensionsMut<'a, S> {
     fn get_tlv_data(&self) -> &[u8] {
@@ -967,6 +1225,20 @@ fn unpack_tlv_data<S: BaseState>(rest: &[u8]) -> Result<&[u8], ProgramError> {
     }
 }
 
+fn unpack_uninitialized_tlv_data<S: BaseState>(rest: &[u8]) -> Result<&[u8], ProgramError> {
+    if let Some((account_type_index, tlv_start_index)) = type_and_tlv_indices::<S>(rest)? {
+        // type_and_tlv_indices() checks that returned indexes are within range
+        let account_type = AccountType::try_from(rest[account_type_index])
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        if account_type != AccountType::Uninitialized {
+            check_account_type::<S>(account_type)?;
+        }
+        Ok(&rest[tlv_start_index..])
+    } else {
+        Ok(&[])
+    }
+}
+
 fn unpack_type_and_tlv_data_with_check_mut<
     S: BaseState,
     F: Fn(AccountType) -> Result<(), ProgramError>,
@@ -1044,6 +1316,9 @@ pub enum AccountType {
     Mint,
     /// Token holding account with additional extensions
     Account,
+    /// Multisig account, never written to account data, only returned by
+    /// `account_type_from_len`
+    Multisig,
 }
 impl Default for AccountType {
     fn default() -> Self {
@@ -1239,6 +1514,18 @@ impl ExtensionType {
         extensions.iter().map(|e| e.try_get_tlv_len()).sum()
     }
 
+    /// Check that no `ExtensionType` appears more than once in the given list
+    ///
+    /// Fails with `InvalidArgument` if any extension type is repeated
+    pub fn check_extension_types_unique(extension_types: &[Self]) -> Result<(), ProgramError> {
+        for (i, extension_type) in extension_types.iter().enumerate() {
+            if extension_types[..i].contains(extension_type) {
+                return Err(ProgramError::InvalidArgument);
+            }
+        }
+        Ok(())
+    }
+
     /// Get the required account data length for the given `ExtensionType`s
     ///
     /// Fails if any of the extension types has a variable length
@@ -1254,6 +1541,49 @@ impl ExtensionType {
         }
     }
 
+    /// Get the required account data length for the given `ExtensionType`s,
+    /// without deduping them
+    ///
+    /// Fails like `try_calculate_account_len`, or with `InvalidArgument` if
+    /// any extension type is repeated
+    pub fn try_calculate_unique_account_len<S: BaseState>(
+        extension_types: &[Self],
+    ) -> Result<usize, ProgramError> {
+        Self::check_extension_types_unique(extension_types)?;
+        Self::try_calculate_account_len::<S>(extension_types)
+    }
+
+    /// Get the required mint data length for the given `ExtensionType`s
+    ///
+    /// Fails like `try_calculate_account_len`, or with
+    /// `ExtensionBaseMismatch` if any extension type belongs on token accounts
+    pub fn try_calculate_mint_len(extension_types: &[Self]) -> Result<usize, ProgramError> {
+        Self::try_calculate_len_for_base::<Mint>(extension_types)
+    }
+
+    /// Get the required token account data length for the given
+    /// `ExtensionType`s
+    ///
+    /// Fails like `try_calculate_account_len`, or with
+    /// `ExtensionBaseMismatch` if any extension type belongs on mints
+    pub fn try_calculate_token_account_len(
+        extension_types: &[Self],
+    ) -> Result<usize, ProgramError> {
+        Self::try_calculate_len_for_base::<Account>(extension_types)
+    }
+
+    fn try_calculate_len_for_base<S: BaseState>(
+        extension_types: &[Self],
+    ) -> Result<usize, ProgramError> {
+        if extension_types
+            .iter()
+            .any(|extension_type| extension_type.get_account_type() != S::ACCOUNT_TYPE)
+        {
+            return Err(TokenError::ExtensionBaseMismatch.into());
+        }
+        Self::try_calculate_account_len::<S>(extension_types)
+    }
+
     /// Get the associated account type
     pub fn get_account_type(&self) -> AccountType {
         match self {
@@ -1555,7 +1885,7 @@ mod test {
         super::*,
         crate::{
             pod::test::{TEST_POD_ACCOUNT, TEST_POD_MINT},
-            state::test::{TEST_ACCOUNT_SLICE, TEST_MINT_SLICE},
+            state::test::{TEST_ACCOUNT, TEST_ACCOUNT_SLICE, TEST_MINT, TEST_MINT_SLICE},
         },
         bytemuck::Pod,
         solana_program::{
@@ -1649,6 +1979,27 @@ mod test {
         1, // data
     ];
 
+    #[test]
+    fn tlv_start_index_per_base() {
+        assert_eq!(tlv_start_index::<Account>(), 0);
+        assert_eq!(tlv_start_index::<Mint>(), Account::LEN - Mint::LEN);
+        assert_eq!(
+            tlv_start_index::<PodAccount>(),
+            tlv_start_index::<Account>()
+        );
+        assert_eq!(tlv_start_index::<PodMint>(), tlv_start_index::<Mint>());
+
+        // the account type lands right after `Account::LEN` for both bases
+        assert_eq!(
+            MINT_WITH_EXTENSION[Mint::LEN + tlv_start_index::<Mint>()],
+            AccountType::Mint as u8
+        );
+        assert_eq!(
+            ACCOUNT_WITH_EXTENSION[Account::LEN + tlv_start_index::<Account>()],
+            AccountType::Account as u8
+        );
+    }
+
     #[test]
     fn unpack_opaque_buffer() {
         // Mint
@@ -1772,6 +2123,40 @@ mod test {
         );
     }
 
+    #[test]
+    fn mint_fail_oversized_length() {
+        // a length that runs past the end of the buffer must never be used to
+        // slice or skip over the value
+        let mut buffer = MINT_WITH_EXTENSION.to_vec();
+        let length_start = BASE_ACCOUNT_LENGTH + 1 + size_of::<ExtensionType>();
+        buffer[length_start..length_start + size_of::<Length>()]
+            .copy_from_slice(&u16::MAX.to_le_bytes());
+
+        let state = PodStateWithExtensions::<PodMint>::unpack(&buffer).unwrap();
+        assert_eq!(
+            state.get_extension::<MintCloseAuthority>(),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            state.get_extension::<TransferFeeConfig>(),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            state.get_extension_types(),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        let mut state = PodStateWithExtensionsMut::<PodMint>::unpack(&mut buffer).unwrap();
+        assert_eq!(
+            state.get_extension_mut::<MintCloseAuthority>(),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            state.init_extension::<TransferFeeConfig>(true),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
     #[test]
     fn account_fail_unpack_opaque_buffer() {
         // input buffer too small
@@ -1905,7 +2290,9 @@ mod test {
         // fail init account extension
         assert_eq!(
             state.init_extension::<TransferFeeAmount>(true),
-            Err(ProgramError::InvalidAccountData),
+            Err(ProgramError::Custom(
+                TokenError::ExtensionBaseMismatch as u32
+            )),
         );
 
         // success write extension
@@ -2049,50 +2436,412 @@ mod test {
     }
 
     #[test]
-    fn mint_extension_any_order() {
-        let mint_size = ExtensionType::try_calculate_account_len::<PodMint>(&[
+    fn mint_with_extension_read_only_unpack() {
+        let mint_size = ExtensionType::try_calculate_account_len::<Mint>(&[
             ExtensionType::MintCloseAuthority,
             ExtensionType::TransferFeeConfig,
         ])
         .unwrap();
         let mut buffer = vec![0; mint_size];
 
-        let mut state =
-            PodStateWithExtensionsMut::<PodMint>::unpack_uninitialized(&mut buffer).unwrap();
-        // write extensions
+        // write extension before the base, as done during mint initialization
         let close_authority =
             OptionalNonZeroPubkey::try_from(Some(Pubkey::new_from_array([1; 32]))).unwrap();
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut buffer).unwrap();
         let extension = state.init_extension::<MintCloseAuthority>(true).unwrap();
         extension.close_authority = close_authority;
 
-        let mint_transfer_fee = test_transfer_fee_config();
-        let extension = state.init_extension::<TransferFeeConfig>(true).unwrap();
-        extension.transfer_fee_config_authority = mint_transfer_fee.transfer_fee_config_authority;
-        extension.withdraw_withheld_authority = mint_transfer_fee.withdraw_withheld_authority;
-        extension.withheld_amount = mint_transfer_fee.withheld_amount;
-        extension.older_transfer_fee = mint_transfer_fee.older_transfer_fee;
-        extension.newer_transfer_fee = mint_transfer_fee.newer_transfer_fee;
+        // fail unpack, base isn't initialized
+        assert_eq!(
+            StateWithExtensions::<Mint>::unpack(&buffer),
+            Err(ProgramError::UninitializedAccount),
+        );
 
+        // unchecked unpack still sees the extension
+        let state = StateWithExtensions::<Mint>::unpack_unchecked(&buffer).unwrap();
+        assert!(!state.base.is_initialized);
         assert_eq!(
             &state.get_extension_types().unwrap(),
-            &[
-                ExtensionType::MintCloseAuthority,
-                ExtensionType::TransferFeeConfig
-            ]
+            &[ExtensionType::MintCloseAuthority]
+        );
+        assert_eq!(
+            state.get_extension::<MintCloseAuthority>().unwrap(),
+            &MintCloseAuthority { close_authority }
         );
 
         // write base mint
-        let mut state =
-            PodStateWithExtensionsMut::<PodMint>::unpack_uninitialized(&mut buffer).unwrap();
-        *state.base = TEST_POD_MINT;
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut buffer).unwrap();
+        state.base = TEST_MINT;
+        state.pack_base();
         state.init_account_type().unwrap();
 
-        let mut other_buffer = vec![0; mint_size];
-        let mut state =
-            PodStateWithExtensionsMut::<PodMint>::unpack_uninitialized(&mut other_buffer).unwrap();
+        // read back through the immutable view
+        let state = StateWithExtensions::<Mint>::unpack(&buffer).unwrap();
+        assert_eq!(state.base, TEST_MINT);
+        assert_eq!(
+            state.get_extension::<MintCloseAuthority>().unwrap(),
+            &MintCloseAuthority { close_authority }
+        );
+        assert_eq!(
+            state.get_extension::<TransferFeeConfig>(),
+            Err(ProgramError::Custom(TokenError::ExtensionNotFound as u32))
+        );
+        assert_eq!(
+            StateWithExtensions::<Mint>::unpack_unchecked(&buffer).unwrap(),
+            state
+        );
 
-        // write base mint
-        *state.base = TEST_POD_MINT;
+        // fail unpack as an account
+        assert_eq!(
+            StateWithExtensions::<Account>::unpack_unchecked(&buffer),
+            Err(ProgramError::InvalidAccountData),
+        );
+    }
+
+    #[test]
+    fn extension_on_wrong_base() {
+        let mint_size =
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::MintCloseAuthority])
+                .unwrap();
+        let mut buffer = vec![0; mint_size];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut buffer).unwrap();
+
+        // account extension cannot be initialized on a mint
+        assert_eq!(
+            state.init_extension::<TransferFeeAmount>(true),
+            Err(ProgramError::Custom(
+                TokenError::ExtensionBaseMismatch as u32
+            ))
+        );
+
+        // nor read back from one
+        state.init_extension::<MintCloseAuthority>(true).unwrap();
+        assert_eq!(
+            state.get_extension::<TransferFeeAmount>(),
+            Err(ProgramError::Custom(
+                TokenError::ExtensionBaseMismatch as u32
+            ))
+        );
+        assert_eq!(
+            state.get_extension_mut::<TransferFeeAmount>(),
+            Err(ProgramError::Custom(
+                TokenError::ExtensionBaseMismatch as u32
+            ))
+        );
+    }
+
+    #[test]
+    fn extension_value_slices() {
+        let mint_size = ExtensionType::try_calculate_account_len::<Mint>(&[
+            ExtensionType::MintCloseAuthority,
+            ExtensionType::TransferFeeConfig,
+        ])
+        .unwrap();
+        let mut buffer = vec![0; mint_size];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut buffer).unwrap();
+        assert_eq!(state.get_extension_value_slices().unwrap(), vec![]);
+
+        let close_authority =
+            OptionalNonZeroPubkey::try_from(Some(Pubkey::new_from_array([1; 32]))).unwrap();
+        state
+            .init_extension::<MintCloseAuthority>(true)
+            .unwrap()
+            .close_authority = close_authority;
+        *state.init_extension::<TransferFeeConfig>(true).unwrap() = test_transfer_fee_config();
+
+        let expected_transfer_fee_config = test_transfer_fee_config();
+        assert_eq!(
+            state.get_extension_value_slices().unwrap(),
+            vec![
+                (
+                    ExtensionType::MintCloseAuthority,
+                    bytemuck::bytes_of(&MintCloseAuthority { close_authority })
+                ),
+                (
+                    ExtensionType::TransferFeeConfig,
+                    bytemuck::bytes_of(&expected_transfer_fee_config)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn base_and_tlv_data_slices() {
+        let mut buffer = MINT_WITH_EXTENSION.to_vec();
+        let mut state = StateWithExtensionsMut::<Mint>::unpack(&mut buffer).unwrap();
+        assert_eq!(state.base_data_slice().len(), Mint::LEN);
+        assert_eq!(state.base_data_slice(), &MINT_WITH_EXTENSION[..Mint::LEN]);
+        assert_eq!(
+            state.tlv_data_slice(),
+            &MINT_WITH_EXTENSION[BASE_ACCOUNT_AND_TYPE_LENGTH..]
+        );
+        assert_eq!(state.tlv_data_slice(), state.get_tlv_data());
+
+        // base changes only show up once packed
+        state.base.supply = 1;
+        assert_eq!(state.base_data_slice(), &MINT_WITH_EXTENSION[..Mint::LEN]);
+        state.pack_base();
+        assert_eq!(Mint::unpack(state.base_data_slice()).unwrap().supply, 1);
+    }
+
+    #[test]
+    fn tlv_checksum_tracks_extensions() {
+        let mint_size =
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::MintCloseAuthority])
+                .unwrap();
+        let mut buffer = vec![0; mint_size];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut buffer).unwrap();
+        state.base = TEST_MINT;
+        state.pack_base();
+        state.init_account_type().unwrap();
+        let empty_checksum = state.tlv_checksum();
+
+        state.init_extension::<MintCloseAuthority>(true).unwrap();
+        let checksum = state.tlv_checksum();
+        assert_ne!(checksum, empty_checksum);
+
+        // base changes are not covered
+        state.base.supply += 1;
+        state.pack_base();
+        assert_eq!(state.tlv_checksum(), checksum);
+
+        // extension changes are
+        state
+            .get_extension_mut::<MintCloseAuthority>()
+            .unwrap()
+            .close_authority =
+            OptionalNonZeroPubkey::try_from(Some(Pubkey::new_from_array([1; 32]))).unwrap();
+        assert_ne!(state.tlv_checksum(), checksum);
+    }
+
+    #[test]
+    fn pack_base_and_type_keeps_extensions() {
+        let mint_size =
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::MintCloseAuthority])
+                .unwrap();
+        let mut buffer = vec![0; mint_size];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut buffer).unwrap();
+        let close_authority =
+            OptionalNonZeroPubkey::try_from(Some(Pubkey::new_from_array([1; 32]))).unwrap();
+        state
+            .init_extension::<MintCloseAuthority>(true)
+            .unwrap()
+            .close_authority = close_authority;
+        let tlv_data = state.tlv_data_slice().to_vec();
+
+        // the first call sets the account type
+        state.pack_base_and_type(TEST_MINT).unwrap();
+        let mut expect = TEST_MINT_SLICE.to_vec();
+        expect.extend_from_slice(&[0; BASE_ACCOUNT_LENGTH - Mint::LEN]);
+        expect.push(AccountType::Mint.into());
+        expect.extend_from_slice(&tlv_data);
+        assert_eq!(state.to_bytes(), expect);
+
+        // repeated base updates only change the base
+        for supply in 1..4 {
+            let mint = Mint {
+                supply,
+                ..TEST_MINT
+            };
+            state.pack_base_and_type(mint).unwrap();
+            assert_eq!(state.base, mint);
+            assert_eq!(state.tlv_data_slice(), tlv_data);
+        }
+        drop(state);
+        let state = StateWithExtensions::<Mint>::unpack(&buffer).unwrap();
+        assert_eq!(state.base.supply, 3);
+        assert_eq!(
+            state
+                .get_extension::<MintCloseAuthority>()
+                .unwrap()
+                .close_authority,
+            close_authority
+        );
+    }
+
+    #[test]
+    fn to_bytes_round_trip() {
+        let mint_size =
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::MintCloseAuthority])
+                .unwrap();
+        let mut buffer = vec![0; mint_size];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut buffer).unwrap();
+        state.base = TEST_MINT;
+        state.pack_base();
+        state.init_account_type().unwrap();
+        let close_authority =
+            OptionalNonZeroPubkey::try_from(Some(Pubkey::new_from_array([1; 32]))).unwrap();
+        state
+            .init_extension::<MintCloseAuthority>(true)
+            .unwrap()
+            .close_authority = close_authority;
+
+        let bytes = state.to_bytes();
+        assert_eq!(bytes, buffer);
+        let state = StateWithExtensions::<Mint>::unpack(&bytes).unwrap();
+        assert_eq!(state.base, TEST_MINT);
+        assert_eq!(
+            state.get_extension::<MintCloseAuthority>().unwrap(),
+            &MintCloseAuthority { close_authority }
+        );
+
+        // without extensions, only the base state is copied
+        let mut buffer = vec![0; Account::LEN];
+        let mut state =
+            StateWithExtensionsMut::<Account>::unpack_uninitialized(&mut buffer).unwrap();
+        state.base = TEST_ACCOUNT;
+        state.pack_base();
+        let bytes = state.to_bytes();
+        assert_eq!(bytes.len(), Account::LEN);
+        assert_eq!(
+            StateWithExtensions::<Account>::unpack(&bytes).unwrap().base,
+            TEST_ACCOUNT
+        );
+    }
+
+    #[test]
+    fn count_duplicate_extension() {
+        let state = StateWithExtensions::<Mint>::unpack(MINT_WITH_EXTENSION).unwrap();
+        assert_eq!(
+            state.count_extension(ExtensionType::MintCloseAuthority),
+            Ok(1)
+        );
+        assert_eq!(
+            state.count_extension(ExtensionType::TransferFeeConfig),
+            Ok(0)
+        );
+
+        // repeat the close authority entry
+        let mut buffer = MINT_WITH_EXTENSION.to_vec();
+        buffer.extend_from_slice(&MINT_WITH_EXTENSION[BASE_ACCOUNT_AND_TYPE_LENGTH..]);
+        let state = StateWithExtensions::<Mint>::unpack(&buffer).unwrap();
+        assert_eq!(
+            state.count_extension(ExtensionType::MintCloseAuthority),
+            Ok(2)
+        );
+        let state = StateWithExtensionsMut::<Mint>::unpack(&mut buffer).unwrap();
+        assert_eq!(
+            state.count_extension(ExtensionType::MintCloseAuthority),
+            Ok(2)
+        );
+    }
+
+    #[test]
+    fn validate_extension_data() {
+        let mint_size = ExtensionType::try_calculate_account_len::<Mint>(&[
+            ExtensionType::MintCloseAuthority,
+            ExtensionType::TransferFeeConfig,
+        ])
+        .unwrap();
+        let mut buffer = vec![0; mint_size];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut buffer).unwrap();
+        state.base = TEST_MINT;
+        state.pack_base();
+        state.init_account_type().unwrap();
+        state.init_extension::<MintCloseAuthority>(true).unwrap();
+        state.init_extension::<TransferFeeConfig>(true).unwrap();
+        assert_eq!(state.validate(), Ok(()));
+
+        let first_type_start = BASE_ACCOUNT_AND_TYPE_LENGTH;
+        let first_length_start = first_type_start + size_of::<ExtensionType>();
+        let second_type_start =
+            first_type_start + add_type_and_length_to_len(size_of::<MintCloseAuthority>());
+        let second_length_start = second_type_start + size_of::<ExtensionType>();
+
+        // read-only state agrees
+        let state = StateWithExtensions::<Mint>::unpack(&buffer).unwrap();
+        assert_eq!(state.validate(), Ok(()));
+
+        // unknown extension type
+        let mut corrupted = buffer.clone();
+        corrupted[first_type_start..first_length_start].copy_from_slice(&[0xfe, 0x7f]);
+        let state = StateWithExtensions::<Mint>::unpack(&corrupted).unwrap();
+        assert_eq!(state.validate(), Err(ProgramError::InvalidAccountData));
+
+        // account extension in a mint
+        let mut corrupted = buffer.clone();
+        corrupted[first_type_start..first_length_start]
+            .copy_from_slice(&(ExtensionType::ImmutableOwner as u16).to_le_bytes());
+        let state = StateWithExtensions::<Mint>::unpack(&corrupted).unwrap();
+        assert_eq!(
+            state.validate(),
+            Err(ProgramError::Custom(
+                TokenError::ExtensionBaseMismatch as u32
+            ))
+        );
+
+        // repeated extension type
+        let mut corrupted = buffer.clone();
+        corrupted[second_type_start..second_length_start]
+            .copy_from_slice(&(ExtensionType::MintCloseAuthority as u16).to_le_bytes());
+        let state = StateWithExtensions::<Mint>::unpack(&corrupted).unwrap();
+        assert_eq!(
+            state.validate(),
+            Err(ProgramError::Custom(
+                TokenError::ExtensionAlreadyInitialized as u32
+            ))
+        );
+
+        // sized extension with the wrong length
+        let mut corrupted = buffer.clone();
+        corrupted[first_length_start..first_length_start + size_of::<Length>()]
+            .copy_from_slice(&(size_of::<MintCloseAuthority>() as u16 - 1).to_le_bytes());
+        let state = StateWithExtensions::<Mint>::unpack(&corrupted).unwrap();
+        assert_eq!(state.validate(), Err(ProgramError::InvalidAccountData));
+
+        // value runs past the end of the buffer
+        let mut corrupted = buffer.clone();
+        corrupted[second_length_start..second_length_start + size_of::<Length>()]
+            .copy_from_slice(&u16::MAX.to_le_bytes());
+        let state = StateWithExtensions::<Mint>::unpack(&corrupted).unwrap();
+        assert_eq!(state.validate(), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn mint_extension_any_order() {
+        let mint_size = ExtensionType::try_calculate_account_len::<PodMint>(&[
+            ExtensionType::MintCloseAuthority,
+            ExtensionType::TransferFeeConfig,
+        ])
+        .unwrap();
+        let mut buffer = vec![0; mint_size];
+
+        let mut state =
+            PodStateWithExtensionsMut::<PodMint>::unpack_uninitialized(&mut buffer).unwrap();
+        // write extensions
+        let close_authority =
+            OptionalNonZeroPubkey::try_from(Some(Pubkey::new_from_array([1; 32]))).unwrap();
+        let extension = state.init_extension::<MintCloseAuthority>(true).unwrap();
+        extension.close_authority = close_authority;
+
+        let mint_transfer_fee = test_transfer_fee_config();
+        let extension = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        extension.transfer_fee_config_authority = mint_transfer_fee.transfer_fee_config_authority;
+        extension.withdraw_withheld_authority = mint_transfer_fee.withdraw_withheld_authority;
+        extension.withheld_amount = mint_transfer_fee.withheld_amount;
+        extension.older_transfer_fee = mint_transfer_fee.older_transfer_fee;
+        extension.newer_transfer_fee = mint_transfer_fee.newer_transfer_fee;
+
+        assert_eq!(
+            &state.get_extension_types().unwrap(),
+            &[
+                ExtensionType::MintCloseAuthority,
+                ExtensionType::TransferFeeConfig
+            ]
+        );
+
+        // write base mint
+        let mut state =
+            PodStateWithExtensionsMut::<PodMint>::unpack_uninitialized(&mut buffer).unwrap();
+        *state.base = TEST_POD_MINT;
+        state.init_account_type().unwrap();
+
+        let mut other_buffer = vec![0; mint_size];
+        let mut state =
+            PodStateWithExtensionsMut::<PodMint>::unpack_uninitialized(&mut other_buffer).unwrap();
+
+        // write base mint
+        *state.base = TEST_POD_MINT;
         state.init_account_type().unwrap();
 
         // write extensions in a different order
@@ -2194,7 +2943,9 @@ mod test {
         // fail init mint extension
         assert_eq!(
             state.init_extension::<TransferFeeConfig>(true),
-            Err(ProgramError::InvalidAccountData),
+            Err(ProgramError::Custom(
+                TokenError::ExtensionBaseMismatch as u32
+            )),
         );
         // success write extension
         let withheld_amount = PodU64::from(u64::MAX);
@@ -2270,6 +3021,120 @@ mod test {
         );
     }
 
+    #[test]
+    fn ambiguous_length_is_only_multisig_len() {
+        assert!(is_ambiguous_length(Multisig::LEN));
+        for len in [
+            0,
+            Mint::LEN,
+            Account::LEN,
+            BASE_ACCOUNT_AND_TYPE_LENGTH,
+            Multisig::LEN - 1,
+            Multisig::LEN + 1,
+            Multisig::LEN + size_of::<ExtensionType>(),
+        ] {
+            assert!(!is_ambiguous_length(len), "{len}");
+        }
+        assert_eq!(
+            adjust_len_for_multisig(Multisig::LEN),
+            Multisig::LEN + size_of::<ExtensionType>()
+        );
+    }
+
+    #[test]
+    fn account_type_from_base_len() {
+        assert_eq!(account_type_from_len(Mint::LEN), AccountType::Mint);
+        assert_eq!(account_type_from_len(Account::LEN), AccountType::Account);
+        assert_eq!(account_type_from_len(Multisig::LEN), AccountType::Multisig);
+
+        // extension accounts are typed by their data, not their length, even
+        // when padded past the multisig length
+        for len in [
+            0,
+            Mint::LEN + 1,
+            BASE_ACCOUNT_AND_TYPE_LENGTH,
+            Multisig::LEN - 1,
+            Multisig::LEN + 1,
+            adjust_len_for_multisig(Multisig::LEN),
+        ] {
+            assert_eq!(
+                account_type_from_len(len),
+                AccountType::Uninitialized,
+                "{len}"
+            );
+        }
+
+        // the multisig variant is never valid as stored account data
+        let mut buffer = vec![0; Multisig::LEN + size_of::<ExtensionType>()];
+        let state =
+            PodStateWithExtensionsMut::<PodAccount>::unpack_uninitialized(&mut buffer).unwrap();
+        *state.base = TEST_POD_ACCOUNT;
+        buffer[Account::LEN] = AccountType::Multisig.into();
+        assert_eq!(
+            PodStateWithExtensions::<PodAccount>::unpack(&buffer),
+            Err(ProgramError::InvalidAccountData),
+        );
+    }
+
+    #[test]
+    fn account_type_consistency() {
+        // base lengths carry no type
+        assert_eq!(validate_account_type_consistency(TEST_MINT_SLICE), Ok(()));
+        assert_eq!(
+            validate_account_type_consistency(TEST_ACCOUNT_SLICE),
+            Ok(())
+        );
+        assert_eq!(
+            validate_account_type_consistency(&[0; Multisig::LEN]),
+            Ok(())
+        );
+
+        // correctly typed extended data
+        assert_eq!(
+            validate_account_type_consistency(MINT_WITH_EXTENSION),
+            Ok(())
+        );
+        assert_eq!(
+            validate_account_type_consistency(ACCOUNT_WITH_EXTENSION),
+            Ok(())
+        );
+
+        // an account's type may be written before its base is initialized
+        let mut buffer = vec![0; BASE_ACCOUNT_AND_TYPE_LENGTH];
+        buffer[BASE_ACCOUNT_LENGTH] = AccountType::Account.into();
+        assert_eq!(validate_account_type_consistency(&buffer), Ok(()));
+
+        // mislabeled extended data
+        let mut buffer = ACCOUNT_WITH_EXTENSION.to_vec();
+        buffer[BASE_ACCOUNT_LENGTH] = AccountType::Mint.into();
+        assert_eq!(
+            validate_account_type_consistency(&buffer),
+            Err(ProgramError::InvalidAccountData)
+        );
+        let mut buffer = MINT_WITH_EXTENSION.to_vec();
+        buffer[BASE_ACCOUNT_LENGTH] = AccountType::Account.into();
+        assert_eq!(
+            validate_account_type_consistency(&buffer),
+            Err(ProgramError::InvalidAccountData)
+        );
+        buffer[BASE_ACCOUNT_LENGTH] = AccountType::Multisig.into();
+        assert_eq!(
+            validate_account_type_consistency(&buffer),
+            Err(ProgramError::InvalidAccountData)
+        );
+        buffer[BASE_ACCOUNT_LENGTH] = 4;
+        assert_eq!(
+            validate_account_type_consistency(&buffer),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // too short to hold a type, but not a base length either
+        assert_eq!(
+            validate_account_type_consistency(&[0; Mint::LEN + 1]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
     #[test]
     fn account_with_multisig_len() {
         let mut buffer = vec![0; Multisig::LEN];
@@ -2597,6 +3462,95 @@ mod test {
         );
     }
 
+    #[test]
+    fn len_for_base_rejects_wrong_extension_types() {
+        assert_eq!(
+            ExtensionType::try_calculate_mint_len(&[ExtensionType::TransferFeeConfig]),
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig])
+        );
+        assert_eq!(
+            ExtensionType::try_calculate_mint_len(&[ExtensionType::TransferFeeAmount]),
+            Err(TokenError::ExtensionBaseMismatch.into())
+        );
+        assert_eq!(
+            ExtensionType::try_calculate_mint_len(&[
+                ExtensionType::MintCloseAuthority,
+                ExtensionType::ImmutableOwner,
+            ]),
+            Err(TokenError::ExtensionBaseMismatch.into())
+        );
+
+        assert_eq!(
+            ExtensionType::try_calculate_token_account_len(&[ExtensionType::TransferFeeAmount]),
+            ExtensionType::try_calculate_account_len::<Account>(&[
+                ExtensionType::TransferFeeAmount
+            ])
+        );
+        assert_eq!(
+            ExtensionType::try_calculate_token_account_len(&[ExtensionType::TransferFeeConfig]),
+            Err(TokenError::ExtensionBaseMismatch.into())
+        );
+
+        // no extensions is valid for either base
+        assert_eq!(ExtensionType::try_calculate_mint_len(&[]), Ok(Mint::LEN));
+        assert_eq!(
+            ExtensionType::try_calculate_token_account_len(&[]),
+            Ok(Account::LEN)
+        );
+    }
+
+    #[test]
+    fn account_len_with_duplicate_extension_types() {
+        assert_eq!(
+            ExtensionType::check_extension_types_unique(&[
+                ExtensionType::MintCloseAuthority,
+                ExtensionType::TransferFeeConfig,
+            ]),
+            Ok(())
+        );
+        assert_eq!(
+            ExtensionType::try_calculate_unique_account_len::<PodMint>(&[
+                ExtensionType::MintCloseAuthority,
+                ExtensionType::TransferFeeConfig,
+            ])
+            .unwrap(),
+            BASE_ACCOUNT_AND_TYPE_LENGTH
+                + add_type_and_length_to_len(pod_get_packed_len::<MintCloseAuthority>())
+                + add_type_and_length_to_len(pod_get_packed_len::<TransferFeeConfig>())
+        );
+
+        assert_eq!(
+            ExtensionType::check_extension_types_unique(&[
+                ExtensionType::TransferFeeConfig,
+                ExtensionType::MintCloseAuthority,
+                ExtensionType::TransferFeeConfig,
+            ]),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            ExtensionType::try_calculate_unique_account_len::<PodMint>(&[
+                ExtensionType::TransferFeeConfig,
+                ExtensionType::TransferFeeConfig,
+            ])
+            .unwrap_err(),
+            ProgramError::InvalidArgument
+        );
+
+        // the lenient version dedupes, as relied on by callers that
+        // concatenate extension lists
+        assert_eq!(
+            ExtensionType::try_calculate_account_len::<PodMint>(&[
+                ExtensionType::TransferFeeConfig,
+                ExtensionType::TransferFeeConfig,
+            ])
+            .unwrap(),
+            ExtensionType::try_calculate_unique_account_len::<PodMint>(&[
+                ExtensionType::TransferFeeConfig,
+            ])
+            .unwrap()
+        );
+    }
+
     #[test]
     fn alloc() {
         let variable_len = VariableLenMintTest { data: vec![1] };
@@ -2640,6 +3594,72 @@ mod test {
         );
     }
 
+    #[test]
+    fn alloc_max_extension_value_len() {
+        let data_len = max_extension_value_len() - size_of::<u64>();
+        let account_size = BASE_ACCOUNT_LENGTH
+            + size_of::<AccountType>()
+            + add_type_and_length_to_len(max_extension_value_len() + 1);
+        let mut buffer = vec![0; account_size];
+        let mut state =
+            PodStateWithExtensionsMut::<PodMint>::unpack_uninitialized(&mut buffer).unwrap();
+
+        // one byte over the limit is rejected even though the buffer fits it
+        let too_long = VariableLenMintTest {
+            data: vec![1; data_len + 1],
+        };
+        assert_eq!(
+            state
+                .init_variable_len_extension(&too_long, false)
+                .unwrap_err(),
+            TokenError::ExtensionValueTooLong.into()
+        );
+
+        // exactly the limit is fine
+        let max_len = VariableLenMintTest {
+            data: vec![1; data_len],
+        };
+        state.init_variable_len_extension(&max_len, false).unwrap();
+        assert_eq!(
+            state.get_variable_len_extension::<VariableLenMintTest>(),
+            Ok(max_len)
+        );
+
+        // and cannot be grown past it
+        assert_eq!(
+            state.realloc_variable_len_extension(&too_long).unwrap_err(),
+            TokenError::ExtensionValueTooLong.into()
+        );
+    }
+
+    #[test]
+    fn extension_types_skip_variable_len_values() {
+        let variable_len = VariableLenMintTest {
+            data: vec![1, 2, 3],
+        };
+        let account_size = BASE_ACCOUNT_LENGTH
+            + size_of::<AccountType>()
+            + add_type_and_length_to_len(variable_len.get_packed_len().unwrap())
+            + add_type_and_length_to_len(size_of::<MintCloseAuthority>())
+            + size_of::<ExtensionType>()
+            + size_of::<Length>();
+        let mut buffer = vec![0; account_size];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut buffer).unwrap();
+        state
+            .init_variable_len_extension(&variable_len, false)
+            .unwrap();
+        state.init_extension::<MintCloseAuthority>(false).unwrap();
+
+        // the trailing zeroed header is read as `Uninitialized` and ends the walk
+        assert_eq!(
+            state.get_extension_types().unwrap(),
+            vec![
+                ExtensionType::VariableLenMintTest,
+                ExtensionType::MintCloseAuthority
+            ]
+        );
+    }
+
     #[test]
     fn realloc() {
         let small_variable_len = VariableLenMintTest {
@@ -2711,6 +3731,76 @@ mod test {
         );
     }
 
+    #[test]
+    fn resize_extension() {
+        let growth = 8;
+        let mint_size = ExtensionType::try_calculate_account_len::<Mint>(&[
+            ExtensionType::MintCloseAuthority,
+            ExtensionType::TransferFeeConfig,
+        ])
+        .unwrap()
+            + growth;
+        let mut buffer = vec![0; mint_size];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut buffer).unwrap();
+        let close_authority =
+            OptionalNonZeroPubkey::try_from(Some(Pubkey::new_from_array([1; 32]))).unwrap();
+        state
+            .init_extension::<MintCloseAuthority>(true)
+            .unwrap()
+            .close_authority = close_authority;
+        *state.init_extension::<TransferFeeConfig>(true).unwrap() = test_transfer_fee_config();
+
+        // grow the first entry, keeping its old value at the front
+        let old_len = size_of::<MintCloseAuthority>();
+        let data = state
+            .resize_extension::<MintCloseAuthority>(old_len + growth)
+            .unwrap();
+        assert_eq!(data.len(), old_len + growth);
+        assert_eq!(
+            &data[..old_len],
+            bytemuck::bytes_of(&MintCloseAuthority { close_authority })
+        );
+        assert_eq!(&data[old_len..], &[0; 8]);
+
+        // the following entry moved intact
+        assert_eq!(
+            state.get_extension::<TransferFeeConfig>().unwrap(),
+            &test_transfer_fee_config()
+        );
+        assert_eq!(
+            state.get_extension::<MintCloseAuthority>(),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // no more room to grow
+        assert_eq!(
+            state.resize_extension::<MintCloseAuthority>(old_len + growth + 1),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // shrink back to the fixed size
+        state
+            .resize_extension::<MintCloseAuthority>(old_len)
+            .unwrap();
+        assert_eq!(
+            state
+                .get_extension::<MintCloseAuthority>()
+                .unwrap()
+                .close_authority,
+            close_authority
+        );
+        assert_eq!(
+            state.get_extension::<TransferFeeConfig>().unwrap(),
+            &test_transfer_fee_config()
+        );
+
+        // missing extension
+        assert_eq!(
+            state.resize_extension::<DefaultAccountState>(1),
+            Err(ProgramError::Custom(TokenError::ExtensionNotFound as u32))
+        );
+    }
+
     #[test]
     fn account_len() {
         let small_variable_len = VariableLenMintTest {
@@ -164,6 +164,14 @@ impl TransferFeeConfig {
         self.get_epoch_fee(epoch)
             .calculate_inverse_fee(post_fee_amount)
     }
+    /// Add fees harvested from an account to the mint's withheld amount
+    pub fn harvest(&mut self, account_withheld: u64) -> ProgramResult {
+        self.withheld_amount = u64::from(self.withheld_amount)
+            .checked_add(account_withheld)
+            .ok_or(TokenError::Overflow)?
+            .into();
+        Ok(())
+    }
 }
 impl Extension for TransferFeeConfig {
     const TYPE: ExtensionType = ExtensionType::TransferFeeConfig;
@@ -187,6 +195,12 @@ impl TransferFeeAmount {
             Err(TokenError::AccountHasWithheldTransferFees.into())
         }
     }
+    /// Clear the withheld amount, returning what was withheld
+    pub fn reset_withheld(&mut self) -> u64 {
+        let withheld_amount = u64::from(self.withheld_amount);
+        self.withheld_amount = 0.into();
+        withheld_amount
+    }
 }
 impl Extension for TransferFeeAmount {
     const TYPE: ExtensionType = ExtensionType::TransferFeeAmount;
@@ -398,6 +412,73 @@ pub(crate) mod test {
         assert_eq!(1, transfer_fee.calculate_pre_fee_amount(1).unwrap());
     }
 
+    #[test]
+    fn calculate_pre_fee_amount_regimes() {
+        let maximum_fee = 100;
+        let transfer_fee = TransferFee {
+            epoch: PodU64::from(0),
+            maximum_fee: PodU64::from(maximum_fee),
+            transfer_fee_basis_points: PodU16::from(1_000),
+        };
+
+        // proportional regime, 10% fee rounded up
+        assert_eq!(10, transfer_fee.calculate_pre_fee_amount(9).unwrap());
+        assert_eq!(12, transfer_fee.calculate_pre_fee_amount(10).unwrap());
+        // rounding boundary, 10 and 11 both net 9, the smaller is chosen
+        assert_eq!(9, transfer_fee.calculate_post_fee_amount(10).unwrap());
+        assert_eq!(9, transfer_fee.calculate_post_fee_amount(11).unwrap());
+        assert_eq!(8, transfer_fee.calculate_post_fee_amount(9).unwrap());
+
+        // implied fee exactly at the maximum
+        assert_eq!(1_000, transfer_fee.calculate_pre_fee_amount(900).unwrap());
+        // implied fee above the maximum adds exactly the maximum
+        assert_eq!(
+            1_000 + maximum_fee,
+            transfer_fee.calculate_pre_fee_amount(1_000).unwrap()
+        );
+        assert_eq!(
+            u64::MAX,
+            transfer_fee
+                .calculate_pre_fee_amount(u64::MAX - maximum_fee)
+                .unwrap()
+        );
+        assert_eq!(
+            None,
+            transfer_fee.calculate_pre_fee_amount(u64::MAX - maximum_fee + 1)
+        );
+
+        // the result is the smallest amount netting the requested amount
+        // across both regimes
+        for post_fee_amount in 1..2_000 {
+            let pre_fee_amount = transfer_fee
+                .calculate_pre_fee_amount(post_fee_amount)
+                .unwrap();
+            assert_eq!(
+                post_fee_amount,
+                transfer_fee
+                    .calculate_post_fee_amount(pre_fee_amount)
+                    .unwrap()
+            );
+            assert!(
+                transfer_fee
+                    .calculate_post_fee_amount(pre_fee_amount - 1)
+                    .unwrap()
+                    < post_fee_amount
+            );
+        }
+
+        // no fee, same amount even at the top of the range
+        let transfer_fee = TransferFee {
+            epoch: PodU64::from(0),
+            maximum_fee: PodU64::from(maximum_fee),
+            transfer_fee_basis_points: PodU16::from(0),
+        };
+        assert_eq!(
+            u64::MAX,
+            transfer_fee.calculate_pre_fee_amount(u64::MAX).unwrap()
+        );
+    }
+
     #[test]
     fn calculate_fee_exact_out_min() {
         let one = u64::try_from(ONE_IN_BASIS_POINTS).unwrap();
@@ -425,6 +506,36 @@ pub(crate) mod test {
         assert_eq!(0, transfer_fee.calculate_inverse_fee(0).unwrap());
     }
 
+    #[test]
+    fn harvest_withheld_amounts() {
+        let mut transfer_fee_config = TransferFeeConfig::default();
+        let mut accounts = [
+            TransferFeeAmount {
+                withheld_amount: PodU64::from(10),
+            },
+            TransferFeeAmount {
+                withheld_amount: PodU64::from(0),
+            },
+            TransferFeeAmount {
+                withheld_amount: PodU64::from(32),
+            },
+        ];
+        for account in accounts.iter_mut() {
+            transfer_fee_config
+                .harvest(account.reset_withheld())
+                .unwrap();
+            assert_eq!(account.closable(), Ok(()));
+        }
+        assert_eq!(transfer_fee_config.withheld_amount, PodU64::from(42));
+
+        // overflow leaves the mint untouched
+        assert_eq!(
+            transfer_fee_config.harvest(u64::MAX),
+            Err(TokenError::Overflow.into())
+        );
+        assert_eq!(transfer_fee_config.withheld_amount, PodU64::from(42));
+    }
+
     proptest! {
         #[test]
         fn round_trip_fee_calculation(
@@ -172,9 +172,7 @@ fn harvest_from_account<'b>(
     let token_account_extension = token_account
         .get_extension_mut::<TransferFeeAmount>()
         .map_err(|_| TokenError::InvalidState)?;
-    let account_withheld_amount = u64::from(token_account_extension.withheld_amount);
-    token_account_extension.withheld_amount = 0.into();
-    Ok(account_withheld_amount)
+    Ok(token_account_extension.reset_withheld())
 }
 
 fn process_harvest_withheld_tokens_to_mint(accounts: &[AccountInfo]) -> ProgramResult {
@@ -188,13 +186,7 @@ fn process_harvest_withheld_tokens_to_mint(accounts: &[AccountInfo]) -> ProgramR
 
     for token_account_info in token_account_infos {
         match harvest_from_account(mint_account_info.key, token_account_info) {
-            Ok(amount) => {
-                let mint_withheld_amount = u64::from(mint_extension.withheld_amount);
-                mint_extension.withheld_amount = mint_withheld_amount
-                    .checked_add(amount)
-                    .ok_or(TokenError::Overflow)?
-                    .into();
-            }
+            Ok(amount) => mint_extension.harvest(amount)?,
             Err(e) => {
                 msg!("Error harvesting from {}: {}", token_account_info.key, e);
             }
@@ -250,8 +242,7 @@ fn process_withdraw_withheld_tokens_from_accounts(
             let token_account_extension = destination_account
                 .get_extension_mut::<TransferFeeAmount>()
                 .map_err(|_| TokenError::InvalidState)?;
-            let account_withheld_amount = u64::from(token_account_extension.withheld_amount);
-            token_account_extension.withheld_amount = 0.into();
+            let account_withheld_amount = token_account_extension.reset_withheld();
             destination_account.base.amount = u64::from(destination_account.base.amount)
                 .checked_add(account_withheld_amount)
                 .ok_or(TokenError::Overflow)?
@@ -2,7 +2,7 @@
 
 use {
     crate::{
-        extension::AccountType,
+        extension::{is_ambiguous_length, AccountType},
         generic_token_account::{is_initialized_account, GenericTokenAccount},
         instruction::MAX_SIGNERS,
     },
@@ -321,7 +321,7 @@ impl GenericTokenAccount for Account {
         // Use spl_token::state::Account::valid_account_data once possible
         account_data.len() == Account::LEN && is_initialized_account(account_data)
             || (account_data.len() > Account::LEN
-                && account_data.len() != Multisig::LEN
+                && !is_ambiguous_length(account_data.len())
                 && ACCOUNTTYPE_ACCOUNT == account_data[Account::LEN]
                 && is_initialized_account(account_data))
     }
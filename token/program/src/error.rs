@@ -80,6 +80,11 @@ pub enum TokenError {
     /// Instruction does not support non-native tokens
     #[error("Instruction does not support non-native tokens")]
     NonNativeNotSupported,
+
+    // 20
+    /// Transfers to incinerator-owned accounts are rejected, use Burn instead
+    #[error("Transfer destination is owned by the incinerator, use Burn instead")]
+    TransferToIncinerator,
 }
 impl From<TokenError> for ProgramError {
     fn from(e: TokenError) -> Self {
@@ -136,6 +141,9 @@ impl PrintProgramError for TokenError {
             TokenError::NonNativeNotSupported => {
                 msg!("Error: Instruction does not support non-native tokens")
             }
+            TokenError::TransferToIncinerator => {
+                msg!("Error: Transfer destination is owned by the incinerator, use Burn instead")
+            }
         }
     }
 }
@@ -9,7 +9,7 @@ use {
         pubkey::Pubkey,
         sysvar,
     },
-    std::{convert::TryInto, mem::size_of},
+    std::{convert::TryInto, fmt, mem::size_of, str::FromStr},
 };
 
 /// Minimum number of multisignature signers (min N)
@@ -579,6 +579,13 @@ impl<'a> TokenInstruction<'a> {
         })
     }
 
+    /// Checks whether a byte buffer unpacks into a
+    /// [`TokenInstruction`](enum.TokenInstruction.html). Unpacking only
+    /// borrows from the buffer, so this never allocates.
+    pub fn is_valid(input: &[u8]) -> bool {
+        TokenInstruction::unpack(input).is_ok()
+    }
+
     /// Packs a [`TokenInstruction`](enum.TokenInstruction.html) into a byte
     /// buffer.
     pub fn pack(&self) -> Vec<u8> {
@@ -773,6 +780,32 @@ impl AuthorityType {
     }
 }
 
+impl fmt::Display for AuthorityType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            AuthorityType::MintTokens => "mint",
+            AuthorityType::FreezeAccount => "freeze",
+            AuthorityType::AccountOwner => "owner",
+            AuthorityType::CloseAccount => "close",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for AuthorityType {
+    type Err = TokenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mint" => Ok(AuthorityType::MintTokens),
+            "freeze" => Ok(AuthorityType::FreezeAccount),
+            "owner" => Ok(AuthorityType::AccountOwner),
+            "close" => Ok(AuthorityType::CloseAccount),
+            _ => Err(TokenError::InvalidInstruction),
+        }
+    }
+}
+
 /// Creates a `InitializeMint` instruction.
 pub fn initialize_mint(
     token_program_id: &Pubkey,
@@ -962,6 +995,34 @@ pub fn initialize_multisig2(
     })
 }
 
+/// A raw token amount, in the smallest unit of its mint, as taken by the
+/// instruction builders. Conversions from a UI amount go through
+/// `from_ui_amount`, which requires the mint's decimals.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TokenAmount(pub u64);
+impl TokenAmount {
+    /// Converts a UI amount to a raw amount, rounding to the nearest unit.
+    /// Negative amounts become 0 and amounts past `u64::MAX` saturate.
+    pub fn from_ui_amount(ui_amount: f64, decimals: u8) -> Self {
+        Self((ui_amount * 10f64.powi(i32::from(decimals))).round() as u64)
+    }
+
+    /// Converts the raw amount to its UI representation
+    pub fn to_ui_amount(self, decimals: u8) -> f64 {
+        crate::amount_to_ui_amount(self.0, decimals)
+    }
+}
+impl From<u64> for TokenAmount {
+    fn from(amount: u64) -> Self {
+        Self(amount)
+    }
+}
+impl From<TokenAmount> for u64 {
+    fn from(amount: TokenAmount) -> Self {
+        amount.0
+    }
+}
+
 /// Creates a `Transfer` instruction.
 pub fn transfer(
     token_program_id: &Pubkey,
@@ -969,10 +1030,13 @@ pub fn transfer(
     destination_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
     signer_pubkeys: &[&Pubkey],
-    amount: u64,
+    amount: impl Into<TokenAmount>,
 ) -> Result<Instruction, ProgramError> {
     check_program_account(token_program_id)?;
-    let data = TokenInstruction::Transfer { amount }.pack();
+    let data = TokenInstruction::Transfer {
+        amount: amount.into().0,
+    }
+    .pack();
 
     let mut accounts = Vec::with_capacity(3 + signer_pubkeys.len());
     accounts.push(AccountMeta::new(*source_pubkey, false));
@@ -1090,10 +1154,13 @@ pub fn mint_to(
     account_pubkey: &Pubkey,
     owner_pubkey: &Pubkey,
     signer_pubkeys: &[&Pubkey],
-    amount: u64,
+    amount: impl Into<TokenAmount>,
 ) -> Result<Instruction, ProgramError> {
     check_program_account(token_program_id)?;
-    let data = TokenInstruction::MintTo { amount }.pack();
+    let data = TokenInstruction::MintTo {
+        amount: amount.into().0,
+    }
+    .pack();
 
     let mut accounts = Vec::with_capacity(3 + signer_pubkeys.len());
     accounts.push(AccountMeta::new(*mint_pubkey, false));
@@ -1120,10 +1187,13 @@ pub fn burn(
     mint_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
     signer_pubkeys: &[&Pubkey],
-    amount: u64,
+    amount: impl Into<TokenAmount>,
 ) -> Result<Instruction, ProgramError> {
     check_program_account(token_program_id)?;
-    let data = TokenInstruction::Burn { amount }.pack();
+    let data = TokenInstruction::Burn {
+        amount: amount.into().0,
+    }
+    .pack();
 
     let mut accounts = Vec::with_capacity(3 + signer_pubkeys.len());
     accounts.push(AccountMeta::new(*account_pubkey, false));
@@ -1172,6 +1242,37 @@ pub fn close_account(
     })
 }
 
+/// Creates a `Burn` instruction for `amount`, followed by a `CloseAccount`
+/// instruction, to empty and close an account in one transaction. The caller
+/// reads the account's balance and passes it as `amount`.
+pub fn burn_and_close(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    amount: u64,
+) -> Result<Vec<Instruction>, ProgramError> {
+    Ok(vec![
+        burn(
+            token_program_id,
+            account_pubkey,
+            mint_pubkey,
+            owner_pubkey,
+            signer_pubkeys,
+            amount,
+        )?,
+        close_account(
+            token_program_id,
+            account_pubkey,
+            destination_pubkey,
+            owner_pubkey,
+            signer_pubkeys,
+        )?,
+    ])
+}
+
 /// Creates a `FreezeAccount` instruction.
 pub fn freeze_account(
     token_program_id: &Pubkey,
@@ -1682,6 +1783,115 @@ mod test {
         assert_eq!(unpacked, check);
     }
 
+    #[test]
+    fn test_initialize_mint_truncated_freeze_authority() {
+        for check in [
+            TokenInstruction::InitializeMint {
+                decimals: 2,
+                mint_authority: Pubkey::new_from_array([2u8; 32]),
+                freeze_authority: COption::Some(Pubkey::new_from_array([3u8; 32])),
+            },
+            TokenInstruction::InitializeMint2 {
+                decimals: 2,
+                mint_authority: Pubkey::new_from_array([2u8; 32]),
+                freeze_authority: COption::Some(Pubkey::new_from_array([3u8; 32])),
+            },
+        ] {
+            let mut packed = check.pack();
+            packed.pop();
+            assert_eq!(
+                TokenInstruction::unpack(&packed),
+                Err(TokenError::InvalidInstruction.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_authority_type_display_from_str() {
+        for (authority_type, name) in [
+            (AuthorityType::MintTokens, "mint"),
+            (AuthorityType::FreezeAccount, "freeze"),
+            (AuthorityType::AccountOwner, "owner"),
+            (AuthorityType::CloseAccount, "close"),
+        ] {
+            assert_eq!(authority_type.to_string(), name);
+            assert_eq!(name.parse::<AuthorityType>(), Ok(authority_type));
+        }
+        assert_eq!(
+            "freezer".parse::<AuthorityType>(),
+            Err(TokenError::InvalidInstruction)
+        );
+    }
+
+    #[test]
+    fn test_token_amount_from_ui_amount() {
+        // whole units only
+        assert_eq!(TokenAmount::from_ui_amount(1.0, 0), TokenAmount(1));
+        assert_eq!(TokenAmount::from_ui_amount(1.4, 0), TokenAmount(1));
+        assert_eq!(TokenAmount::from_ui_amount(1.5, 0), TokenAmount(2));
+
+        // 0.000249 * 10^6 is 248.99999999999997 in floating point, which
+        // truncating would turn into 248
+        assert_eq!(TokenAmount::from_ui_amount(0.000_249, 6), TokenAmount(249));
+        assert_eq!(crate::ui_amount_to_amount(0.000_249, 6), 248);
+        assert_eq!(TokenAmount::from_ui_amount(0.000_000_4, 6), TokenAmount(0));
+        assert_eq!(TokenAmount::from_ui_amount(0.000_000_5, 6), TokenAmount(1));
+
+        assert_eq!(
+            TokenAmount::from_ui_amount(0.000_000_015, 9),
+            TokenAmount(15)
+        );
+        assert_eq!(
+            TokenAmount::from_ui_amount(0.3, 9),
+            TokenAmount(300_000_000)
+        );
+        assert_eq!(
+            TokenAmount::from_ui_amount(1.000_000_001, 9),
+            TokenAmount(1_000_000_001)
+        );
+        assert_eq!(TokenAmount::from_ui_amount(-1.0, 9), TokenAmount(0));
+
+        assert_eq!(TokenAmount(300_000_000).to_ui_amount(9), 0.3);
+
+        // the wire format is unchanged
+        let program_id = crate::id();
+        let key = Pubkey::new_unique();
+        assert_eq!(
+            transfer(&program_id, &key, &key, &key, &[], TokenAmount(5))
+                .unwrap()
+                .data,
+            TokenInstruction::Transfer { amount: 5 }.pack()
+        );
+    }
+
+    #[test]
+    fn test_instruction_is_valid() {
+        for check in [
+            TokenInstruction::InitializeAccount,
+            TokenInstruction::Transfer { amount: 1 },
+            TokenInstruction::TransferChecked {
+                amount: 1,
+                decimals: 2,
+            },
+            TokenInstruction::SetAuthority {
+                authority_type: AuthorityType::AccountOwner,
+                new_authority: COption::None,
+            },
+            TokenInstruction::UiAmountToAmount { ui_amount: "0.1" },
+        ] {
+            assert!(TokenInstruction::is_valid(&check.pack()));
+        }
+
+        // empty, unknown tag, truncated, and invalid enum or utf-8 data
+        assert!(!TokenInstruction::is_valid(&[]));
+        assert!(!TokenInstruction::is_valid(&[25]));
+        assert!(!TokenInstruction::is_valid(&[255, 0, 0]));
+        let transfer = TokenInstruction::Transfer { amount: 1 }.pack();
+        assert!(!TokenInstruction::is_valid(&transfer[..transfer.len() - 1]));
+        assert!(!TokenInstruction::is_valid(&[6, 4, 0]));
+        assert!(!TokenInstruction::is_valid(&[24, 0xff]));
+    }
+
     #[test]
     fn test_instruction_unpack_panic() {
         for i in 0..255u8 {
@@ -1701,5 +1911,30 @@ mod test {
         ) {
             let _no_panic = TokenInstruction::unpack(&data);
         }
+
+        #[test]
+        fn test_instruction_is_valid_set_authority(
+            authority_type in any::<u8>(),
+            option_tag in any::<u8>(),
+            key in prop::collection::vec(any::<u8>(), 0..=32)
+        ) {
+            let mut data = vec![6, authority_type, option_tag];
+            data.extend_from_slice(&key);
+            // only the four authority types exist, and a new authority is
+            // either absent or a full pubkey
+            let expected = authority_type <= 3
+                && (option_tag == 0 || (option_tag == 1 && key.len() == 32));
+            prop_assert_eq!(TokenInstruction::is_valid(&data), expected);
+        }
+
+        #[test]
+        fn test_instruction_is_valid_transfer(
+            amount in prop::collection::vec(any::<u8>(), 0..=8)
+        ) {
+            let mut data = vec![3];
+            data.extend_from_slice(&amount);
+            // any amount is allowed, as long as all eight bytes are present
+            prop_assert_eq!(TokenInstruction::is_valid(&data), amount.len() == 8);
+        }
     }
 }
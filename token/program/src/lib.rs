@@ -16,7 +16,10 @@ mod entrypoint;
 // Export current sdk types for downstream users building with a different sdk
 // version
 pub use solana_program;
-use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+use {
+    error::TokenError,
+    solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey},
+};
 
 /// Convert the UI representation of a token amount (using the decimals field
 /// defined in its mint) to the raw amount
@@ -82,6 +85,28 @@ pub fn try_ui_amount_into_amount(ui_amount: String, decimals: u8) -> Result<u64,
         .map_err(|_| ProgramError::InvalidArgument)
 }
 
+/// Sum a batch of transfer amounts, failing if the total overflows a `u64`
+pub fn sum_amounts(amounts: &[u64]) -> Result<u64, ProgramError> {
+    amounts.iter().try_fold(0u64, |total, amount| {
+        total
+            .checked_add(*amount)
+            .ok_or_else(|| TokenError::Overflow.into())
+    })
+}
+
+/// Compute the net change to a mint's supply from a batch of `MintTo` and
+/// `Burn` operations, given as `(is_mint, amount)` pairs
+pub fn net_supply_delta(ops: &[(bool, u64)]) -> Result<i128, ProgramError> {
+    ops.iter().try_fold(0i128, |delta, &(is_mint, amount)| {
+        if is_mint {
+            delta.checked_add(i128::from(amount))
+        } else {
+            delta.checked_sub(i128::from(amount))
+        }
+        .ok_or_else(|| TokenError::Overflow.into())
+    })
+}
+
 solana_program::declare_id!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
 
 /// Checks that the supplied program ID is the correct one for SPL-token
@@ -4,7 +4,7 @@ use {
     crate::{
         amount_to_ui_amount_string_trimmed,
         error::TokenError,
-        instruction::{is_valid_signer_index, AuthorityType, TokenInstruction, MAX_SIGNERS},
+        instruction::{is_valid_signer_index, AuthorityType, TokenInstruction},
         state::{Account, AccountState, Mint, Multisig},
         try_ui_amount_into_amount,
     },
@@ -126,7 +126,7 @@ impl Processor {
         account.delegated_amount = 0;
         account.state = AccountState::Initialized;
         if is_native_mint {
-            let rent_exempt_reserve = rent.minimum_balance(new_account_info_data_len);
+            let rent_exempt_reserve = Account::rent_exempt_reserve(&rent);
             account.is_native = COption::Some(rent_exempt_reserve);
             account.amount = new_account_info
                 .lamports()
@@ -257,6 +257,10 @@ impl Processor {
         if !Self::cmp_pubkeys(&source_account.mint, &destination_account.mint) {
             return Err(TokenError::MintMismatch.into());
         }
+        #[cfg(feature = "reject-incinerator-transfers")]
+        if solana_program::incinerator::check_id(&destination_account.owner) {
+            return Err(TokenError::TransferToIncinerator.into());
+        }
 
         if let Some((mint_info, expected_decimals)) = expected_mint_info {
             if !Self::cmp_pubkeys(mint_info.key, &source_account.mint) {
@@ -280,9 +284,7 @@ impl Processor {
                     authority_info,
                     account_info_iter.as_slice(),
                 )?;
-                if source_account.delegated_amount < amount {
-                    return Err(TokenError::InsufficientFunds.into());
-                }
+                source_account.check_delegate_amount(authority_info.key, amount)?;
                 if !self_transfer {
                     source_account.delegated_amount = source_account
                         .delegated_amount
@@ -629,9 +631,7 @@ impl Processor {
                         account_info_iter.as_slice(),
                     )?;
 
-                    if source_account.delegated_amount < amount {
-                        return Err(TokenError::InsufficientFunds.into());
-                    }
+                    source_account.check_delegate_amount(authority_info.key, amount)?;
                     source_account.delegated_amount = source_account
                         .delegated_amount
                         .checked_sub(amount)
@@ -723,7 +723,12 @@ impl Processor {
         let authority_info = next_account_info(account_info_iter)?;
 
         let mut source_account = Account::unpack(&source_account_info.data.borrow())?;
-        if freeze && source_account.is_frozen() || !freeze && !source_account.is_frozen() {
+        let new_state = if freeze {
+            AccountState::Frozen
+        } else {
+            AccountState::Initialized
+        };
+        if !source_account.state.can_transition(new_state) {
             return Err(TokenError::InvalidState.into());
         }
         if source_account.is_native() {
@@ -744,11 +749,7 @@ impl Processor {
             COption::None => Err(TokenError::MintCannotFreeze.into()),
         }?;
 
-        source_account.state = if freeze {
-            AccountState::Frozen
-        } else {
-            AccountState::Initialized
-        };
+        source_account.state = new_state;
 
         Account::pack(source_account, &mut source_account_info.data.borrow_mut())?;
 
@@ -995,28 +996,50 @@ impl Processor {
             && owner_account_info.data_len() == Multisig::get_packed_len()
         {
             let multisig = Multisig::unpack(&owner_account_info.data.borrow())?;
-            let mut num_signers = 0;
-            let mut matched = [false; MAX_SIGNERS];
-            for signer in signers.iter() {
-                for (position, key) in multisig.signers[0..multisig.n as usize].iter().enumerate() {
-                    if Self::cmp_pubkeys(key, signer.key) && !matched[position] {
-                        if !signer.is_signer {
-                            return Err(ProgramError::MissingRequiredSignature);
-                        }
-                        matched[position] = true;
-                        num_signers += 1;
+            let signer_keys = &multisig.signers[0..multisig.n as usize];
+            // the first account passed in for each multisig signer must sign
+            for key in signer_keys.iter() {
+                if let Some(signer) = signers
+                    .iter()
+                    .find(|signer| Self::cmp_pubkeys(key, signer.key))
+                {
+                    if !signer.is_signer {
+                        return Err(ProgramError::MissingRequiredSignature);
                     }
                 }
             }
-            if num_signers < multisig.m {
-                return Err(ProgramError::MissingRequiredSignature);
-            }
-            return Ok(());
+            let provided_signers = signers
+                .iter()
+                .filter(|signer| signer.is_signer)
+                .map(|signer| signer.key)
+                .collect::<Vec<_>>();
+            return Self::validate_multisig_signers(multisig.m, signer_keys, &provided_signers);
         } else if !owner_account_info.is_signer {
             return Err(ProgramError::MissingRequiredSignature);
         }
         Ok(())
     }
+
+    /// Validates that at least `m` of the multisig `signer_keys` are among the
+    /// `provided_signers`, counting each multisig signer at most once
+    pub fn validate_multisig_signers(
+        m: u8,
+        signer_keys: &[Pubkey],
+        provided_signers: &[&Pubkey],
+    ) -> ProgramResult {
+        let num_signers = signer_keys
+            .iter()
+            .filter(|key| {
+                provided_signers
+                    .iter()
+                    .any(|signer| Self::cmp_pubkeys(key, signer))
+            })
+            .count();
+        if num_signers < m as usize {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        Ok(())
+    }
 }
 
 /// Helper function to mostly delete an account in a test environment.  We could
@@ -4621,6 +4644,108 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "reject-incinerator-transfers")]
+    fn test_transfer_to_incinerator_rejected() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let incinerator_account_key = Pubkey::new_unique();
+        let mut incinerator_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = SolanaAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+
+        do_process_instruction(
+            initialize_mint2(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_account3(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![&mut account_account, &mut mint_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_account3(
+                &program_id,
+                &incinerator_account_key,
+                &mint_key,
+                &solana_program::incinerator::id(),
+            )
+            .unwrap(),
+            vec![&mut incinerator_account, &mut mint_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        assert_eq!(
+            Err(TokenError::TransferToIncinerator.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &incinerator_account_key,
+                    &owner_key,
+                    &[],
+                    500,
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut incinerator_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+        assert_eq!(
+            Err(TokenError::TransferToIncinerator.into()),
+            do_process_instruction(
+                transfer_checked(
+                    &program_id,
+                    &account_key,
+                    &mint_key,
+                    &incinerator_account_key,
+                    &owner_key,
+                    &[],
+                    500,
+                    2,
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut incinerator_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // burning is unaffected
+        do_process_instruction(
+            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 500).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
+        )
+        .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 500);
+    }
+
+    #[test]
+    #[cfg(not(feature = "reject-incinerator-transfers"))]
     fn test_burn_and_close_system_and_incinerator_tokens() {
         let program_id = crate::id();
         let account_key = Pubkey::new_unique();
@@ -5292,6 +5417,54 @@ mod tests {
         .unwrap();
     }
 
+    #[test]
+    fn test_validate_multisig_signers() {
+        let signer_keys = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let outsider = Pubkey::new_unique();
+
+        // exactly m signers
+        assert_eq!(
+            Processor::validate_multisig_signers(
+                2,
+                &signer_keys,
+                &[&signer_keys[0], &signer_keys[2]]
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            Processor::validate_multisig_signers(
+                2,
+                &signer_keys,
+                &[&outsider, &signer_keys[1], &signer_keys[0]]
+            ),
+            Ok(())
+        );
+
+        // fewer than m signers
+        assert_eq!(
+            Processor::validate_multisig_signers(2, &signer_keys, &[&signer_keys[1], &outsider]),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            Processor::validate_multisig_signers(1, &signer_keys, &[]),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        // a duplicated signer only counts once
+        assert_eq!(
+            Processor::validate_multisig_signers(
+                2,
+                &signer_keys,
+                &[&signer_keys[1], &signer_keys[1]]
+            ),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+    }
+
     #[test]
     fn test_validate_owner() {
         let program_id = crate::id();
@@ -5588,6 +5761,85 @@ mod tests {
         assert_eq!(*to_close_account_info.data.borrow(), &[0u8; Account::LEN]);
     }
 
+    #[test]
+    fn test_burn_and_close() {
+        let program_id = crate::id();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let destination_key = Pubkey::new_unique();
+        let mut destination_account = SolanaAccount::default();
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = SolanaAccount::default();
+        let mut rent_sysvar = rent_sysvar();
+
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 42).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        let amount = Account::unpack(&account_account.data).unwrap().amount;
+        let instructions = burn_and_close(
+            &program_id,
+            &account_key,
+            &mint_key,
+            &destination_key,
+            &owner_key,
+            &[],
+            amount,
+        )
+        .unwrap();
+        assert_eq!(instructions.len(), 2);
+        let mut instructions = instructions.into_iter();
+
+        // burn the full balance
+        do_process_instruction(
+            instructions.next().unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
+        )
+        .unwrap();
+        let account = Account::unpack(&account_account.data).unwrap();
+        assert_eq!(account.amount, 0);
+        let mint = Mint::unpack(&mint_account.data).unwrap();
+        assert_eq!(mint.supply, 0);
+
+        // close the emptied account
+        do_process_instruction(
+            instructions.next().unwrap(),
+            vec![
+                &mut account_account,
+                &mut destination_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+        assert_eq!(account_account.lamports, 0);
+        assert_eq!(destination_account.lamports, account_minimum_balance());
+        assert!(account_account.data.iter().all(|&byte| byte == 0));
+    }
+
     #[test]
     fn test_close_account() {
         let program_id = crate::id();
@@ -7023,4 +7275,38 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_sum_amounts() {
+        assert_eq!(crate::sum_amounts(&[]), Ok(0));
+        assert_eq!(crate::sum_amounts(&[1, 20, 300]), Ok(321));
+        assert_eq!(crate::sum_amounts(&[u64::MAX - 1, 1]), Ok(u64::MAX));
+        assert_eq!(
+            crate::sum_amounts(&[u64::MAX, 1]),
+            Err(TokenError::Overflow.into())
+        );
+    }
+
+    #[test]
+    fn test_net_supply_delta() {
+        assert_eq!(crate::net_supply_delta(&[]), Ok(0));
+
+        // mints only
+        assert_eq!(
+            crate::net_supply_delta(&[(true, 10), (true, u64::MAX)]),
+            Ok(i128::from(u64::MAX) + 10)
+        );
+
+        // burns only
+        assert_eq!(
+            crate::net_supply_delta(&[(false, 7), (false, u64::MAX)]),
+            Ok(-i128::from(u64::MAX) - 7)
+        );
+
+        // burning more than is minted nets negative
+        assert_eq!(
+            crate::net_supply_delta(&[(true, 100), (false, 30), (true, 5), (false, 125)]),
+            Ok(-50)
+        );
+    }
 }
@@ -1,7 +1,7 @@
 //! State transition types
 
 use {
-    crate::instruction::MAX_SIGNERS,
+    crate::{error::TokenError, instruction::MAX_SIGNERS, processor::Processor},
     arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
     num_enum::TryFromPrimitive,
     solana_program::{
@@ -9,6 +9,7 @@ use {
         program_option::COption,
         program_pack::{IsInitialized, Pack, Sealed},
         pubkey::{Pubkey, PUBKEY_BYTES},
+        rent::Rent,
     },
 };
 
@@ -30,6 +31,28 @@ pub struct Mint {
     /// Optional authority to freeze token accounts.
     pub freeze_authority: COption<Pubkey>,
 }
+impl Mint {
+    /// Checks if the mint has an authority able to freeze token accounts
+    pub fn has_freeze_authority(&self) -> bool {
+        self.freeze_authority.is_some()
+    }
+}
+#[cfg(any(test, feature = "test-utils"))]
+impl Mint {
+    /// Packed state of a freshly-initialized mint, for test fixtures
+    pub fn new_initialized_packed(mint_authority: Pubkey, supply: u64, decimals: u8) -> Vec<u8> {
+        let mint = Mint {
+            mint_authority: COption::Some(mint_authority),
+            supply,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        let mut data = vec![0; Mint::LEN];
+        Mint::pack(mint, &mut data).unwrap();
+        data
+    }
+}
 impl Sealed for Mint {}
 impl IsInitialized for Mint {
     fn is_initialized(&self) -> bool {
@@ -123,6 +146,44 @@ impl Account {
         solana_program::system_program::check_id(&self.owner)
             || solana_program::incinerator::check_id(&self.owner)
     }
+    /// Lamports a native account must keep to stay rent-exempt, which are not
+    /// counted in its token amount
+    pub fn rent_exempt_reserve(rent: &Rent) -> u64 {
+        rent.minimum_balance(Self::LEN)
+    }
+    /// Checks that `delegate` is the account's delegate and is approved to
+    /// move at least `amount` tokens
+    pub fn check_delegate_amount(
+        &self,
+        delegate: &Pubkey,
+        amount: u64,
+    ) -> Result<(), ProgramError> {
+        match self.delegate {
+            COption::Some(ref account_delegate)
+                if Processor::cmp_pubkeys(account_delegate, delegate)
+                    && self.delegated_amount >= amount =>
+            {
+                Ok(())
+            }
+            _ => Err(TokenError::InsufficientFunds.into()),
+        }
+    }
+}
+#[cfg(any(test, feature = "test-utils"))]
+impl Account {
+    /// Packed state of a freshly-initialized account, for test fixtures
+    pub fn new_initialized_packed(mint: Pubkey, owner: Pubkey, amount: u64) -> Vec<u8> {
+        let account = Account {
+            mint,
+            owner,
+            amount,
+            state: AccountState::Initialized,
+            ..Account::default()
+        };
+        let mut data = vec![0; Account::LEN];
+        Account::pack(account, &mut data).unwrap();
+        data
+    }
 }
 impl Sealed for Account {}
 impl IsInitialized for Account {
@@ -196,6 +257,19 @@ pub enum AccountState {
     /// this account.
     Frozen,
 }
+impl AccountState {
+    /// Checks if an account may move from this state to `to`. Accounts are
+    /// initialized thawed, and may then be frozen and thawed by the mint
+    /// freeze authority.
+    pub fn can_transition(self, to: AccountState) -> bool {
+        matches!(
+            (self, to),
+            (AccountState::Uninitialized, AccountState::Initialized)
+                | (AccountState::Initialized, AccountState::Frozen)
+                | (AccountState::Frozen, AccountState::Initialized)
+        )
+    }
+}
 
 /// Multisignature data.
 #[repr(C)]
@@ -374,12 +448,90 @@ mod tests {
         assert_eq!(mint, ProgramError::InvalidAccountData);
     }
 
+    #[test]
+    fn test_mint_has_freeze_authority() {
+        let mint = Mint::default();
+        assert!(!mint.has_freeze_authority());
+
+        let mint = Mint {
+            freeze_authority: COption::Some(Pubkey::new_from_array([1; 32])),
+            ..Mint::default()
+        };
+        assert!(mint.has_freeze_authority());
+    }
+
     #[test]
     fn test_account_state() {
         let account_state = AccountState::default();
         assert_eq!(account_state, AccountState::Uninitialized);
     }
 
+    #[test]
+    fn test_account_rent_exempt_reserve() {
+        let rent = Rent::default();
+        let reserve = Account::rent_exempt_reserve(&rent);
+        assert_eq!(reserve, rent.minimum_balance(Account::LEN));
+        assert!(rent.is_exempt(reserve, Account::LEN));
+        assert!(!rent.is_exempt(reserve - 1, Account::LEN));
+    }
+
+    #[test]
+    fn test_account_state_transitions() {
+        use AccountState::*;
+        let states = [Uninitialized, Initialized, Frozen];
+        let valid = [
+            (Uninitialized, Initialized),
+            (Initialized, Frozen),
+            (Frozen, Initialized),
+        ];
+        for from in states {
+            for to in states {
+                assert_eq!(
+                    from.can_transition(to),
+                    valid.contains(&(from, to)),
+                    "{:?} -> {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_account_check_delegate_amount() {
+        let delegate = Pubkey::new_from_array([1; 32]);
+        let account = Account {
+            delegate: COption::Some(delegate),
+            delegated_amount: 100,
+            ..Account::default()
+        };
+
+        // exact amount
+        assert_eq!(account.check_delegate_amount(&delegate, 100), Ok(()));
+        assert_eq!(account.check_delegate_amount(&delegate, 0), Ok(()));
+
+        // over amount
+        assert_eq!(
+            account.check_delegate_amount(&delegate, 101),
+            Err(TokenError::InsufficientFunds.into())
+        );
+
+        // wrong delegate
+        let other = Pubkey::new_from_array([2; 32]);
+        assert_eq!(
+            account.check_delegate_amount(&other, 1),
+            Err(TokenError::InsufficientFunds.into())
+        );
+        let account = Account {
+            delegate: COption::None,
+            ..account
+        };
+        assert_eq!(
+            account.check_delegate_amount(&delegate, 0),
+            Err(TokenError::InsufficientFunds.into())
+        );
+    }
+
     #[test]
     fn test_multisig_unpack_from_slice() {
         let src: [u8; 355] = [0; 355];
@@ -489,4 +641,35 @@ mod tests {
         let result = Account::unpack_account_mint(&src);
         assert_eq!(result, Option::None);
     }
+
+    #[test]
+    fn test_new_initialized_packed() {
+        let mint_authority = Pubkey::new_from_array([1; 32]);
+        let data = Mint::new_initialized_packed(mint_authority, 42, 6);
+        assert_eq!(
+            Mint::unpack(&data).unwrap(),
+            Mint {
+                mint_authority: COption::Some(mint_authority),
+                supply: 42,
+                decimals: 6,
+                is_initialized: true,
+                freeze_authority: COption::None,
+            }
+        );
+
+        let mint = Pubkey::new_from_array([2; 32]);
+        let owner = Pubkey::new_from_array([3; 32]);
+        let data = Account::new_initialized_packed(mint, owner, 7);
+        assert_eq!(
+            Account::unpack(&data).unwrap(),
+            Account {
+                mint,
+                owner,
+                amount: 7,
+                state: AccountState::Initialized,
+                ..Account::default()
+            }
+        );
+        assert!(is_initialized_account(&data));
+    }
 }